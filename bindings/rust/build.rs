@@ -0,0 +1,18 @@
+// Link the core library built by CMake. Set UMSBB_LIB_DIR to the directory
+// holding it; by default the repository's `build` directory is searched.
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=UMSBB_LIB_DIR");
+
+    let lib_dir = env::var_os("UMSBB_LIB_DIR").map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("../../build")
+    });
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    println!("cargo:rustc-link-lib=universal_multi_segmented_bi_buffer_bus");
+
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        println!("cargo:rustc-link-lib=pthread");
+    }
+}
@@ -0,0 +1,21 @@
+//! Direct usage example
+
+use umsbb_direct::DirectUniversalBus;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let bus = DirectUniversalBus::new(1024 * 1024, 0, true, true)?;
+    
+    println!("GPU Info: {:?}", bus.get_gpu_info());
+    println!("Scaling Status: {:?}", bus.get_scaling_status());
+    
+    // Send test data
+    bus.send("Hello from Rust!", 1)?;
+    bus.send([1, 2, 3, 4, 5], 2)?;
+    
+    // Receive data
+    while let Some(data) = bus.receive() {
+        println!("Received: {:?}", String::from_utf8_lossy(&data));
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! End-to-end delivery receipts for individual messages
+//!
+//! An acked message is framed as:
+//!
+//! ```text
+//! ["UMAK"][u64 LE correlation id][payload]
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{BusError, DirectUniversalBus};
+
+const ACK_MAGIC: &[u8; 4] = b"UMAK";
+const ACK_HEADER_LEN: usize = 12;
+
+/// Correlates a message sent with `send_acked` with its receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AckToken(pub u64);
+
+/// A received message, with the token to acknowledge it if it asked for one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AckedMessage {
+    pub payload: Vec<u8>,
+    pub token: Option<AckToken>,
+}
+
+impl AckedMessage {
+    // Split off the ack header; messages without one pass through untouched
+    fn parse(mut message: Vec<u8>) -> Self {
+        let token = message
+            .split_first_chunk::<ACK_HEADER_LEN>()
+            .filter(|(header, _)| header.starts_with(ACK_MAGIC))
+            .map(|(header, _)| AckToken(u64::from_le_bytes(header[4..].try_into().unwrap())));
+        if token.is_some() {
+            message.drain(..ACK_HEADER_LEN);
+        }
+        AckedMessage { payload: message, token }
+    }
+}
+
+// Outstanding tokens mapped to whether they have been acknowledged
+#[derive(Default)]
+pub(crate) struct AckRegistry {
+    next_id: AtomicU64,
+    outstanding: Mutex<HashMap<u64, bool>>,
+    acked: Condvar,
+}
+
+impl AckRegistry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, bool>> {
+        self.outstanding.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl DirectUniversalBus {
+    /// Send a message whose consumer must confirm it
+    /// 
+    /// The payload is prefixed with a correlation id. Consumers read it with
+    /// [`DirectUniversalBus::receive_acked`] and confirm with
+    /// [`DirectUniversalBus::ack`]; the producer waits for that with
+    /// [`DirectUniversalBus::wait_ack`].
+    /// 
+    /// # Example
+    /// ```rust
+    /// let token = bus.send_acked(b"transfer #42", 1)?;
+    /// if !bus.wait_ack(token, Duration::from_secs(5)) {
+    ///     eprintln!("transfer #42 was not confirmed");
+    /// }
+    /// ```
+    pub fn send_acked(&self, data: &[u8], type_id: u32) -> Result<AckToken, BusError> {
+        let token = AckToken(self.acks.next_id.fetch_add(1, Ordering::Relaxed));
+
+        let mut frame = Vec::with_capacity(ACK_HEADER_LEN + data.len());
+        frame.extend_from_slice(ACK_MAGIC);
+        frame.extend_from_slice(&token.0.to_le_bytes());
+        frame.extend_from_slice(data);
+
+        // Registered first so an ack racing the send isn't lost
+        self.acks.lock().insert(token.0, false);
+        if let Err(e) = self.send(frame, type_id) {
+            self.acks.lock().remove(&token.0);
+            return Err(e);
+        }
+        Ok(token)
+    }
+
+    /// Receive a message, separating any acknowledgment token
+    pub fn receive_acked(&self) -> Option<AckedMessage> {
+        self.receive().map(AckedMessage::parse)
+    }
+
+    /// Confirm that `message` has been consumed
+    /// 
+    /// A no-op for messages that didn't request an ack, or whose producer
+    /// has already stopped waiting.
+    pub fn ack(&self, message: &AckedMessage) {
+        let Some(token) = message.token else {
+            return;
+        };
+        if let Some(acked) = self.acks.lock().get_mut(&token.0) {
+            *acked = true;
+            self.acks.acked.notify_all();
+        }
+    }
+
+    /// Block until `token` is acknowledged or `timeout` passes
+    /// 
+    /// Returns whether the ack arrived. Either way the token is retired, so
+    /// each token can be waited on once.
+    pub fn wait_ack(&self, token: AckToken, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut outstanding = self.acks.lock();
+        loop {
+            match outstanding.get(&token.0) {
+                Some(true) => break,
+                Some(false) => {}
+                None => return false,
+            }
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                break;
+            };
+            outstanding = self
+                .acks
+                .acked
+                .wait_timeout(outstanding, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        outstanding.remove(&token.0).unwrap_or(false)
+    }
+}
@@ -0,0 +1,106 @@
+//! Out-of-band storage for payloads too large for the message ring
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::drops::DropTracker;
+use crate::DropReason;
+
+// Marks a message as a blob reference: magic followed by the u64 LE id
+const BLOB_REF_MAGIC: &[u8; 4] = b"UMBL";
+const BLOB_REF_LEN: usize = 12;
+
+/// Default time a blob is kept if nobody retrieves it
+pub const DEFAULT_BLOB_TTL: Duration = Duration::from_secs(60);
+
+/// Identifier of a blob sent with `DirectUniversalBus::send_blob`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlobId(pub u64);
+
+impl BlobId {
+    /// Parse a received message as a blob reference
+    ///
+    /// Returns `None` for ordinary messages.
+    pub fn from_message(message: &[u8]) -> Option<BlobId> {
+        let (magic, id) = message.split_first_chunk::<4>()?;
+        if magic != BLOB_REF_MAGIC || id.len() != BLOB_REF_LEN - 4 {
+            return None;
+        }
+        Some(BlobId(u64::from_le_bytes(id.try_into().ok()?)))
+    }
+
+    pub(crate) fn to_message(self) -> [u8; BLOB_REF_LEN] {
+        let mut message = [0u8; BLOB_REF_LEN];
+        message[..4].copy_from_slice(BLOB_REF_MAGIC);
+        message[4..].copy_from_slice(&self.0.to_le_bytes());
+        message
+    }
+}
+
+// Blobs keyed by id, each stamped with its expiry
+pub(crate) struct BlobStore {
+    next_id: AtomicU64,
+    ttl: Duration,
+    blobs: Mutex<HashMap<BlobId, (Vec<u8>, Instant)>>,
+}
+
+impl BlobStore {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        BlobStore {
+            next_id: AtomicU64::new(0),
+            ttl,
+            blobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    pub(crate) fn insert(&self, data: &[u8], drops: &DropTracker) -> BlobId {
+        let id = BlobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let expires = Instant::now() + self.ttl;
+        let mut blobs = self.blobs.lock().unwrap_or_else(|e| e.into_inner());
+        let expired = Self::purge_expired(&mut blobs);
+        blobs.insert(id, (data.to_vec(), expires));
+        drop(blobs);
+        Self::report_expired(expired, drops);
+        id
+    }
+
+    pub(crate) fn remove(&self, id: BlobId, drops: &DropTracker) -> Option<Vec<u8>> {
+        let mut blobs = self.blobs.lock().unwrap_or_else(|e| e.into_inner());
+        let expired = Self::purge_expired(&mut blobs);
+        let blob = blobs.remove(&id).map(|(data, _)| data);
+        drop(blobs);
+        Self::report_expired(expired, drops);
+        blob
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.blobs.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    // Remove expired blobs, returning their payloads
+    fn purge_expired(blobs: &mut HashMap<BlobId, (Vec<u8>, Instant)>) -> Vec<Vec<u8>> {
+        let now = Instant::now();
+        let expired: Vec<BlobId> = blobs
+            .iter()
+            .filter(|(_, (_, expires))| *expires <= now)
+            .map(|(&id, _)| id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| blobs.remove(&id).map(|(data, _)| data))
+            .collect()
+    }
+
+    // Report outside the store lock so the drop hook may use the store
+    fn report_expired(expired: Vec<Vec<u8>>, drops: &DropTracker) {
+        for data in expired {
+            drops.record(&data, DropReason::Expired);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! All-or-nothing capacity reservations for message batches
+
+use crate::{BusError, DirectUniversalBus};
+
+/// Message slots held by [`DirectUniversalBus::reserve_capacity`]
+///
+/// Sends through the guard consume reserved slots and are never refused
+/// for lack of message capacity. Unused slots are released on drop.
+pub struct CapacityGuard<'a> {
+    bus: &'a DirectUniversalBus,
+    remaining: u32,
+}
+
+impl CapacityGuard<'_> {
+    /// Send using one reserved slot
+    ///
+    /// # Errors
+    /// * `BufferFull` - Every reserved slot has been used, or the core
+    ///   rejected the message (reservations count messages, not bytes)
+    /// * `Validation` - The validator registered for `type_id` rejected
+    ///   the message; the slot stays reserved
+    /// * `Duplicate` - Dedup is set to reject and the message repeats a
+    ///   recent one; the slot stays reserved, as it does when dedup
+    ///   silently drops the message
+    pub fn send<T: AsRef<[u8]>>(&mut self, data: T, type_id: u32) -> Result<(), BusError> {
+        if self.remaining == 0 {
+            return Err(BusError::BufferFull);
+        }
+        let data = data.as_ref();
+        self.bus.validate(data, type_id)?;
+        // Hand the slot over to the pending message in one step, so other
+        // senders never count it twice
+        let mut reserved = self.bus.lock_reserved();
+        let mut queued = false;
+        self.bus.send_deduplicated(data, self.bus.dedup_key(data, type_id), |data| {
+            self.bus.send_bytes(data, type_id)?;
+            queued = true;
+            Ok(())
+        })?;
+        if queued {
+            *reserved -= 1;
+            self.remaining -= 1;
+        }
+        Ok(())
+    }
+
+    /// Reserved slots not yet used
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+}
+
+impl Drop for CapacityGuard<'_> {
+    fn drop(&mut self) {
+        *self.bus.lock_reserved() -= self.remaining;
+    }
+}
+
+impl DirectUniversalBus {
+    /// Cap the number of pending messages at `limit`
+    /// 
+    /// Sends that would exceed it fail with `BufferFull` before reaching
+    /// the core. The cap is what [`DirectUniversalBus::reserve_capacity`]
+    /// reserves against; the core's byte capacity still applies on top.
+    pub fn with_message_capacity(mut self, limit: u32) -> Self {
+        self.message_capacity = Some(limit);
+        self
+    }
+
+    /// Reserve room for `count` messages so a batch fits completely
+    /// 
+    /// Fails with `BufferFull` unless pending messages plus existing
+    /// reservations leave at least `count` free slots. While the guard
+    /// lives, other senders can't use the reserved slots. Without a
+    /// message capacity every reservation succeeds.
+    /// 
+    /// # Example
+    /// ```rust
+    /// let mut guard = bus.reserve_capacity(batch.len() as u32)?;
+    /// for message in &batch {
+    ///     guard.send(message, 1)?;
+    /// }
+    /// ```
+    pub fn reserve_capacity(&self, count: u32) -> Result<CapacityGuard<'_>, BusError> {
+        let mut reserved = self.lock_reserved();
+        if let Some(limit) = self.message_capacity {
+            let in_use = self.len().saturating_add(*reserved);
+            if limit.saturating_sub(in_use) < count {
+                return Err(BusError::BufferFull);
+            }
+        }
+        *reserved += count;
+        Ok(CapacityGuard {
+            bus: self,
+            remaining: count,
+        })
+    }
+
+    // Send if a slot is free beyond pending messages and reservations. The
+    // reservation lock is held until the message is queued, so concurrent
+    // senders can't all pass the check for the last slot.
+    pub(crate) fn send_unreserved(&self, bytes: &[u8], type_id: u32) -> Result<(), BusError> {
+        self.with_free_slot(|| self.send_bytes(bytes, type_id))
+    }
+
+    // Run `send` under the same check, for sends that don't go through
+    // `send_bytes`
+    pub(crate) fn with_free_slot(
+        &self,
+        send: impl FnOnce() -> Result<(), BusError>,
+    ) -> Result<(), BusError> {
+        let Some(limit) = self.message_capacity else {
+            return send();
+        };
+        let reserved = self.lock_reserved();
+        if self.len().saturating_add(*reserved) >= limit {
+            return Err(BusError::BufferFull);
+        }
+        send()
+    }
+
+    fn lock_reserved(&self) -> std::sync::MutexGuard<'_, u32> {
+        self.reserved.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,168 @@
+//! Write coalescing for workloads dominated by tiny messages
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{frame, BusError, DirectUniversalBus, DropReason};
+
+#[derive(Default)]
+struct Pending {
+    batch: Vec<u8>,
+    type_id: u32,
+    started: Option<Instant>,
+}
+
+/// Batches small sends into framed super-messages
+///
+/// Messages are buffered locally and submitted as one bus message once the
+/// batch reaches `max_batch_bytes` or the oldest buffered message is older
+/// than `max_delay`. The delay is only checked on `send`, so call
+/// [`CoalescingBus::flush`] when a producer goes quiet. The batch is
+/// submitted with the type id of its first message.
+///
+/// The receive side unpacks batches and hands back individual messages,
+/// so both ends of the bus must use `CoalescingBus`.
+///
+/// Dropping the bus flushes any buffered batch first; see
+/// [`CoalescingBus::with_flush_on_drop`] to skip this. Messages that are
+/// not submitted are reported as [`DropReason::Unflushed`].
+pub struct CoalescingBus {
+    bus: DirectUniversalBus,
+    max_batch_bytes: usize,
+    max_delay: Duration,
+    flush_on_drop: bool,
+    pending: Mutex<Pending>,
+    unpacked: Mutex<VecDeque<(u32, Vec<u8>)>>,
+}
+
+impl CoalescingBus {
+    /// Wrap `bus`, flushing once a batch holds `max_batch_bytes` or has
+    /// waited `max_delay`
+    ///
+    /// `max_batch_bytes` is capped at the bus's largest message size.
+    pub fn new(bus: DirectUniversalBus, max_batch_bytes: usize, max_delay: Duration) -> Self {
+        let max_batch_bytes = max_batch_bytes.min(bus.max_message_size());
+        CoalescingBus {
+            bus,
+            max_batch_bytes,
+            max_delay,
+            flush_on_drop: true,
+            pending: Mutex::new(Pending::default()),
+            unpacked: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Whether dropping the bus submits the buffered batch (default true)
+    ///
+    /// Disable for fast teardown, e.g. when shutting down after an error,
+    /// where losing the buffered messages is acceptable.
+    pub fn with_flush_on_drop(mut self, flush_on_drop: bool) -> Self {
+        self.flush_on_drop = flush_on_drop;
+        self
+    }
+
+    /// Buffer a message, flushing the batch when a threshold is reached
+    ///
+    /// # Errors
+    /// * `InvalidParams` - The message can't fit in a batch on its own
+    /// * Any error from submitting a full batch; the batch is kept so a
+    ///   later `send` or `flush` can retry it, and `data` is not buffered
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        let data = data.as_ref();
+        let entry_len = frame::BATCH_ENTRY_OVERHEAD + data.len();
+        if entry_len > self.max_batch_bytes {
+            return Err(BusError::InvalidParams);
+        }
+
+        let mut pending = self.lock_pending();
+        if pending.batch.len() + entry_len > self.max_batch_bytes {
+            self.submit(&mut pending)?;
+        }
+
+        if pending.batch.is_empty() {
+            pending.type_id = type_id;
+            pending.started = Some(Instant::now());
+        }
+        frame::push_batch_entry(&mut pending.batch, type_id, data).ok_or(BusError::InvalidParams)?;
+
+        let expired = pending.started.is_some_and(|t| t.elapsed() >= self.max_delay);
+        if pending.batch.len() == self.max_batch_bytes || expired {
+            self.submit(&mut pending)?;
+        }
+        Ok(())
+    }
+
+    /// Submit whatever is buffered now
+    pub fn flush(&self) -> Result<(), BusError> {
+        let mut pending = self.lock_pending();
+        self.submit(&mut pending)
+    }
+
+    /// Number of bytes buffered but not yet submitted
+    pub fn pending_bytes(&self) -> usize {
+        self.lock_pending().batch.len()
+    }
+
+    /// Receive the next individual message
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.receive_with_type().map(|(_, data)| data)
+    }
+
+    /// Receive the next individual message along with its type id
+    ///
+    /// A bus message that isn't a valid batch is logged and discarded.
+    pub fn receive_with_type(&self) -> Option<(u32, Vec<u8>)> {
+        let mut unpacked = self.unpacked.lock().unwrap_or_else(|e| e.into_inner());
+        while unpacked.is_empty() {
+            let batch = self.bus.receive()?;
+            match frame::decode_batch(&batch) {
+                Some(entries) => unpacked.extend(
+                    entries
+                        .into_iter()
+                        .map(|(type_id, payload)| (type_id, payload.to_vec())),
+                ),
+                None => {
+                    eprintln!("[Rust Direct] Discarding malformed coalesced batch");
+                    self.bus.record_drop(&batch, DropReason::Malformed);
+                }
+            }
+        }
+        unpacked.pop_front()
+    }
+
+    /// Access the underlying bus
+    pub fn inner(&self) -> &DirectUniversalBus {
+        &self.bus
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn submit(&self, pending: &mut Pending) -> Result<(), BusError> {
+        if pending.batch.is_empty() {
+            return Ok(());
+        }
+        self.bus.send(&pending.batch[..], pending.type_id)?;
+        pending.batch.clear();
+        pending.started = None;
+        Ok(())
+    }
+}
+
+impl Drop for CoalescingBus {
+    fn drop(&mut self) {
+        if self.flush_on_drop {
+            match self.flush() {
+                Ok(()) => return,
+                Err(e) => eprintln!("[Rust Direct] Dropping unflushed coalesced batch: {}", e),
+            }
+        }
+
+        let pending = self.lock_pending();
+        for (_, payload) in frame::decode_batch(&pending.batch).unwrap_or_default() {
+            self.bus.record_drop(payload, DropReason::Unflushed);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Typed decoding of received messages
+
+use crate::{BusError, DirectUniversalBus};
+
+/// Errors decoding a received message into a typed value
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The payload didn't deserialize as the requested type
+    Malformed(String),
+    /// The format tag is unknown or its codec isn't compiled in
+    UnsupportedFormat(u8),
+    /// No message type is declared for the type id
+    UnknownType(u32),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Malformed(reason) => write!(f, "Malformed message: {}", reason),
+            DecodeError::UnsupportedFormat(tag) => write!(f, "Unsupported format tag {}", tag),
+            DecodeError::UnknownType(type_id) => write!(f, "Unknown message type {}", type_id),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Serialization format recorded in a message's leading tag byte
+///
+/// Each codec is decoded only when its feature is enabled: `bincode`,
+/// `msgpack`, or `serde` for JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WireFormat {
+    Raw = 0,
+    Bincode = 1,
+    MessagePack = 2,
+    Json = 3,
+}
+
+impl WireFormat {
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(WireFormat::Raw),
+            1 => Some(WireFormat::Bincode),
+            2 => Some(WireFormat::MessagePack),
+            3 => Some(WireFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn decode_tagged<T: serde::de::DeserializeOwned>(message: &[u8]) -> Result<T, DecodeError> {
+    let (&tag, body) = message
+        .split_first()
+        .ok_or_else(|| DecodeError::Malformed("missing format tag".to_string()))?;
+
+    match WireFormat::from_tag(tag) {
+        Some(WireFormat::Json) => {
+            serde_json::from_slice(body).map_err(|e| DecodeError::Malformed(e.to_string()))
+        }
+        #[cfg(feature = "bincode")]
+        Some(WireFormat::Bincode) => {
+            bincode::deserialize(body).map_err(|e| DecodeError::Malformed(e.to_string()))
+        }
+        #[cfg(feature = "msgpack")]
+        Some(WireFormat::MessagePack) => {
+            rmp_serde::from_slice(body).map_err(|e| DecodeError::Malformed(e.to_string()))
+        }
+        // Raw payloads carry no schema to deserialize with
+        _ => Err(DecodeError::UnsupportedFormat(tag)),
+    }
+}
+
+/// Iterator returned by [`DirectUniversalBus::drain_as`]
+///
+/// Yields one decoded item per drained message and ends when the bus is
+/// empty. A message that fails to decode yields an `Err` for that item only.
+#[cfg(feature = "serde")]
+pub struct DrainAs<'a, T> {
+    bus: &'a DirectUniversalBus,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> Iterator for DrainAs<'_, T> {
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.bus.receive()?;
+        Some(serde_json::from_slice(&data).map_err(|e| DecodeError::Malformed(e.to_string())))
+    }
+}
+
+impl DirectUniversalBus {
+    /// Send an already-serialized payload tagged with its format
+    ///
+    /// # Example
+    /// ```rust
+    /// let body = serde_json::to_vec(&record)?;
+    /// bus.send_tagged(WireFormat::Json, &body, 1)?;
+    /// ```
+    pub fn send_tagged(&self, format: WireFormat, data: &[u8], type_id: u32) -> Result<(), BusError> {
+        self.send_vectored(&[&[format as u8], data], type_id)
+    }
+
+    /// Receive a tagged message and decode it with the codec its tag names
+    ///
+    /// Lets one consumer accept messages from producers using different
+    /// codecs. Returns `None` if the bus is empty, and
+    /// `UnsupportedFormat` for unknown tags, raw payloads, or codecs whose
+    /// feature isn't enabled.
+    #[cfg(feature = "serde")]
+    pub fn receive_auto<T: serde::de::DeserializeOwned>(&self) -> Option<Result<T, DecodeError>> {
+        let data = self.receive()?;
+        Some(decode_tagged(&data))
+    }
+
+    /// Drain the bus, deserializing each JSON message as `T`
+    ///
+    /// # Example
+    /// ```rust
+    /// for record in bus.drain_as::<LogRecord>() {
+    ///     match record {
+    ///         Ok(record) => ingest(record),
+    ///         Err(e) => eprintln!("Skipping bad record: {}", e),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn drain_as<T: serde::de::DeserializeOwned>(&self) -> DrainAs<'_, T> {
+        DrainAs {
+            bus: self,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Receive a fixed-layout message as an owned `T`
+    ///
+    /// The payload is copied into `T` without per-field parsing. Returns
+    /// `None` if the bus is empty, and `Malformed` if the payload isn't
+    /// exactly `size_of::<T>()` bytes; the message is consumed either way.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[derive(FromBytes, KnownLayout, Immutable)]
+    /// #[repr(C)]
+    /// struct Tick { symbol: u32, price: u64, volume: u32 }
+    ///
+    /// if let Some(tick) = bus.receive_as_struct::<Tick>() {
+    ///     book.apply(tick?);
+    /// }
+    /// ```
+    #[cfg(feature = "zerocopy")]
+    pub fn receive_as_struct<T: zerocopy::FromBytes>(&self) -> Option<Result<T, DecodeError>> {
+        self.drain_with(crate::LanguageType::Rust, |_, data| {
+            T::read_from_bytes(data).map_err(|_| {
+                DecodeError::Malformed(format!(
+                    "expected {} bytes, got {}",
+                    std::mem::size_of::<T>(),
+                    data.len()
+                ))
+            })
+        })
+    }
+
+    /// Receive a fixed-layout message and view it in place as a `&T`
+    ///
+    /// `f` borrows the core's drained copy, so nothing is copied at all;
+    /// the copy is freed once `f` returns. Fails with `Malformed` if the
+    /// payload's length or alignment doesn't suit `T`, without calling `f`.
+    #[cfg(feature = "zerocopy")]
+    pub fn receive_struct_with<T, R>(&self, f: impl FnOnce(&T) -> R) -> Option<Result<R, DecodeError>>
+    where
+        T: zerocopy::FromBytes + zerocopy::KnownLayout + zerocopy::Immutable,
+    {
+        self.drain_with(crate::LanguageType::Rust, |_, data| {
+            T::ref_from_bytes(data)
+                .map(f)
+                .map_err(|e| DecodeError::Malformed(e.to_string()))
+        })
+    }
+}
@@ -0,0 +1,89 @@
+//! Building buses from environment variables
+
+use std::str::FromStr;
+
+use crate::{BusError, DirectUniversalBus, ScalingConfig, DEFAULT_BUFFER_SIZE};
+
+impl DirectUniversalBus {
+    /// Create a bus configured from `UMSBB_*` environment variables
+    ///
+    /// | Variable | Default |
+    /// |---|---|
+    /// | `UMSBB_BUFFER_SIZE` | `1048576` |
+    /// | `UMSBB_SEGMENT_COUNT` | `0` (auto-determine) |
+    /// | `UMSBB_GPU_PREFERRED` | `false` |
+    /// | `UMSBB_AUTO_SCALE` | `false` |
+    ///
+    /// When auto-scaling is enabled, each [`ScalingConfig`] field can be
+    /// overridden too: `UMSBB_MIN_PRODUCERS`, `UMSBB_MAX_PRODUCERS`,
+    /// `UMSBB_MIN_CONSUMERS`, `UMSBB_MAX_CONSUMERS`,
+    /// `UMSBB_SCALE_THRESHOLD_PERCENT`, `UMSBB_SCALE_COOLDOWN_MS` and
+    /// `UMSBB_AUTO_BALANCE_LOAD`; unset fields keep their defaults.
+    ///
+    /// Booleans accept `1`/`0`, `true`/`false`, `yes`/`no` and `on`/`off`.
+    /// A value that doesn't parse fails with `InvalidEnv` rather than
+    /// silently falling back to the default.
+    pub fn from_env() -> Result<Self, BusError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    pub(crate) fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, BusError> {
+        let env = EnvReader { lookup };
+
+        let buffer_size = env.number("UMSBB_BUFFER_SIZE", DEFAULT_BUFFER_SIZE)?;
+        let segment_count = env.number("UMSBB_SEGMENT_COUNT", 0)?;
+        let gpu_preferred = env.flag("UMSBB_GPU_PREFERRED", false)?;
+        let auto_scale = env.flag("UMSBB_AUTO_SCALE", false)?;
+
+        let scaling = if auto_scale {
+            let defaults = ScalingConfig::default();
+            Some(ScalingConfig {
+                min_producers: env.number("UMSBB_MIN_PRODUCERS", defaults.min_producers)?,
+                max_producers: env.number("UMSBB_MAX_PRODUCERS", defaults.max_producers)?,
+                min_consumers: env.number("UMSBB_MIN_CONSUMERS", defaults.min_consumers)?,
+                max_consumers: env.number("UMSBB_MAX_CONSUMERS", defaults.max_consumers)?,
+                scale_threshold_percent: env
+                    .number("UMSBB_SCALE_THRESHOLD_PERCENT", defaults.scale_threshold_percent)?,
+                scale_cooldown_ms: env.number("UMSBB_SCALE_COOLDOWN_MS", defaults.scale_cooldown_ms)?,
+                gpu_preferred,
+                auto_balance_load: env.flag("UMSBB_AUTO_BALANCE_LOAD", defaults.auto_balance_load)?,
+            })
+        } else {
+            None
+        };
+
+        Self::create(buffer_size, segment_count, gpu_preferred, scaling.as_ref(), None)
+    }
+}
+
+struct EnvReader<F> {
+    lookup: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvReader<F> {
+    fn number<T: FromStr>(&self, name: &str, default: T) -> Result<T, BusError> {
+        match (self.lookup)(name) {
+            Some(value) => value.trim().parse().map_err(|_| invalid(name, value)),
+            None => Ok(default),
+        }
+    }
+
+    fn flag(&self, name: &str, default: bool) -> Result<bool, BusError> {
+        let Some(value) = (self.lookup)(name) else {
+            return Ok(default);
+        };
+
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(invalid(name, value)),
+        }
+    }
+}
+
+fn invalid(name: &str, value: String) -> BusError {
+    BusError::InvalidEnv {
+        name: name.to_string(),
+        value,
+    }
+}
@@ -0,0 +1,87 @@
+//! A ready-made consumer loop for a single bus
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::latency::LatencyRecorder;
+use crate::{panic_message, DirectUniversalBus, LatencyStats};
+
+// How long each wait for a message lasts before the shutdown flag is
+// checked again
+const SHUTDOWN_POLL: Duration = Duration::from_millis(10);
+
+/// What a [`DirectUniversalBus::consume_loop`] did before returning
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConsumeReport {
+    /// Messages handed to the handler, including ones it panicked on
+    pub processed: u64,
+    /// Handler invocations that panicked
+    pub panics: u64,
+    /// Time spent in the handler per message
+    pub latency: LatencyStats,
+}
+
+impl DirectUniversalBus {
+    /// Receive and handle messages until `shutdown` is set
+    ///
+    /// Waits for messages with [`DirectUniversalBus::receive_timeout`], so
+    /// an idle loop doesn't spin and a [`crate::ReceiveWaker`] makes it
+    /// recheck the flag straight away; the loop resets the wake once it has
+    /// done so, even if it was woken before it started waiting. A panic in `handler` is logged and
+    /// contained to the message that caused it. Once `shutdown` is set the
+    /// loop keeps going until the bus is empty, so messages already queued
+    /// are not lost, and then returns what it did.
+    ///
+    /// # Example
+    /// ```rust
+    /// let shutdown = Arc::new(AtomicBool::new(false));
+    /// let worker = {
+    ///     let (bus, shutdown) = (Arc::clone(&bus), Arc::clone(&shutdown));
+    ///     std::thread::spawn(move || bus.consume_loop(shutdown, |data| handle(data)))
+    /// };
+    /// // ...
+    /// shutdown.store(true, Ordering::Relaxed);
+    /// let report = worker.join().unwrap();
+    /// ```
+    pub fn consume_loop<F>(&self, shutdown: Arc<AtomicBool>, mut handler: F) -> ConsumeReport
+    where
+        F: FnMut(Vec<u8>),
+    {
+        let latency = LatencyRecorder::default();
+        let mut report = ConsumeReport::default();
+
+        loop {
+            let stopping = shutdown.load(Ordering::Acquire);
+            let received = if stopping {
+                self.receive()
+            } else {
+                self.receive_timeout(SHUTDOWN_POLL)
+            };
+
+            let Some(data) = received else {
+                if stopping {
+                    break; // Drained
+                }
+                // A wake only asks for the flag to be rechecked, which the
+                // next iteration does
+                if self.wake_state.is_set() {
+                    self.wake_state.reset();
+                }
+                continue;
+            };
+
+            let started = Instant::now();
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(data)));
+            latency.record(started.elapsed());
+            report.processed += 1;
+            if let Err(payload) = result {
+                report.panics += 1;
+                eprintln!("[Rust Direct] Consumer handler panicked: {}", panic_message(&*payload));
+            }
+        }
+
+        report.latency = latency.stats();
+        report
+    }
+}
@@ -0,0 +1,129 @@
+//! Suppression of repeated sends by content hash
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{BusError, DirectUniversalBus, DropReason};
+
+/// What a send does when its payload duplicates a recent one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Fail the send with [`BusError::Duplicate`]
+    Reject,
+    /// Report success without submitting anything
+    Drop,
+}
+
+// Recently admitted hashes, oldest first
+pub(crate) struct DedupFilter {
+    window: usize,
+    max_age: Duration,
+    action: DuplicateAction,
+    recent: Mutex<VecDeque<(u64, Instant)>>,
+    suppressed: AtomicU64,
+}
+
+impl DedupFilter {
+    fn admit(&self, hash: u64) -> bool {
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        while recent
+            .front()
+            .is_some_and(|&(_, seen)| now.duration_since(seen) > self.max_age)
+        {
+            recent.pop_front();
+        }
+
+        if recent.iter().any(|&(seen, _)| seen == hash) {
+            return false;
+        }
+        if recent.len() == self.window {
+            recent.pop_front();
+        }
+        recent.push_back((hash, now));
+        true
+    }
+
+    // Drop a hash whose send failed, so a retry isn't mistaken for a
+    // duplicate
+    fn forget(&self, hash: u64) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = recent.iter().rposition(|&(seen, _)| seen == hash) {
+            recent.remove(index);
+        }
+    }
+}
+
+impl DirectUniversalBus {
+    /// Suppress sends that repeat a recent payload
+    ///
+    /// Each payload sent through `send`, `send_owned` or a
+    /// [`CapacityGuard`](crate::CapacityGuard) is hashed together with its
+    /// type id; a send matching one of the last `window` admitted messages
+    /// that is at most `max_age` old is handled per `action`. Failed sends are
+    /// not remembered, so retrying them is safe. Hash collisions are
+    /// possible in principle but vanishingly rare.
+    ///
+    /// # Example
+    /// ```rust
+    /// let bus = DirectUniversalBus::with_defaults()?
+    ///     .with_dedup(1024, Duration::from_secs(10), DuplicateAction::Drop);
+    /// ```
+    pub fn with_dedup(mut self, window: usize, max_age: Duration, action: DuplicateAction) -> Self {
+        self.dedup = Some(DedupFilter {
+            window: window.max(1),
+            max_age,
+            action,
+            recent: Mutex::new(VecDeque::with_capacity(window.max(1))),
+            suppressed: AtomicU64::new(0),
+        });
+        self
+    }
+
+    /// Number of sends rejected or dropped as duplicates
+    pub fn duplicates_suppressed(&self) -> u64 {
+        self.dedup
+            .as_ref()
+            .map_or(0, |filter| filter.suppressed.load(Ordering::Relaxed))
+    }
+
+    // The key to deduplicate `data` under, or `None` when dedup is off
+    pub(crate) fn dedup_key(&self, data: &[u8], type_id: u32) -> Option<u64> {
+        self.dedup.as_ref()?;
+        let mut hasher = DefaultHasher::new();
+        type_id.hash(&mut hasher);
+        data.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    // Run `send` on `data` unless `key` duplicates a recent message
+    pub(crate) fn send_deduplicated<D: AsRef<[u8]>>(
+        &self,
+        data: D,
+        key: Option<u64>,
+        send: impl FnOnce(D) -> Result<(), BusError>,
+    ) -> Result<(), BusError> {
+        let (Some(filter), Some(hash)) = (&self.dedup, key) else {
+            return send(data);
+        };
+
+        if !filter.admit(hash) {
+            filter.suppressed.fetch_add(1, Ordering::Relaxed);
+            self.record_drop(data.as_ref(), DropReason::Duplicate);
+            return match filter.action {
+                DuplicateAction::Reject => Err(BusError::Duplicate),
+                DuplicateAction::Drop => Ok(()),
+            };
+        }
+
+        let result = send(data);
+        if result.is_err() {
+            filter.forget(hash);
+        }
+        result
+    }
+}
@@ -0,0 +1,104 @@
+//! Handles restricted to one direction of a bus
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{BusError, BusStats, DirectUniversalBus, ReceiveResult, ReceivedMessage};
+
+/// A handle that can only receive from a bus
+///
+/// Created by [`DirectUniversalBus::into_reader`] or
+/// [`DirectUniversalBus::into_halves`]. Clones share the same bus, which is
+/// destroyed once the last reader or writer is dropped.
+#[derive(Clone)]
+pub struct BusReader {
+    bus: Arc<DirectUniversalBus>,
+}
+
+/// A handle that can only send to a bus
+///
+/// Created by [`DirectUniversalBus::into_writer`] or
+/// [`DirectUniversalBus::into_halves`]. Clones share the same bus, which is
+/// destroyed once the last reader or writer is dropped.
+#[derive(Clone)]
+pub struct BusWriter {
+    bus: Arc<DirectUniversalBus>,
+}
+
+impl DirectUniversalBus {
+    /// Split the bus into a reader and a writer for the same bus
+    ///
+    /// Neither half can recover the other, so handing the reader to a
+    /// consumer really does keep it from sending.
+    pub fn into_halves(self) -> (BusReader, BusWriter) {
+        let bus = Arc::new(self);
+        (
+            BusReader {
+                bus: Arc::clone(&bus),
+            },
+            BusWriter { bus },
+        )
+    }
+
+    /// Give up the send half of the API for good
+    pub fn into_reader(self) -> BusReader {
+        BusReader {
+            bus: Arc::new(self),
+        }
+    }
+
+    /// Give up the receive half of the API for good
+    pub fn into_writer(self) -> BusWriter {
+        BusWriter {
+            bus: Arc::new(self),
+        }
+    }
+}
+
+impl BusReader {
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.bus.receive()
+    }
+
+    pub fn try_receive(&self) -> Result<Option<Vec<u8>>, BusError> {
+        self.bus.try_receive()
+    }
+
+    pub fn receive_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        self.bus.receive_timeout(timeout)
+    }
+
+    pub fn receive_full(&self) -> Option<ReceivedMessage> {
+        self.bus.receive_full()
+    }
+
+    pub fn receive_or_eof(&self) -> ReceiveResult {
+        self.bus.receive_or_eof()
+    }
+
+    pub fn stats(&self) -> BusStats {
+        self.bus.stats()
+    }
+}
+
+impl BusWriter {
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        self.bus.send(data, type_id)
+    }
+
+    pub fn send_blocking<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        self.bus.send_blocking(data, type_id)
+    }
+
+    pub fn send_owned(&self, data: Vec<u8>, type_id: u32) -> Result<(), BusError> {
+        self.bus.send_owned(data, type_id)
+    }
+
+    pub fn send_eof(&self) -> Result<(), BusError> {
+        self.bus.send_eof()
+    }
+
+    pub fn stats(&self) -> BusStats {
+        self.bus.stats()
+    }
+}
@@ -0,0 +1,170 @@
+//! Accounting for messages the bus discards
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::DirectUniversalBus;
+
+/// Why the bus discarded a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// The send repeated a recent payload and dedup suppressed it
+    Duplicate,
+    /// The validator registered for the message's type id rejected it
+    Validation,
+    /// A blob was not retrieved before its TTL ran out
+    Expired,
+    /// The core reported an implausible message size; the payload can't
+    /// be trusted, so the hook receives an empty slice
+    Corrupted,
+    /// A received message didn't have the framing its reader expects,
+    /// such as a coalesced batch, a sequence number prefix or a fixed size
+    Malformed,
+    /// A message was still buffered in the bindings when its bus was
+    /// dropped, either coalesced or held by the fair-queue scheduler, or
+    /// an autoscaling consumer had taken it when an immediate stop landed
+    Unflushed,
+}
+
+impl DropReason {
+    const ALL: [DropReason; 6] = [
+        DropReason::Duplicate,
+        DropReason::Validation,
+        DropReason::Expired,
+        DropReason::Corrupted,
+        DropReason::Malformed,
+        DropReason::Unflushed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropReason::Duplicate => "duplicate",
+            DropReason::Validation => "validation failed",
+            DropReason::Expired => "expired",
+            DropReason::Corrupted => "corrupted",
+            DropReason::Malformed => "malformed",
+            DropReason::Unflushed => "unflushed",
+        }
+    }
+}
+
+impl std::fmt::Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Messages discarded per reason, from [`DirectUniversalBus::drop_stats`]
+/// and [`BusStats::dropped`](crate::BusStats::dropped)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DropStats {
+    pub duplicate: u64,
+    pub validation: u64,
+    pub expired: u64,
+    pub corrupted: u64,
+    pub malformed: u64,
+    pub unflushed: u64,
+}
+
+impl DropStats {
+    /// Count for a single reason
+    pub fn get(&self, reason: DropReason) -> u64 {
+        match reason {
+            DropReason::Duplicate => self.duplicate,
+            DropReason::Validation => self.validation,
+            DropReason::Expired => self.expired,
+            DropReason::Corrupted => self.corrupted,
+            DropReason::Malformed => self.malformed,
+            DropReason::Unflushed => self.unflushed,
+        }
+    }
+
+    /// Messages discarded for any reason
+    pub fn total(&self) -> u64 {
+        DropReason::ALL.iter().map(|&reason| self.get(reason)).sum()
+    }
+
+    // Every count in reason order, for encoding
+    pub(crate) fn counts(&self) -> [u64; 6] {
+        DropReason::ALL.map(|reason| self.get(reason))
+    }
+
+    pub(crate) fn from_counts(counts: [u64; 6]) -> Self {
+        DropStats {
+            duplicate: counts[0],
+            validation: counts[1],
+            expired: counts[2],
+            corrupted: counts[3],
+            malformed: counts[4],
+            unflushed: counts[5],
+        }
+    }
+
+    // Per-reason sum, saturating rather than wrapping
+    pub(crate) fn saturating_add(&self, other: &DropStats) -> DropStats {
+        let (a, b) = (self.counts(), other.counts());
+        DropStats::from_counts(std::array::from_fn(|i| a[i].saturating_add(b[i])))
+    }
+}
+
+type DropHook = Arc<dyn Fn(&[u8], DropReason) + Send + Sync>;
+
+// Per-reason counters, indexed like `DropReason::ALL`, and the user hook
+#[derive(Default)]
+pub(crate) struct DropTracker {
+    counts: [AtomicU64; 6],
+    hook: RwLock<Option<DropHook>>,
+}
+
+impl DropTracker {
+    pub(crate) fn record(&self, data: &[u8], reason: DropReason) {
+        self.counts[reason as usize].fetch_add(1, Ordering::Relaxed);
+        // Clone the hook out so it can replace itself without deadlocking
+        let hook = self.hook.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(hook) = hook {
+            hook(data, reason);
+        }
+    }
+
+    fn stats(&self) -> DropStats {
+        DropStats::from_counts(std::array::from_fn(|i| self.counts[i].load(Ordering::Relaxed)))
+    }
+}
+
+impl DirectUniversalBus {
+    /// Call `hook` with every message the bus discards and the reason
+    ///
+    /// Covers sends suppressed by dedup (whether rejected or silently
+    /// dropped), sends rejected by a validator, blobs that expire before
+    /// being retrieved, corrupted messages skipped on receive, messages
+    /// the coalescing, ordered and fixed-size readers can't decode, and
+    /// messages lost when a `CoalescingBus` is dropped with a batch
+    /// unflushed, a bus is dropped while the fair-queue scheduler still
+    /// holds messages or an immediate stop catches an autoscaling consumer
+    /// with a message it hasn't run yet. The hook runs on the thread that discarded the
+    /// message, so it should be quick. Replaces any hook already installed.
+    ///
+    /// # Example
+    /// ```rust
+    /// bus.on_drop(|data, reason| {
+    ///     eprintln!("dropped {} bytes: {}", data.len(), reason);
+    /// });
+    /// ```
+    pub fn on_drop<F>(&self, hook: F)
+    where
+        F: Fn(&[u8], DropReason) + Send + Sync + 'static,
+    {
+        *self.drops.hook.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+    }
+
+    /// Number of messages discarded so far, per reason
+    ///
+    /// The same counts are reported in [`BusStats::dropped`](crate::BusStats::dropped).
+    pub fn drop_stats(&self) -> DropStats {
+        self.drops.stats()
+    }
+
+    pub(crate) fn record_drop(&self, data: &[u8], reason: DropReason) {
+        self.drops.record(data, reason);
+    }
+}
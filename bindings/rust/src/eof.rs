@@ -0,0 +1,70 @@
+//! End-of-stream signalling from producers to consumers
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{BusError, DirectUniversalBus};
+
+// Payload of the end-of-stream marker; chosen to be implausible as
+// application data
+const EOF_MARKER: &[u8] = b"\0umsbb:end-of-stream\0";
+
+/// Outcome of [`DirectUniversalBus::receive_or_eof`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveResult {
+    Message(Vec<u8>),
+    /// Nothing available yet, but the stream is still open
+    Empty,
+    /// The bus is empty and a producer has sent end-of-stream
+    EndOfStream,
+}
+
+// Whether an end-of-stream marker has been received on this bus
+#[derive(Debug, Default)]
+pub(crate) struct EofState {
+    seen: AtomicBool,
+}
+
+impl DirectUniversalBus {
+    /// Tell consumers that no more data is coming
+    ///
+    /// Enqueues a marker that [`DirectUniversalBus::receive_or_eof`]
+    /// consumes; plain `receive` returns it as an ordinary message, so all
+    /// consumers of a bus that uses end-of-stream should receive through
+    /// `receive_or_eof`. The marker skips validation.
+    pub fn send_eof(&self) -> Result<(), BusError> {
+        self.send_unvalidated(EOF_MARKER, 0)
+    }
+
+    /// Receive the next message, distinguishing a finished stream from a
+    /// momentarily empty one
+    ///
+    /// Once the end-of-stream marker has been seen, messages still in the
+    /// bus keep being returned; `EndOfStream` is reported only when it is
+    /// empty. With several segments the marker may be drained before
+    /// messages sent ahead of it in other segments, which are still
+    /// delivered before `EndOfStream`. The state is shared by every
+    /// consumer of this `DirectUniversalBus`.
+    ///
+    /// # Example
+    /// ```rust
+    /// loop {
+    ///     match bus.receive_or_eof() {
+    ///         ReceiveResult::Message(data) => process(data),
+    ///         ReceiveResult::Empty => std::thread::sleep(Duration::from_millis(1)),
+    ///         ReceiveResult::EndOfStream => break,
+    ///     }
+    /// }
+    /// ```
+    pub fn receive_or_eof(&self) -> ReceiveResult {
+        loop {
+            match self.receive() {
+                Some(data) if data == EOF_MARKER => {
+                    self.eof.seen.store(true, Ordering::Release);
+                }
+                Some(data) => return ReceiveResult::Message(data),
+                None if self.eof.seen.load(Ordering::Acquire) => return ReceiveResult::EndOfStream,
+                None => return ReceiveResult::Empty,
+            }
+        }
+    }
+}
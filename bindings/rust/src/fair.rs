@@ -0,0 +1,202 @@
+//! Weighted fair queuing across type ids
+//!
+//! Implements deficit round robin: each type id with queued messages gets a
+//! turn in rotation, and every turn credits it `weight * QUANTUM_BYTES` of
+//! deficit to spend on messages. Over time each backlogged type receives
+//! bandwidth in proportion to its weight, so no type starves. Without
+//! weights, fair drain mode serves one message per type per turn instead.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{DirectUniversalBus, DropReason, LanguageType};
+
+// Bytes of deficit credited per unit of weight each round
+const QUANTUM_BYTES: u64 = 4096;
+
+// Weight for type ids that were not given one
+const DEFAULT_WEIGHT: u32 = 1;
+
+// Messages the scheduler pulls out of the core ahead of `receive`
+pub(crate) const MAX_BUFFERED: usize = 1024;
+
+#[derive(Default)]
+struct Scheduler {
+    weights: HashMap<u32, u32>,
+    queues: HashMap<u32, VecDeque<Vec<u8>>>,
+    // Type ids with queued messages, in round-robin order; the front is
+    // the type currently being served
+    active: VecDeque<u32>,
+    deficits: HashMap<u32, u64>,
+    head_credited: bool,
+    round_robin: bool,
+    // Messages across all queues
+    buffered: usize,
+}
+
+impl Scheduler {
+    fn enqueue(&mut self, type_id: u32, data: Vec<u8>) {
+        let queue = self.queues.entry(type_id).or_default();
+        if queue.is_empty() {
+            self.active.push_back(type_id);
+        }
+        queue.push_back(data);
+        self.buffered += 1;
+    }
+
+    fn quantum(&self, type_id: u32) -> u64 {
+        let weight = self.weights.get(&type_id).copied().unwrap_or(DEFAULT_WEIGHT);
+        weight.max(1) as u64 * QUANTUM_BYTES
+    }
+
+    // Whether messages should be pulled from the core into the queues
+    fn is_enabled(&self) -> bool {
+        !self.weights.is_empty() || self.round_robin
+    }
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let message = if self.weights.is_empty() {
+            self.next_round_robin()
+        } else {
+            self.next_weighted()
+        };
+        if message.is_some() {
+            self.buffered -= 1;
+        }
+        message
+    }
+
+    fn next_weighted(&mut self) -> Option<Vec<u8>> {
+
+        loop {
+            let &type_id = self.active.front()?;
+            if !self.head_credited {
+                let quantum = self.quantum(type_id);
+                *self.deficits.entry(type_id).or_default() += quantum;
+                self.head_credited = true;
+            }
+
+            let queue = self.queues.get_mut(&type_id)?;
+            let deficit = self.deficits.entry(type_id).or_default();
+            let size = queue.front().map_or(0, Vec::len) as u64;
+            if *deficit >= size {
+                *deficit -= size;
+                let message = queue.pop_front();
+                if queue.is_empty() {
+                    // An idle type does not bank credit for later
+                    self.active.pop_front();
+                    self.deficits.remove(&type_id);
+                    self.head_credited = false;
+                }
+                return message;
+            }
+
+            // Not enough credit left this round; move on to the next type
+            self.active.rotate_left(1);
+            self.head_credited = false;
+        }
+    }
+
+    // One message from the front type, which then goes to the back
+    fn next_round_robin(&mut self) -> Option<Vec<u8>> {
+        let type_id = self.active.pop_front()?;
+        let queue = self.queues.get_mut(&type_id)?;
+        let message = queue.pop_front();
+        if !queue.is_empty() {
+            self.active.push_back(type_id);
+        }
+        self.deficits.remove(&type_id);
+        self.head_credited = false;
+        message
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct FairQueue {
+    scheduler: Mutex<Scheduler>,
+}
+
+impl FairQueue {
+    fn lock(&self) -> MutexGuard<'_, Scheduler> {
+        self.scheduler.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl DirectUniversalBus {
+    /// Schedule [`DirectUniversalBus::receive`] fairly across type ids
+    ///
+    /// Once weights are set, `receive` pulls messages queued in the core
+    /// into per-type queues, up to 1024 held at a time, and picks the next
+    /// message by deficit round robin, so a backlogged type receives
+    /// bandwidth in proportion to its weight. Type ids without a weight get
+    /// weight 1, and a weight of 0 is treated as 1. Passing an empty map
+    /// restores plain FIFO order once the per-type queues have drained.
+    ///
+    /// Types are the ids messages were sent with. Fairness applies among
+    /// the messages held, so a type whose messages sit behind a deeper
+    /// backlog in the core waits until they are pulled. Messages held in the
+    /// per-type queues still count as pending in
+    /// [`DirectUniversalBus::stats`] and `len`, and only `receive` and
+    /// `receive_bytes` consult the scheduler.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// // Control messages (type 0) get 8x the share of bulk data (type 1)
+    /// bus.set_type_weights(HashMap::from([(0, 8), (1, 1)]));
+    /// ```
+    pub fn set_type_weights(&self, weights: HashMap<u32, u32>) {
+        self.fair.lock().weights = weights;
+    }
+
+    /// Have [`DirectUniversalBus::receive`] take turns between type ids
+    ///
+    /// With fair drain on, `receive` cycles through the type ids that have
+    /// messages queued, one message each, instead of following the core's
+    /// drain order, so a burst of one type can't starve the others. Like
+    /// [`DirectUniversalBus::set_type_weights`], it pulls up to 1024
+    /// messages from the core into per-type queues. Weights, when set, take
+    /// precedence.
+    pub fn set_fair_drain(&self, enabled: bool) {
+        self.fair.lock().round_robin = enabled;
+    }
+
+    // Receive through the scheduler, or `None` if fair queuing is off and
+    // nothing is left in its queues
+    pub(crate) fn receive_fair(&self) -> Option<Option<Vec<u8>>> {
+        let mut scheduler = self.fair.lock();
+        if !scheduler.is_enabled() && scheduler.active.is_empty() {
+            return None;
+        }
+
+        if scheduler.is_enabled() {
+            while scheduler.buffered < MAX_BUFFERED {
+                let Some((type_id, data)) =
+                    self.drain_with(LanguageType::Rust, |origin, data| (origin.type_id, data.to_vec()))
+                else {
+                    break;
+                };
+                scheduler.enqueue(type_id, data);
+            }
+        }
+        Some(scheduler.next())
+    }
+
+    // Messages pulled out of the core and not yet received
+    pub(crate) fn fair_buffered(&self) -> u32 {
+        self.fair.lock().buffered as u32
+    }
+
+    // Report whatever the scheduler still holds as unflushed. The hook runs
+    // after the lock is released, so it may call back into the bus.
+    pub(crate) fn drop_fair_queued(&self) {
+        let scheduler = std::mem::take(&mut *self.fair.lock());
+        let Scheduler { mut queues, active, .. } = scheduler;
+        for type_id in active {
+            for data in queues.remove(&type_id).unwrap_or_default() {
+                self.record_drop(&data, DropReason::Unflushed);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Framing helpers for metadata carried alongside message payloads
+//!
+//! A header frame is laid out as:
+//!
+//! ```text
+//! [u32 LE block length][block][payload]
+//! block = [u16 LE count] { [u16 LE key len][key][u16 LE value len][value] }*
+//! ```
+//!
+//! A coalesced batch is a run of entries filling the whole message:
+//!
+//! ```text
+//! { [u32 LE type id][u32 LE payload length][payload] }*
+//! ```
+//!
+//! On a byte stream, each frame is preceded by its length:
+//!
+//! ```text
+//! [u32 LE body length][body]
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::Headers;
+
+// Largest stream frame accepted, so a corrupt length can't force a huge
+// allocation
+pub(crate) const MAX_STREAM_FRAME: usize = 64 * 1024 * 1024;
+
+/// Encode `headers` followed by `payload` into a single frame
+///
+/// Returns `None` if a key, value, or the header count doesn't fit in `u16`.
+pub(crate) fn encode_headers(headers: &[(&str, &str)], payload: &[u8]) -> Option<Vec<u8>> {
+    let count = u16::try_from(headers.len()).ok()?;
+
+    let mut block = Vec::new();
+    block.extend_from_slice(&count.to_le_bytes());
+    for (key, value) in headers {
+        for field in [key, value] {
+            let len = u16::try_from(field.len()).ok()?;
+            block.extend_from_slice(&len.to_le_bytes());
+            block.extend_from_slice(field.as_bytes());
+        }
+    }
+
+    let mut frame = Vec::with_capacity(4 + block.len() + payload.len());
+    frame.extend_from_slice(&(block.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&block);
+    frame.extend_from_slice(payload);
+    Some(frame)
+}
+
+/// Split a header frame into its headers and payload
+///
+/// Returns `None` if the frame is truncated or a header isn't valid UTF-8.
+pub(crate) fn decode_headers(frame: &[u8]) -> Option<(Headers, &[u8])> {
+    let block_len = u32::from_le_bytes(frame.get(..4)?.try_into().ok()?) as usize;
+    let block = frame.get(4..4usize.checked_add(block_len)?)?;
+    let payload = &frame[4 + block_len..];
+
+    let mut cursor = block;
+    let count = take_u16(&mut cursor)?;
+    let mut headers = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let key = take_str(&mut cursor)?;
+        let value = take_str(&mut cursor)?;
+        headers.push((key, value));
+    }
+
+    Some((headers, payload))
+}
+
+fn take_u16(cursor: &mut &[u8]) -> Option<u16> {
+    let (bytes, rest) = cursor.split_first_chunk::<2>()?;
+    *cursor = rest;
+    Some(u16::from_le_bytes(*bytes))
+}
+
+fn take_str(cursor: &mut &[u8]) -> Option<String> {
+    let len = take_u16(cursor)? as usize;
+    let bytes = cursor.get(..len)?;
+    *cursor = &cursor[len..];
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+// Bytes a batch entry adds on top of its payload
+pub(crate) const BATCH_ENTRY_OVERHEAD: usize = 8;
+
+/// Append one message to a coalesced batch
+///
+/// Returns `None` if the payload is too long for a `u32` length.
+pub(crate) fn push_batch_entry(batch: &mut Vec<u8>, type_id: u32, payload: &[u8]) -> Option<()> {
+    let len = u32::try_from(payload.len()).ok()?;
+    batch.extend_from_slice(&type_id.to_le_bytes());
+    batch.extend_from_slice(&len.to_le_bytes());
+    batch.extend_from_slice(payload);
+    Some(())
+}
+
+/// Split a coalesced batch back into `(type_id, payload)` entries
+///
+/// Returns `None` if the last entry is truncated.
+pub(crate) fn decode_batch(mut batch: &[u8]) -> Option<Vec<(u32, &[u8])>> {
+    let mut entries = Vec::new();
+    while !batch.is_empty() {
+        let type_id = take_u32(&mut batch)?;
+        let len = take_u32(&mut batch)? as usize;
+        let payload = batch.get(..len)?;
+        batch = &batch[len..];
+        entries.push((type_id, payload));
+    }
+    Some(entries)
+}
+
+fn take_u32(cursor: &mut &[u8]) -> Option<u32> {
+    let (bytes, rest) = cursor.split_first_chunk::<4>()?;
+    *cursor = rest;
+    Some(u32::from_le_bytes(*bytes))
+}
+
+/// Write `body` to a stream as one length-prefixed frame
+pub(crate) fn write_frame<W: Write>(writer: &mut W, body: &[u8]) -> io::Result<()> {
+    if body.len() > MAX_STREAM_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too large"));
+    }
+    writer.write_all(&(body.len() as u32).to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+/// Read one length-prefixed frame from a stream
+///
+/// Returns `Ok(None)` if the stream ends cleanly before a new frame.
+pub(crate) fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_STREAM_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
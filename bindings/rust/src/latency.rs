@@ -0,0 +1,92 @@
+//! Timing of consumer callbacks
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Recent invocations the percentiles are computed over
+const LATENCY_WINDOW: usize = 1024;
+
+/// Consumer callback durations from [`crate::AutoScalingBus::consumer_latency_stats`]
+///
+/// `min` and `max` cover every invocation since the bus was created;
+/// `p50` and `p99` cover the most recent 1024.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub min: Duration,
+    pub max: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+}
+
+pub(crate) type SlowConsumerHook = Arc<dyn Fn(u32, Duration) + Send + Sync>;
+
+#[derive(Debug, Default)]
+struct Samples {
+    recent: VecDeque<Duration>,
+    count: u64,
+    min: Duration,
+    max: Duration,
+}
+
+// Durations shared by all consumers of a bus
+#[derive(Debug, Default)]
+pub(crate) struct LatencyRecorder {
+    samples: Mutex<Samples>,
+}
+
+impl LatencyRecorder {
+    pub(crate) fn record(&self, elapsed: Duration) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.min = if samples.count == 0 { elapsed } else { samples.min.min(elapsed) };
+        samples.max = samples.max.max(elapsed);
+        samples.count += 1;
+        if samples.recent.len() == LATENCY_WINDOW {
+            samples.recent.pop_front();
+        }
+        samples.recent.push_back(elapsed);
+    }
+
+    pub(crate) fn stats(&self) -> LatencyStats {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sorted: Vec<_> = samples.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        let percentile = |p: usize| {
+            sorted
+                .get(sorted.len().saturating_sub(1) * p / 100)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        LatencyStats {
+            count: samples.count,
+            min: samples.min,
+            max: samples.max,
+            p50: percentile(50),
+            p99: percentile(99),
+        }
+    }
+}
+
+// What a consumer does when one invocation exceeds the threshold
+#[derive(Clone, Default)]
+pub(crate) struct SlowConsumerCheck {
+    pub(crate) threshold: Option<Duration>,
+    pub(crate) hook: Option<SlowConsumerHook>,
+}
+
+impl SlowConsumerCheck {
+    pub(crate) fn check(&self, worker_id: u32, elapsed: Duration) {
+        if self.threshold.is_none_or(|t| elapsed <= t) {
+            return;
+        }
+        match &self.hook {
+            Some(hook) => hook(worker_id, elapsed),
+            None => eprintln!(
+                "[Rust AutoScale] Consumer {} took {:?} on one message",
+                worker_id, elapsed
+            ),
+        }
+    }
+}
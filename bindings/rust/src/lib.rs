@@ -1,14 +1,77 @@
 //! Universal Multi-Segmented Bi-Buffer Bus - Rust Direct Binding
 //! No API wrapper - Direct FFI connection with auto-scaling and GPU support
 
-use std::ffi::{CStr, CString};
-use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::os::raw::c_void;
 use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use padded::CachePadded;
+
+mod ack;
+mod blob;
+mod capacity;
+mod codec;
+mod coalesce;
+mod config;
+mod consume;
+mod dedup;
+mod direction;
+mod drops;
+mod eof;
+mod fair;
+mod frame;
+mod latency;
+mod messages;
+mod mirror;
+mod padded;
+mod pipe;
+mod poll;
+mod registry;
+mod remote;
+mod retry;
+mod selector;
+mod sequence;
+mod sharded;
+mod tap;
+mod timeout;
+mod transform;
+mod validate;
+mod waker;
+
+pub use ack::{AckToken, AckedMessage};
+pub use blob::{BlobId, DEFAULT_BLOB_TTL};
+pub use capacity::CapacityGuard;
+pub use codec::{DecodeError, WireFormat};
+pub use coalesce::CoalescingBus;
+pub use consume::ConsumeReport;
+pub use dedup::DuplicateAction;
+pub use direction::{BusReader, BusWriter};
+pub use drops::{DropReason, DropStats};
+pub use eof::ReceiveResult;
+pub use latency::LatencyStats;
+pub use messages::MessagePayload;
+pub use mirror::{MirrorPolicy, MirrorReport, MirroredBus};
+#[cfg(feature = "serde")]
+pub use codec::DrainAs;
+pub use pipe::Subscription;
+pub use poll::ReceiveStrategy;
+pub use registry::BusRegistry;
+pub use remote::{serve, RemoteBus, RemoteServer};
+pub use retry::RetryPolicy;
+pub use selector::BusSelector;
+pub use sequence::{SequenceEvent, SequenceStats, SequenceTracker};
+pub use sharded::ShardedBus;
+pub use tap::{read_tap_log, replay, TapDirection, TapRecord, TappedBus};
+pub use transform::TransformBus;
+pub use validate::ValidationError;
+pub use waker::ReceiveWaker;
 
 // Language types
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LanguageType {
     C = 0,
     Cpp = 1,
@@ -32,9 +95,23 @@ pub struct UniversalData {
     pub source_lang: LanguageType,
 }
 
+/// How `send` picks the segment a message is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentStrategy {
+    /// Let the core decide (`type_id % segment_count`)
+    #[default]
+    Core,
+    /// Cycle through the segments in order
+    RoundRobin,
+    /// Hash `type_id`, keeping every message of a type in one segment
+    HashTypeId,
+    /// Pick the segment currently holding the fewest bytes
+    LeastFull,
+}
+
 // Scaling configuration
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ScalingConfig {
     pub min_producers: u32,
     pub max_producers: u32,
@@ -46,6 +123,21 @@ pub struct ScalingConfig {
     pub auto_balance_load: bool,
 }
 
+impl Default for ScalingConfig {
+    fn default() -> Self {
+        ScalingConfig {
+            min_producers: 1,
+            max_producers: 16,
+            min_consumers: 1,
+            max_consumers: 8,
+            scale_threshold_percent: 75,
+            scale_cooldown_ms: 1000,
+            gpu_preferred: false,
+            auto_balance_load: true,
+        }
+    }
+}
+
 // GPU capabilities
 #[repr(C)]
 #[derive(Debug, Clone)]
@@ -59,13 +151,152 @@ pub struct GpuCapabilities {
     pub max_threads: usize,
 }
 
+// Bus statistics
+#[derive(Debug, Clone, Default)]
+pub struct BusStats {
+    pub total_messages: u64,
+    pub total_bytes: u64,
+    pub pending_messages: u32,
+    pub active_segments: u32,
+    /// Messages the bindings discarded, per reason
+    pub dropped: DropStats,
+}
+
+// The counters kept by the core, as laid out by `umsbb_direct_stats_t`
+#[repr(C)]
+struct CoreStats {
+    total_messages: u64,
+    total_bytes: u64,
+    pending_messages: u32,
+    active_segments: u32,
+}
+
+/// Decoded message headers, in the order they were sent
+pub type Headers = Vec<(String, String)>;
+
+// Errors reported by bus operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum BusError {
+    InvalidParams,
+    BufferFull,
+    InvalidHandle,
+    MemoryAllocation,
+    CreationFailed,
+    ScalingConfigFailed,
+    AbiMismatch { expected: u32, found: u32 },
+    InvalidEnv { name: String, value: String },
+    CorruptedData,
+    Io { kind: std::io::ErrorKind, message: String },
+    ShuttingDown,
+    Validation { type_id: u32, reason: String },
+    Duplicate,
+    Timeout,
+}
+
+impl BusError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BusError::InvalidParams => "Invalid parameters",
+            BusError::BufferFull => "Buffer is full",
+            BusError::InvalidHandle => "Invalid bus handle",
+            BusError::MemoryAllocation => "Memory allocation failed",
+            BusError::CreationFailed => "Failed to create Universal Bus",
+            BusError::ScalingConfigFailed => "Failed to configure auto-scaling",
+            BusError::AbiMismatch { .. } => "Core ABI version mismatch",
+            BusError::InvalidEnv { .. } => "Invalid environment variable",
+            BusError::CorruptedData => "Corrupted data detected",
+            BusError::Io { .. } => "I/O error",
+            BusError::ShuttingDown => "Bus is shutting down",
+            BusError::Validation { .. } => "Message failed validation",
+            BusError::Duplicate => "Duplicate of a recent message",
+            BusError::Timeout => "Operation timed out",
+        }
+    }
+
+    /// Whether retrying the same operation later might succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(self, BusError::BufferFull | BusError::MemoryAllocation)
+    }
+}
+
+impl std::fmt::Display for BusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BusError::AbiMismatch { expected, found } => write!(
+                f,
+                "{} (expected {}, found {})",
+                self.as_str(),
+                expected,
+                found
+            ),
+            BusError::InvalidEnv { name, value } => {
+                write!(f, "{} {}={:?}", self.as_str(), name, value)
+            }
+            BusError::Io { message, .. } => write!(f, "{}: {}", self.as_str(), message),
+            BusError::Validation { type_id, reason } => {
+                write!(f, "{} for type {}: {}", self.as_str(), type_id, reason)
+            }
+            _ => write!(f, "{}", self.as_str()),
+        }
+    }
+}
+
+/// Version of the C ABI these bindings were written against
+/// 
+/// Bumped whenever a `#[repr(C)]` struct or FFI signature changes.
+pub const ABI_VERSION: u32 = 1;
+
+impl std::error::Error for BusError {}
+
+impl From<std::io::Error> for BusError {
+    fn from(e: std::io::Error) -> Self {
+        BusError::Io { kind: e.kind(), message: e.to_string() }
+    }
+}
+
+// Signature of `umsbb_drain_direct_ex`; tests swap in a misbehaving core
+type DrainFn = unsafe extern "C" fn(*mut c_void, LanguageType, *mut LanguageType) -> *mut UniversalData;
+
+// Frees a buffer handed to `umsbb_submit_owned_direct`
+type ReleaseFn = unsafe extern "C" fn(*mut c_void, usize, usize);
+
+// Rebuilds and drops the `Vec` a `send_owned` buffer came from
+unsafe extern "C" fn release_vec(data: *mut c_void, size: usize, capacity: usize) {
+    drop(Vec::from_raw_parts(data as *mut u8, size, capacity));
+}
+
 // External C functions
 extern "C" {
     // Core functions
     fn umsbb_create_direct(buffer_size: usize, segment_count: u32, lang: LanguageType) -> *mut c_void;
+    fn umsbb_create_direct_numa(
+        buffer_size: usize,
+        segment_count: u32,
+        lang: LanguageType,
+        numa_node: u32,
+    ) -> *mut c_void;
     fn umsbb_submit_direct(handle: *mut c_void, data: *const UniversalData) -> bool;
-    fn umsbb_drain_direct(handle: *mut c_void, target_lang: LanguageType) -> *mut UniversalData;
+    fn umsbb_submit_to_segment_direct(handle: *mut c_void, segment_id: u32, data: *const UniversalData) -> bool;
+    fn umsbb_submit_owned_direct(
+        handle: *mut c_void,
+        segment_id: u32,
+        data: *mut c_void,
+        size: usize,
+        capacity: usize,
+        type_id: u32,
+        lang: LanguageType,
+        release: ReleaseFn,
+    ) -> bool;
+    fn umsbb_segment_used_direct(handle: *mut c_void, segment_id: u32) -> usize;
+    fn umsbb_segment_count_direct(handle: *mut c_void) -> u32;
+    fn umsbb_drain_direct_ex(
+        handle: *mut c_void,
+        target_lang: LanguageType,
+        source_lang: *mut LanguageType,
+    ) -> *mut UniversalData;
     fn umsbb_destroy_direct(handle: *mut c_void);
+    fn umsbb_abi_version() -> u32;
+    fn umsbb_get_stats_direct(handle: *mut c_void) -> CoreStats;
     
     // GPU functions
     fn initialize_gpu() -> bool;
@@ -74,6 +305,7 @@ extern "C" {
     
     // Scaling functions
     fn configure_auto_scaling(config: *const ScalingConfig) -> bool;
+    fn get_scaling_config() -> ScalingConfig;
     fn get_optimal_producer_count() -> u32;
     fn get_optimal_consumer_count() -> u32;
     fn trigger_scale_evaluation();
@@ -83,26 +315,105 @@ extern "C" {
     fn free_universal_data(data: *mut UniversalData);
 }
 
+/// Snapshot of FFI call counts made by a bus
+///
+/// Counters are only maintained when the `ffi-stats` feature is enabled;
+/// otherwise every field reads as zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiCallStats {
+    pub submit_calls: u64,
+    pub drain_calls: u64,
+    pub create_data_calls: u64,
+    pub free_data_calls: u64,
+}
+
+// Relaxed counters bumped around each FFI call; padded apart because
+// producers bump `submit` while consumers bump `drain`
+#[derive(Debug, Default)]
+struct FfiCounters {
+    submit: CachePadded<AtomicU64>,
+    drain: CachePadded<AtomicU64>,
+    create_data: CachePadded<AtomicU64>,
+    free_data: CachePadded<AtomicU64>,
+}
+
+impl FfiCounters {
+    #[inline]
+    fn count(&self, _counter: fn(&Self) -> &CachePadded<AtomicU64>) {
+        #[cfg(feature = "ffi-stats")]
+        _counter(self).fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> FfiCallStats {
+        FfiCallStats {
+            submit_calls: self.submit.load(Ordering::Relaxed),
+            drain_calls: self.drain.load(Ordering::Relaxed),
+            create_data_calls: self.create_data.load(Ordering::Relaxed),
+            free_data_calls: self.free_data.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Direct Universal Bus for Rust
 /// 
 /// Provides zero-cost abstractions over the native C implementation
 /// with Rust safety guarantees and ergonomic APIs
+/// 
+/// The native handle is destroyed exactly once: either explicitly through
+/// [`DirectUniversalBus::destroy`], or when the bus is dropped.
 pub struct DirectUniversalBus {
     handle: *mut c_void,
     buffer_size: usize,
     segment_count: u32,
     gpu_enabled: bool,
+    drain: DrainFn,
+    ffi_counters: FfiCounters,
+    scratch: Mutex<Vec<u8>>,
+    data_pool: Option<Mutex<Vec<UniversalData>>>,
+    segment_strategy: SegmentStrategy,
+    next_segment: CachePadded<AtomicU32>,
+    sends_interrupted: CachePadded<std::sync::atomic::AtomicBool>,
+    blobs: blob::BlobStore,
+    type_stats: Mutex<HashMap<u32, TypeStats>>,
+    acks: ack::AckRegistry,
+    message_capacity: Option<u32>,
+    reserved: Mutex<u32>,
+    wake_state: Arc<waker::WakeState>,
+    retry_policy: RetryPolicy,
+    fair: fair::FairQueue,
+    validators: validate::Validators,
+    dedup: Option<dedup::DedupFilter>,
+    send_sequence: CachePadded<AtomicU64>,
+    eof: eof::EofState,
+    drops: drops::DropTracker,
 }
 
+/// Segment size used by [`DirectUniversalBus::with_defaults`]
+pub const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+
+// The direct bindings take a per-handle lock around every call into the
+// core, and guard the process-wide scaling metrics with their own lock, so
+// the handle may be shared between worker threads.
+unsafe impl Send for DirectUniversalBus {}
+unsafe impl Sync for DirectUniversalBus {}
+
 impl DirectUniversalBus {
     /// Create a new Direct Universal Bus
     /// 
+    /// The linked core's ABI version is checked first, so a core built
+    /// against different struct layouts fails here with `AbiMismatch`
+    /// instead of corrupting memory later.
+    /// 
     /// # Arguments
     /// * `buffer_size` - Size of each buffer segment (default: 1MB)
     /// * `segment_count` - Number of segments (0 = auto-determine)
     /// * `gpu_preferred` - Prefer GPU processing for large operations
     /// * `auto_scale` - Enable automatic scaling
     /// 
+    /// The core keeps a single, process-wide scaling configuration, so
+    /// `auto_scale = true` also changes scaling for every other bus in the
+    /// process. Use [`DirectUniversalBus::new_isolated`] to avoid that.
+    /// 
     /// # Example
     /// ```rust
     /// use umsbb_direct::DirectUniversalBus;
@@ -115,17 +426,79 @@ impl DirectUniversalBus {
         segment_count: u32,
         gpu_preferred: bool,
         auto_scale: bool,
-    ) -> Result<Self, String> {
-        if auto_scale {
-            Self::configure_auto_scaling_internal(gpu_preferred)?;
+    ) -> Result<Self, BusError> {
+        let scaling = auto_scale.then(|| ScalingConfig {
+            gpu_preferred,
+            ..ScalingConfig::default()
+        });
+        Self::create(buffer_size, segment_count, gpu_preferred, scaling.as_ref(), None)
+    }
+
+    /// Create a bus whose buffers are allocated on NUMA node `numa_node`
+    /// 
+    /// Placing the buffers on the node where the consumer threads run
+    /// avoids cross-socket memory traffic on multi-socket machines. If the
+    /// core can't honor the hint (no NUMA support, or no such node), the
+    /// bus is created as by [`DirectUniversalBus::new`] and a message is
+    /// logged. `None` is equivalent to `new`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// let bus = DirectUniversalBus::new_on_numa_node(1024 * 1024, 0, false, false, Some(1))?;
+    /// ```
+    pub fn new_on_numa_node(
+        buffer_size: usize,
+        segment_count: u32,
+        gpu_preferred: bool,
+        auto_scale: bool,
+        numa_node: Option<u32>,
+    ) -> Result<Self, BusError> {
+        let scaling = auto_scale.then(|| ScalingConfig {
+            gpu_preferred,
+            ..ScalingConfig::default()
+        });
+        Self::create(buffer_size, segment_count, gpu_preferred, scaling.as_ref(), numa_node)
+    }
+
+    /// Create a bus with 1MB segments, an automatic segment count and
+    /// neither GPU preference nor auto-scaling
+    /// 
+    /// Equivalent to `new(DEFAULT_BUFFER_SIZE, 0, false, false)`.
+    pub fn with_defaults() -> Result<Self, BusError> {
+        Self::new(DEFAULT_BUFFER_SIZE, 0, false, false)
+    }
+
+    /// Create a bus without touching the core's global scaling configuration
+    /// 
+    /// The bus runs under whatever scaling configuration is already in
+    /// effect, so creating it never changes the behavior of other buses in
+    /// the process.
+    pub fn new_isolated(
+        buffer_size: usize,
+        segment_count: u32,
+        gpu_preferred: bool,
+    ) -> Result<Self, BusError> {
+        Self::create(buffer_size, segment_count, gpu_preferred, None, None)
+    }
+
+    // Shared constructor; `scaling` is applied before the bus is created
+    fn create(
+        buffer_size: usize,
+        segment_count: u32,
+        gpu_preferred: bool,
+        scaling: Option<&ScalingConfig>,
+        numa_node: Option<u32>,
+    ) -> Result<Self, BusError> {
+        Self::check_abi()?;
+
+        if let Some(config) = scaling {
+            Self::configure_auto_scaling_internal(config)?;
         }
 
-        let handle = unsafe {
-            umsbb_create_direct(buffer_size, segment_count, LanguageType::Rust)
-        };
+        let handle = Self::create_handle(buffer_size, segment_count, numa_node);
 
         if handle.is_null() {
-            return Err("Failed to create Universal Bus".to_string());
+            return Err(BusError::CreationFailed);
         }
 
         let gpu_enabled = if gpu_preferred {
@@ -139,32 +512,97 @@ impl DirectUniversalBus {
             buffer_size, gpu_enabled
         );
 
-        Ok(DirectUniversalBus {
+        Ok(Self::from_parts(handle, buffer_size, gpu_enabled))
+    }
+
+    // Create the native bus, on `numa_node` if the core supports it
+    fn create_handle(buffer_size: usize, segment_count: u32, numa_node: Option<u32>) -> *mut c_void {
+        if let Some(node) = numa_node {
+            let handle = unsafe {
+                umsbb_create_direct_numa(buffer_size, segment_count, LanguageType::Rust, node)
+            };
+            if !handle.is_null() {
+                return handle;
+            }
+            eprintln!("[Rust Direct] NUMA node {} unavailable, allocating without a hint", node);
+        }
+
+        unsafe { umsbb_create_direct(buffer_size, segment_count, LanguageType::Rust) }
+    }
+
+    fn from_parts(handle: *mut c_void, buffer_size: usize, gpu_enabled: bool) -> Self {
+        DirectUniversalBus {
             handle,
             buffer_size,
-            segment_count,
+            // What the core created, which differs from the request when
+            // it picked the count itself or capped it
+            segment_count: unsafe { umsbb_segment_count_direct(handle) },
             gpu_enabled,
-        })
+            drain: umsbb_drain_direct_ex,
+            ffi_counters: FfiCounters::default(),
+            scratch: Mutex::new(Vec::new()),
+            data_pool: None,
+            segment_strategy: SegmentStrategy::Core,
+            next_segment: CachePadded::new(AtomicU32::new(0)),
+            sends_interrupted: CachePadded::new(std::sync::atomic::AtomicBool::new(false)),
+            blobs: blob::BlobStore::new(DEFAULT_BLOB_TTL),
+            type_stats: Mutex::new(HashMap::new()),
+            acks: ack::AckRegistry::default(),
+            message_capacity: None,
+            reserved: Mutex::new(0),
+            wake_state: Arc::default(),
+            retry_policy: RetryPolicy::default(),
+            fair: fair::FairQueue::default(),
+            validators: validate::Validators::default(),
+            dedup: None,
+            send_sequence: CachePadded::new(AtomicU64::new(0)),
+            eof: eof::EofState::default(),
+            drops: drops::DropTracker::default(),
+        }
     }
 
-    /// Configure automatic scaling parameters
-    fn configure_auto_scaling_internal(gpu_preferred: bool) -> Result<(), String> {
-        let config = ScalingConfig {
-            min_producers: 1,
-            max_producers: 16,
-            min_consumers: 1,
-            max_consumers: 8,
-            scale_threshold_percent: 75,
-            scale_cooldown_ms: 1000,
-            gpu_preferred,
-            auto_balance_load: true,
-        };
+    /// Wrap a bus handle obtained from the C side
+    /// 
+    /// The returned bus takes ownership of the handle and destroys it when
+    /// dropped; use [`DirectUniversalBus::into_raw_handle`] to give it back.
+    /// 
+    /// # Safety
+    /// `handle` must be a live handle returned by `umsbb_create_direct`
+    /// that nothing else will destroy, and `buffer_size` must match the
+    /// value it was created with, since received message sizes are
+    /// validated against it. The segment count is read from the handle.
+    pub unsafe fn from_raw_handle(handle: *mut c_void, buffer_size: usize) -> Self {
+        Self::from_parts(handle, buffer_size, false)
+    }
+
+    /// Release ownership of the native handle without destroying it
+    /// 
+    /// The caller becomes responsible for eventually passing the handle to
+    /// `umsbb_destroy_direct` (or back to [`DirectUniversalBus::from_raw_handle`]).
+    pub fn into_raw_handle(mut self) -> *mut c_void {
+        std::mem::replace(&mut self.handle, ptr::null_mut())
+    }
 
-        let success = unsafe { configure_auto_scaling(&config) };
+    /// Compare the linked core's ABI version against [`ABI_VERSION`]
+    pub fn check_abi() -> Result<(), BusError> {
+        let found = unsafe { umsbb_abi_version() };
+        if found == ABI_VERSION {
+            Ok(())
+        } else {
+            Err(BusError::AbiMismatch {
+                expected: ABI_VERSION,
+                found,
+            })
+        }
+    }
+
+    /// Configure automatic scaling parameters
+    fn configure_auto_scaling_internal(config: &ScalingConfig) -> Result<(), BusError> {
+        let success = unsafe { configure_auto_scaling(config) };
         if success {
             Ok(())
         } else {
-            Err("Failed to configure auto-scaling".to_string())
+            Err(BusError::ScalingConfigFailed)
         }
     }
 
@@ -179,9 +617,39 @@ impl DirectUniversalBus {
     /// bus.send("Hello from Rust!", 1)?;
     /// bus.send(&[1, 2, 3, 4], 2)?;
     /// ```
-    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), String> {
-        let bytes = data.as_ref();
+    /// 
+    /// # Errors
+    /// * `MemoryAllocation` - The core couldn't allocate the message
+    /// * `BufferFull` - The core rejected the submit, or the message
+    ///   capacity is taken up by pending messages and reservations
+    /// * `InvalidParams` - The message is larger than the bus's total
+    ///   capacity, so a receiver would reject it as corrupted
+    /// * `InvalidHandle` - The bus has been destroyed
+    /// * `Validation` - The validator registered for `type_id` rejected
+    ///   the message
+    /// * `Duplicate` - Dedup is on and the message repeats a recent one
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        let data = data.as_ref();
+        self.validate(data, type_id)?;
+        self.send_deduplicated(data, self.dedup_key(data, type_id), |data| {
+            self.send_unreserved(data, type_id)
+        })
+    }
+
+    // Send without consulting the message capacity; reserved sends land here
+    fn send_bytes(&self, bytes: &[u8], type_id: u32) -> Result<(), BusError> {
+        if self.handle.is_null() {
+            return Err(BusError::InvalidHandle);
+        }
+        if bytes.len() > self.max_message_size() {
+            return Err(BusError::InvalidParams);
+        }
+
+        if let Some(result) = self.send_pooled(bytes, type_id) {
+            return result;
+        }
         
+        self.ffi_counters.count(|c| &c.create_data);
         let udata = unsafe {
             create_universal_data(
                 bytes.as_ptr() as *const c_void,
@@ -192,323 +660,3368 @@ impl DirectUniversalBus {
         };
 
         if udata.is_null() {
-            return Err("Failed to create universal data".to_string());
+            return Err(BusError::MemoryAllocation);
         }
 
-        let result = unsafe { umsbb_submit_direct(self.handle, udata) };
+        let result = self.submit(udata, type_id);
+        self.ffi_counters.count(|c| &c.free_data);
         unsafe { free_universal_data(udata) };
 
         if result {
             Ok(())
         } else {
-            Err("Failed to submit data".to_string())
+            Err(BusError::BufferFull)
         }
     }
 
-    /// Receive data from the bus
+    /// Send an owned buffer, handing it to the core instead of copying it
     /// 
-    /// # Returns
-    /// * `Some(Vec<u8>)` - Received data
-    /// * `None` - No data available
+    /// `send` copies the payload into a message the core queues. Here the
+    /// core adopts `data`'s allocation and queues a pointer to it, so a
+    /// large payload is never copied on the way in.
+    /// 
+    /// # Ownership
+    /// Once the call returns `Ok` the buffer belongs to the bus. It is freed,
+    /// as the `Vec` it was, after a receiver has drained the message (the
+    /// receiver gets its own copy) or when the bus is destroyed with the
+    /// message still queued. On `Err` the buffer comes back from the core
+    /// and is dropped here, so the payload is lost; use `send` when a
+    /// rejected message has to be retried.
+    /// 
+    /// Validation, dedup and the message capacity apply as for `send`, and
+    /// the call costs a single submit in [`FfiCallStats`].
     /// 
     /// # Example
     /// ```rust
-    /// if let Some(data) = bus.receive() {
-    ///     println!("Received: {:?}", data);
-    /// }
+    /// let frame: Vec<u8> = encode_frame();
+    /// bus.send_owned(frame, 7)?;
     /// ```
-    pub fn receive(&self) -> Option<Vec<u8>> {
-        let udata_ptr = unsafe { umsbb_drain_direct(self.handle, LanguageType::Rust) };
+    /// 
+    /// # Errors
+    /// As for [`DirectUniversalBus::send`]; a failed allocation in the core
+    /// is reported as `BufferFull`.
+    pub fn send_owned(&self, data: Vec<u8>, type_id: u32) -> Result<(), BusError> {
+        self.validate(&data, type_id)?;
+        let key = self.dedup_key(&data, type_id);
+        self.send_deduplicated(data, key, |data| {
+            self.with_free_slot(|| self.submit_owned(data, type_id))
+        })
+    }
 
-        if udata_ptr.is_null() {
-            return None;
+    fn submit_owned(&self, data: Vec<u8>, type_id: u32) -> Result<(), BusError> {
+        if self.handle.is_null() {
+            return Err(BusError::InvalidHandle);
+        }
+        if data.len() > self.max_message_size() {
+            return Err(BusError::InvalidParams);
         }
 
-        let udata = unsafe { &*udata_ptr };
-        let data_slice = unsafe { slice::from_raw_parts(udata.data as *const u8, udata.size) };
-        let result = data_slice.to_vec();
+        // The core falls back to the type id when no segment is chosen
+        let segment = self.select_segment(type_id).unwrap_or(type_id);
+        let mut data = std::mem::ManuallyDrop::new(data);
+        let (ptr, size, capacity) = (data.as_mut_ptr(), data.len(), data.capacity());
+        self.ffi_counters.count(|c| &c.submit);
+        let accepted = unsafe {
+            umsbb_submit_owned_direct(
+                self.handle,
+                segment,
+                ptr as *mut c_void,
+                size,
+                capacity,
+                type_id,
+                LanguageType::Rust,
+                release_vec,
+            )
+        };
 
-        unsafe { free_universal_data(udata_ptr) };
+        if !accepted {
+            // Not adopted, so the buffer is still ours to free
+            drop(unsafe { Vec::from_raw_parts(ptr, size, capacity) });
+            return Err(BusError::BufferFull);
+        }
+        self.record_sent(type_id, size);
+        Ok(())
+    }
 
-        Some(result)
+    /// Preallocate `capacity` reusable `UniversalData` descriptors
+    /// 
+    /// With a pool in place `send` fills in a pooled descriptor and submits
+    /// it directly, skipping the `create_universal_data` and
+    /// `free_universal_data` round trip. When every descriptor is in use,
+    /// `send` falls back to the allocating path.
+    /// 
+    /// # Example
+    /// ```rust
+    /// let bus = DirectUniversalBus::with_defaults()?
+    ///     .with_data_pool(64);
+    /// ```
+    pub fn with_data_pool(mut self, capacity: usize) -> Self {
+        let pool = (0..capacity)
+            .map(|_| UniversalData {
+                data: ptr::null_mut(),
+                size: 0,
+                type_id: 0,
+                source_lang: LanguageType::Rust,
+            })
+            .collect();
+        self.data_pool = Some(Mutex::new(pool));
+        self
     }
 
-    /// Send data and wait for a response
+    /// Choose how messages are spread across segments
     /// 
-    /// # Arguments
-    /// * `data` - Data to send
-    /// * `type_id` - Type identifier
-    /// * `timeout_ms` - Timeout in milliseconds
+    /// Strategies spread messages over the segments the core actually
+    /// created, including when it picked the count because the bus was
+    /// created with `segment_count` 0.
     /// 
-    /// # Returns
-    /// * `Some(Vec<u8>)` - Response data
-    /// * `None` - No response within timeout
-    pub fn send_and_receive<T: AsRef<[u8]>>(
-        &self,
-        data: T,
-        type_id: u32,
-        timeout_ms: u64,
-    ) -> Option<Vec<u8>> {
-        if self.send(data, type_id).is_err() {
-            return None;
-        }
+    /// # Example
+    /// ```rust
+    /// let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false)?
+    ///     .with_segment_strategy(SegmentStrategy::HashTypeId);
+    /// ```
+    pub fn with_segment_strategy(mut self, strategy: SegmentStrategy) -> Self {
+        self.segment_strategy = strategy;
+        self
+    }
 
-        let start = std::time::Instant::now();
-        loop {
-            if let Some(response) = self.receive() {
-                return Some(response);
+    // Segment chosen by the configured strategy; None defers to the core
+    fn select_segment(&self, type_id: u32) -> Option<u32> {
+        let segments = self.segment_count;
+        if segments == 0 {
+            return None; // No live handle to submit to
+        }
+        match self.segment_strategy {
+            SegmentStrategy::Core => None,
+            SegmentStrategy::RoundRobin => {
+                Some(self.next_segment.fetch_add(1, Ordering::Relaxed) % segments)
             }
-
-            if start.elapsed().as_millis() >= timeout_ms as u128 {
-                return None;
+            // Fibonacci hashing so neighbouring type ids don't pile into
+            // neighbouring segments
+            SegmentStrategy::HashTypeId => {
+                Some(((type_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as u32 % segments)
             }
+            SegmentStrategy::LeastFull => (0..segments)
+                .min_by_key(|&segment| unsafe { umsbb_segment_used_direct(self.handle, segment) }),
+        }
+    }
 
-            std::thread::sleep(std::time::Duration::from_micros(100));
+    fn submit(&self, udata: *const UniversalData, type_id: u32) -> bool {
+        let segment = self.select_segment(type_id);
+        self.ffi_counters.count(|c| &c.submit);
+        let accepted = match segment {
+            Some(segment) => unsafe { umsbb_submit_to_segment_direct(self.handle, segment, udata) },
+            None => unsafe { umsbb_submit_direct(self.handle, udata) },
+        };
+        if accepted {
+            self.record_sent(type_id, unsafe { (*udata).size });
         }
+        accepted
     }
 
-    /// Get GPU capabilities information
-    pub fn get_gpu_info(&self) -> GpuInfo {
-        let caps = unsafe { get_gpu_capabilities() };
-        let available = unsafe { gpu_available() };
+    fn record_sent(&self, type_id: u32, bytes: usize) {
+        let mut type_stats = self.type_stats.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = type_stats.entry(type_id).or_default();
+        entry.messages += 1;
+        entry.bytes += bytes as u64;
+    }
 
-        GpuInfo {
-            available,
-            has_cuda: caps.has_cuda,
-            has_opencl: caps.has_opencl,
-            has_compute: caps.has_compute,
-            memory_size: caps.memory_size,
-            compute_capability: caps.compute_capability,
-            max_threads: caps.max_threads,
-        }
+    /// Messages and bytes sent through this bus, broken down by `type_id`
+    /// 
+    /// Tallied on the Rust side as sends are accepted, so traffic submitted
+    /// by other handles or languages to the same core bus isn't included.
+    pub fn stats_by_type(&self) -> HashMap<u32, TypeStats> {
+        self.type_stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
     }
 
-    /// Get current auto-scaling status
-    pub fn get_scaling_status(&self) -> ScalingStatus {
-        let optimal_producers = unsafe { get_optimal_producer_count() };
-        let optimal_consumers = unsafe { get_optimal_consumer_count() };
-        let gpu_info = self.get_gpu_info();
+    // Submit through a pooled descriptor; None when no descriptor is free
+    fn send_pooled(&self, bytes: &[u8], type_id: u32) -> Option<Result<(), BusError>> {
+        let pool = self.data_pool.as_ref()?;
+        let mut udata = pool.lock().unwrap_or_else(|e| e.into_inner()).pop()?;
 
-        ScalingStatus {
-            optimal_producers,
-            optimal_consumers,
-            gpu_info,
-        }
+        udata.data = bytes.as_ptr() as *mut c_void;
+        udata.size = bytes.len();
+        udata.type_id = type_id;
+
+        let result = self.submit(&udata, type_id);
+
+        // Don't leave a dangling pointer to the caller's bytes in the pool
+        udata.data = ptr::null_mut();
+        pool.lock().unwrap_or_else(|e| e.into_inner()).push(udata);
+
+        Some(if result {
+            Ok(())
+        } else {
+            Err(BusError::BufferFull)
+        })
     }
 
-    /// Trigger manual scale evaluation
-    pub fn trigger_scale_evaluation(&self) {
-        unsafe { trigger_scale_evaluation() };
+    /// Send data, waiting for room while the buffer is full
+    /// 
+    /// Retries until the message is accepted, waiting between attempts
+    /// according to the bus's [`RetryPolicy`] (its attempt limit doesn't
+    /// apply). Owners such as
+    /// [`AutoScalingBus`] can interrupt the wait during shutdown, in which
+    /// case `ShuttingDown` is returned and the message is not sent.
+    /// 
+    /// # Errors
+    /// * `ShuttingDown` - The wait was interrupted by a shutdown
+    /// * Any non-`BufferFull` error from [`DirectUniversalBus::send`]
+    pub fn send_blocking<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        self.send_blocking_until(data.as_ref(), type_id, || false)
     }
-}
 
-impl Drop for DirectUniversalBus {
-    fn drop(&mut self) {
-        if !self.handle.is_null() {
-            unsafe { umsbb_destroy_direct(self.handle) };
-            self.handle = ptr::null_mut();
-            println!("[Rust Direct] Bus destroyed");
+    // `send_blocking` that also gives up with `ShuttingDown` once `stop`
+    // returns true
+    fn send_blocking_until(
+        &self,
+        data: &[u8],
+        type_id: u32,
+        stop: impl Fn() -> bool,
+    ) -> Result<(), BusError> {
+        let mut retry = 0;
+        loop {
+            if self.sends_interrupted.load(Ordering::Acquire) || stop() {
+                return Err(BusError::ShuttingDown);
+            }
+            match self.send(data, type_id) {
+                Err(BusError::BufferFull) => {
+                    std::thread::sleep(self.retry_policy.backoff(retry));
+                    retry = retry.saturating_add(1);
+                }
+                result => return result,
+            }
         }
     }
-}
-
-// Helper structures for better Rust ergonomics
-#[derive(Debug, Clone)]
-pub struct GpuInfo {
-    pub available: bool,
-    pub has_cuda: bool,
-    pub has_opencl: bool,
-    pub has_compute: bool,
-    pub memory_size: usize,
-    pub compute_capability: i32,
-    pub max_threads: usize,
-}
 
-#[derive(Debug, Clone)]
-pub struct ScalingStatus {
-    pub optimal_producers: u32,
-    pub optimal_consumers: u32,
-    pub gpu_info: GpuInfo,
-}
+    // Make blocked and future `send_blocking` calls fail with `ShuttingDown`
+    fn interrupt_blocking_sends(&self, interrupted: bool) {
+        self.sends_interrupted.store(interrupted, Ordering::Release);
+    }
+
+    /// Send a large payload out-of-band
+    /// 
+    /// The payload is kept in a blob store owned by this bus and only a
+    /// 12-byte reference message is enqueued, so a big blob doesn't crowd
+    /// small messages out of the ring. Consumers recognize the reference
+    /// with [`BlobId::from_message`] and fetch the payload with
+    /// [`DirectUniversalBus::get_blob`]. Blobs nobody retrieves are freed
+    /// after the blob TTL (see [`DirectUniversalBus::with_blob_ttl`]).
+    /// 
+    /// # Example
+    /// ```rust
+    /// bus.send_blob(&large_payload, 9)?;
+    /// 
+    /// if let Some(message) = bus.receive() {
+    ///     if let Some(id) = BlobId::from_message(&message) {
+    ///         let payload = bus.get_blob(id);
+    ///     }
+    /// }
+    /// ```
+    pub fn send_blob(&self, data: &[u8], type_id: u32) -> Result<BlobId, BusError> {
+        let id = self.blobs.insert(data, &self.drops);
+        if let Err(e) = self.send(id.to_message(), type_id) {
+            self.blobs.remove(id, &self.drops);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Take a blob out of the store
+    /// 
+    /// Each blob can be retrieved once; returns `None` if it was already
+    /// taken or has expired.
+    pub fn get_blob(&self, id: BlobId) -> Option<Vec<u8>> {
+        self.blobs.remove(id, &self.drops)
+    }
+
+    /// Number of blobs waiting to be retrieved
+    pub fn pending_blobs(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Keep unretrieved blobs for `ttl` instead of [`DEFAULT_BLOB_TTL`]
+    pub fn with_blob_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.blobs.set_ttl(ttl);
+        self
+    }
+
+    /// Send data, retrying transient failures
+    /// 
+    /// `BufferFull` and `MemoryAllocation` are retried up to `retries` more
+    /// times, sleeping `backoff` between attempts; the last error is
+    /// returned if every attempt fails. Permanent errors such as
+    /// `InvalidParams` and `InvalidHandle` are returned immediately.
+    /// 
+    /// Shorthand for [`DirectUniversalBus::send_with_policy`] with
+    /// [`RetryPolicy::fixed`].
+    pub fn send_with_retry<T: AsRef<[u8]>>(
+        &self,
+        data: T,
+        type_id: u32,
+        retries: u32,
+        backoff: std::time::Duration,
+    ) -> Result<(), BusError> {
+        self.send_with_policy(data, type_id, &RetryPolicy::fixed(retries, backoff))
+    }
+
+    /// Send several non-contiguous buffers as a single message
+    /// 
+    /// The parts are gathered into a scratch buffer owned by the bus and
+    /// reused across calls, so callers don't need to concatenate them.
+    /// 
+    /// # Example
+    /// ```rust
+    /// let header = [0u8, 1, 2, 3];
+    /// bus.send_vectored(&[&header, b"body"], 1)?;
+    /// ```
+    pub fn send_vectored(&self, parts: &[&[u8]], type_id: u32) -> Result<(), BusError> {
+        let mut scratch = self.scratch.lock().unwrap_or_else(|e| e.into_inner());
+        scratch.clear();
+        for part in parts {
+            scratch.extend_from_slice(part);
+        }
+        self.send(&scratch[..], type_id)
+    }
+
+    /// Stream a reader into the bus in `chunk_size` pieces until EOF
+    /// 
+    /// Each chunk is sent as its own message (only the last may be short),
+    /// so large inputs pass through with bounded memory.
+    /// 
+    /// # Returns
+    /// Total number of bytes sent
+    /// 
+    /// # Example
+    /// ```rust
+    /// let mut file = std::fs::File::open("input.bin")?;
+    /// let sent = bus.send_from_reader(&mut file, 64 * 1024, 1)?;
+    /// ```
+    pub fn send_from_reader<R: std::io::Read>(
+        &self,
+        reader: &mut R,
+        chunk_size: usize,
+        type_id: u32,
+    ) -> Result<usize, BusError> {
+        if chunk_size == 0 {
+            return Err(BusError::InvalidParams);
+        }
+
+        let mut chunk = vec![0u8; chunk_size];
+        let mut total = 0;
+        loop {
+            // Fill the chunk completely so message boundaries don't depend
+            // on how the reader happens to split its data
+            let mut filled = 0;
+            while filled < chunk_size {
+                match reader.read(&mut chunk[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            if filled == 0 {
+                return Ok(total);
+            }
+            self.send(&chunk[..filled], type_id)?;
+            total += filled;
+            if filled < chunk_size {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Receive data from the bus
+    /// 
+    /// Messages come back in the core's drain order unless weights have
+    /// been set with [`DirectUniversalBus::set_type_weights`].
+    /// 
+    /// # Returns
+    /// * `Some(Vec<u8>)` - Received data
+    /// * `None` - No data available
+    /// 
+    /// # Example
+    /// ```rust
+    /// if let Some(data) = bus.receive() {
+    ///     println!("Received: {:?}", data);
+    /// }
+    /// ```
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        if let Some(message) = self.receive_fair() {
+            return message;
+        }
+        self.drain_with(LanguageType::Rust, |_, data| data.to_vec())
+    }
+
+    /// Receive the next message as [`bytes::Bytes`] without copying it
+    /// 
+    /// The returned `Bytes` takes ownership of the core's drained copy and
+    /// frees it through `free_universal_data` when the last clone is
+    /// dropped, so the payload can go straight into a networking stack.
+    /// With type weights or fair drain set, messages come through the
+    /// fair-queue scheduler as in [`DirectUniversalBus::receive`]; those
+    /// were already copied into its per-type queues.
+    #[cfg(feature = "bytes")]
+    pub fn receive_bytes(&self) -> Option<bytes::Bytes> {
+        if let Some(message) = self.receive_fair() {
+            return message.map(bytes::Bytes::from);
+        }
+        self.ffi_counters.count(|c| &c.drain);
+        let udata_ptr = unsafe { (self.drain)(self.handle, LanguageType::Rust, std::ptr::null_mut()) };
+
+        if udata_ptr.is_null() {
+            return None;
+        }
+
+        // The message may outlive the bus, so its free is counted up front
+        self.ffi_counters.count(|c| &c.free_data);
+        let message = NativeMessage(udata_ptr);
+        if let Err(e) = validated_payload(unsafe { &*udata_ptr }, self.max_message_size()) {
+            eprintln!("[Rust Direct] Discarding message: {}", e);
+            self.record_drop(&[], DropReason::Corrupted);
+            return None;
+        }
+
+        Some(bytes::Bytes::from_owner(message))
+    }
+
+    /// Drain every pending message, copying each payload into `arena`
+    /// 
+    /// The returned slices borrow from the arena, so a whole batch is
+    /// freed at once by resetting it instead of message by message.
+    /// Messages come back in the core's drain order, bypassing per-type
+    /// fair scheduling; corrupted messages are logged and skipped.
+    /// 
+    /// # Example
+    /// ```rust
+    /// let mut arena = bumpalo::Bump::new();
+    /// loop {
+    ///     for message in bus.drain_into_arena(&arena) {
+    ///         process(message);
+    ///     }
+    ///     arena.reset();
+    /// }
+    /// ```
+    #[cfg(feature = "bumpalo")]
+    pub fn drain_into_arena<'a>(&self, arena: &'a bumpalo::Bump) -> Vec<&'a [u8]> {
+        let mut messages = Vec::new();
+        loop {
+            match self.try_drain_with(LanguageType::Rust, |_, data| &*arena.alloc_slice_copy(data)) {
+                Ok(Some(message)) => messages.push(message),
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("[Rust Direct] Discarding message: {}", e);
+                    self.record_drop(&[], DropReason::Corrupted);
+                }
+            }
+        }
+        messages
+    }
+
+    /// Receive the next message as delivered to a consumer in `target`
+    /// 
+    /// The only effect of `target` is that the core allocates the drained
+    /// copy through the allocator registered for that language, if any.
+    /// The payload bytes are passed through unchanged: the core neither
+    /// validates them nor rewrites encodings between languages, so any
+    /// representation change must be agreed by producer and consumer.
+    pub fn receive_as(&self, target: LanguageType) -> Option<Vec<u8>> {
+        self.drain_with(target, |_, data| data.to_vec())
+    }
+
+    /// Receive the next message together with its type id and source
+    /// language
+    /// 
+    /// Bypasses per-type fair scheduling and returns messages in the
+    /// core's drain order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// if let Some(message) = bus.receive_full() {
+    ///     println!("{} bytes of type {} from {:?}",
+    ///         message.data.len(), message.type_id, message.source_lang);
+    /// }
+    /// ```
+    pub fn receive_full(&self) -> Option<ReceivedMessage> {
+        self.drain_with(LanguageType::Rust, |origin, data| ReceivedMessage {
+            data: data.to_vec(),
+            type_id: origin.type_id,
+            source_lang: origin.source_lang,
+        })
+    }
+
+    /// Drain messages into a writer, one payload after another
+    /// 
+    /// Stops after `max_messages` (if given) or once the bus is empty.
+    /// Corrupted messages are logged and skipped. Payloads are written
+    /// back-to-back without delimiters.
+    /// 
+    /// # Returns
+    /// Number of messages written
+    /// 
+    /// # Example
+    /// ```rust
+    /// let mut file = std::fs::File::create("output.bin")?;
+    /// let written = bus.drain_to_writer(&mut file, None)?;
+    /// ```
+    pub fn drain_to_writer<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        max_messages: Option<usize>,
+    ) -> Result<usize, BusError> {
+        let mut written = 0;
+        while max_messages.is_none_or(|max| written < max) {
+            match self.try_drain_with(LanguageType::Rust, |_, data| writer.write_all(data)) {
+                Ok(Some(result)) => {
+                    result?;
+                    written += 1;
+                }
+                Ok(None) => break,
+                Err(BusError::CorruptedData) => {
+                    eprintln!("[Rust Direct] Discarding message: {}", BusError::CorruptedData);
+                    self.record_drop(&[], DropReason::Corrupted);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Receive data, reporting corrupted messages instead of skipping them
+    /// 
+    /// # Returns
+    /// * `Ok(Some(Vec<u8>))` - Received data
+    /// * `Ok(None)` - No data available
+    /// * `Err(CorruptedData)` - The core reported an implausible message
+    ///   size; the message is discarded
+    pub fn try_receive(&self) -> Result<Option<Vec<u8>>, BusError> {
+        self.try_drain_with(LanguageType::Rust, |_, data| data.to_vec())
+    }
+
+    // Drain the next message and hand it to `f` before the native copy is
+    // freed; corrupted messages are logged and discarded
+    fn drain_with<R>(
+        &self,
+        target: LanguageType,
+        f: impl FnOnce(MessageOrigin, &[u8]) -> R,
+    ) -> Option<R> {
+        match self.try_drain_with(target, f) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[Rust Direct] Discarding message: {}", e);
+                self.record_drop(&[], DropReason::Corrupted);
+                None
+            }
+        }
+    }
+
+    fn try_drain_with<R>(
+        &self,
+        target: LanguageType,
+        f: impl FnOnce(MessageOrigin, &[u8]) -> R,
+    ) -> Result<Option<R>, BusError> {
+        self.ffi_counters.count(|c| &c.drain);
+        let mut source_lang = target;
+        let udata_ptr = unsafe { (self.drain)(self.handle, target, &mut source_lang) };
+
+        if udata_ptr.is_null() {
+            return Ok(None);
+        }
+
+        let udata = unsafe { &*udata_ptr };
+        let origin = MessageOrigin {
+            type_id: udata.type_id,
+            source_lang,
+        };
+        let result = validated_payload(udata, self.max_message_size()).map(|data| f(origin, data));
+
+        self.ffi_counters.count(|c| &c.free_data);
+        unsafe { free_universal_data(udata_ptr) };
+
+        result.map(Some)
+    }
+
+    // No message can be larger than the bus's total capacity, counted
+    // over the segments the core actually created
+    fn max_message_size(&self) -> usize {
+        self.buffer_size.saturating_mul(self.segment_count.max(1) as usize)
+    }
+
+    /// Send a message whose size is fixed at compile time
+    /// 
+    /// Pairs with [`DirectUniversalBus::receive_fixed`] for fixed-layout
+    /// protocols.
+    pub fn send_fixed<const N: usize>(&self, data: &[u8; N], type_id: u32) -> Result<(), BusError> {
+        self.send(data, type_id)
+    }
+
+    /// Receive a message that must be exactly `N` bytes long
+    /// 
+    /// The message is copied straight into a stack array, avoiding the heap
+    /// allocation of `receive`. Returns `None` if no message is available or
+    /// if the next message has a different length; in the latter case the
+    /// message is still consumed and reported as [`DropReason::Malformed`].
+    /// 
+    /// # Example
+    /// ```rust
+    /// if let Some(header) = bus.receive_fixed::<16>() {
+    ///     println!("Header: {:?}", header);
+    /// }
+    /// ```
+    pub fn receive_fixed<const N: usize>(&self) -> Option<[u8; N]> {
+        self.drain_with(LanguageType::Rust, |_, data| {
+            let fixed = <[u8; N]>::try_from(data).ok();
+            if fixed.is_none() {
+                self.record_drop(data, DropReason::Malformed);
+            }
+            fixed
+        })?
+    }
+
+    /// Send data with key-value headers kept separate from the payload
+    /// 
+    /// Headers are serialized into a length-prefixed block at the front of
+    /// the message; read them back with [`DirectUniversalBus::receive_with_headers`].
+    /// Keys and values are limited to 65535 bytes each.
+    /// 
+    /// # Example
+    /// ```rust
+    /// bus.send_with_headers(b"payload", &[("trace-id", "abc123")], 1)?;
+    /// ```
+    pub fn send_with_headers(
+        &self,
+        data: &[u8],
+        headers: &[(&str, &str)],
+        type_id: u32,
+    ) -> Result<(), BusError> {
+        let frame = frame::encode_headers(headers, data).ok_or(BusError::InvalidParams)?;
+        self.send(frame, type_id)
+    }
+
+    /// Receive a message sent with [`DirectUniversalBus::send_with_headers`]
+    /// 
+    /// A message without a valid header block is returned whole as the
+    /// payload with no headers, so header-framed and plain messages should
+    /// not be mixed on the same bus.
+    pub fn receive_with_headers(&self) -> Option<(Vec<u8>, Headers)> {
+        let data = self.receive()?;
+        match frame::decode_headers(&data) {
+            Some((headers, payload)) => Some((payload.to_vec(), headers)),
+            None => Some((data, Vec::new())),
+        }
+    }
+
+    /// Block until a message arrives, spinning before falling back to sleep
+    /// 
+    /// Polls up to `spin_count` times with [`std::hint::spin_loop`] between
+    /// attempts, catching messages that arrive within microseconds, then
+    /// polls every `then_sleep` until one arrives.
+    /// 
+    /// Spinning keeps a core busy for the whole spin phase; it pays off when
+    /// a core can be dedicated to latency. With `spin_count = 0` this is a
+    /// plain sleep-polling receive.
+    pub fn receive_spin(&self, spin_count: u32, then_sleep: std::time::Duration) -> Vec<u8> {
+        for _ in 0..spin_count {
+            if let Some(data) = self.receive() {
+                return data;
+            }
+            std::hint::spin_loop();
+        }
+
+        loop {
+            if let Some(data) = self.receive() {
+                return data;
+            }
+            std::thread::sleep(then_sleep);
+        }
+    }
+
+    /// Send data and wait for a response
+    /// 
+    /// # Arguments
+    /// * `data` - Data to send
+    /// * `type_id` - Type identifier
+    /// * `timeout_ms` - Timeout in milliseconds
+    /// 
+    /// # Returns
+    /// * `Some(Vec<u8>)` - Response data
+    /// * `None` - No response within timeout
+    pub fn send_and_receive<T: AsRef<[u8]>>(
+        &self,
+        data: T,
+        type_id: u32,
+        timeout_ms: u64,
+    ) -> Option<Vec<u8>> {
+        if self.send(data, type_id).is_err() {
+            return None;
+        }
+
+        self.receive_timeout(std::time::Duration::from_millis(timeout_ms))
+    }
+
+    /// Get GPU capabilities information
+    pub fn get_gpu_info(&self) -> GpuInfo {
+        let caps = unsafe { get_gpu_capabilities() };
+        let available = unsafe { gpu_available() };
+
+        GpuInfo {
+            available,
+            has_cuda: caps.has_cuda,
+            has_opencl: caps.has_opencl,
+            has_compute: caps.has_compute,
+            memory_size: caps.memory_size,
+            compute_capability: caps.compute_capability,
+            max_threads: caps.max_threads,
+        }
+    }
+
+    /// Exercise the FFI contract end to end on a throwaway bus
+    /// 
+    /// Creates a small bus, round-trips a known pattern, checks the stats
+    /// reflect it, and tears the bus down. Fails only if no bus can be
+    /// created at all; otherwise the report says which steps worked, which
+    /// is the quickest way to spot a mis-linked or ABI-mismatched core.
+    pub fn self_test() -> Result<SelfTestReport, BusError> {
+        const PATTERN: [u8; 8] = [0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01, 0x7F, 0xFF];
+
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false)?;
+        let mut report = SelfTestReport {
+            created: true,
+            ..SelfTestReport::default()
+        };
+
+        report.sent = bus.send(PATTERN, 0).is_ok();
+        let stats = bus.stats();
+        report.stats_consistent =
+            report.sent && stats.total_messages >= 1 && stats.pending_messages == 1;
+
+        let received = bus.receive();
+        report.received = received.is_some();
+        report.payload_intact = received.as_deref() == Some(&PATTERN[..]);
+        report.destroyed = bus.destroy().is_ok();
+
+        Ok(report)
+    }
+
+    /// Readiness probe combining handle, GPU and backlog checks
+    /// 
+    /// Equivalent to [`DirectUniversalBus::health_with_limit`] with
+    /// [`DEFAULT_HEALTHY_PENDING_LIMIT`].
+    pub fn health(&self) -> HealthStatus {
+        self.health_with_limit(DEFAULT_HEALTHY_PENDING_LIMIT)
+    }
+
+    /// Readiness probe treating more than `pending_limit` queued messages
+    /// as a backlog
+    /// 
+    /// * `Unhealthy` - The handle has been released
+    /// * `Degraded` - GPU acceleration was enabled but is no longer
+    ///   available, or the backlog exceeds `pending_limit`
+    /// * `Healthy` - Otherwise
+    pub fn health_with_limit(&self, pending_limit: u32) -> HealthStatus {
+        let handle_valid = !self.handle.is_null();
+        let gpu_available = self.gpu_enabled && unsafe { gpu_available() };
+        let pending_messages = if handle_valid { self.len() } else { 0 };
+
+        let state = if !handle_valid {
+            HealthState::Unhealthy
+        } else if (self.gpu_enabled && !gpu_available) || pending_messages > pending_limit {
+            HealthState::Degraded
+        } else {
+            HealthState::Healthy
+        };
+
+        HealthStatus {
+            state,
+            handle_valid,
+            gpu_enabled: self.gpu_enabled,
+            gpu_available,
+            pending_messages,
+            pending_limit,
+        }
+    }
+
+    /// Get message counters for this bus
+    /// 
+    /// `pending_messages` includes messages the fair-queue scheduler has
+    /// pulled out of the core but not yet handed to `receive`.
+    pub fn stats(&self) -> BusStats {
+        let core = unsafe { umsbb_get_stats_direct(self.handle) };
+        BusStats {
+            total_messages: core.total_messages,
+            total_bytes: core.total_bytes,
+            pending_messages: core.pending_messages.saturating_add(self.fair_buffered()),
+            active_segments: core.active_segments,
+            dropped: self.drop_stats(),
+        }
+    }
+
+    /// Number of messages waiting to be received
+    pub fn len(&self) -> u32 {
+        self.stats().pending_messages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read back the scaling configuration currently active in the core
+    /// 
+    /// The configuration is process-wide, so this reflects the most recent
+    /// auto-scaled bus created by any caller, not necessarily this one.
+    pub fn scaling_config(&self) -> ScalingConfig {
+        unsafe { get_scaling_config() }
+    }
+
+    /// Get current auto-scaling status
+    pub fn get_scaling_status(&self) -> ScalingStatus {
+        let optimal_producers = unsafe { get_optimal_producer_count() };
+        let optimal_consumers = unsafe { get_optimal_consumer_count() };
+        let gpu_info = self.get_gpu_info();
+
+        ScalingStatus {
+            optimal_producers,
+            optimal_consumers,
+            gpu_info,
+        }
+    }
+
+    /// Trigger manual scale evaluation
+    pub fn trigger_scale_evaluation(&self) {
+        unsafe { trigger_scale_evaluation() };
+    }
+
+    /// Destroy the bus now instead of at scope end
+    /// 
+    /// Consumes the bus and releases the native handle; the subsequent
+    /// `Drop` is a no-op. Returns `InvalidHandle` if the handle was
+    /// already null.
+    pub fn destroy(mut self) -> Result<(), BusError> {
+        if self.release() {
+            Ok(())
+        } else {
+            Err(BusError::InvalidHandle)
+        }
+    }
+
+    // Destroy the native handle if still held, returning whether it was
+    fn release(&mut self) -> bool {
+        if self.handle.is_null() {
+            return false;
+        }
+
+        self.drop_fair_queued();
+        unsafe { umsbb_destroy_direct(self.handle) };
+        self.handle = ptr::null_mut();
+        println!("[Rust Direct] Bus destroyed");
+        true
+    }
+
+    /// Get the number of FFI calls made through this bus
+    ///
+    /// Useful for judging whether a workload is FFI-bound: each `send`
+    /// costs one create/submit/free triple and each `receive` one
+    /// drain (plus a free when data was returned).
+    pub fn ffi_call_stats(&self) -> FfiCallStats {
+        self.ffi_counters.snapshot()
+    }
+}
+
+impl Drop for DirectUniversalBus {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+// View a drained message's payload, rejecting sizes the bus couldn't
+// have produced rather than building an out-of-bounds slice
+fn validated_payload(udata: &UniversalData, limit: usize) -> Result<&[u8], BusError> {
+    if udata.size > limit || (udata.data.is_null() && udata.size > 0) {
+        return Err(BusError::CorruptedData);
+    }
+    if udata.size == 0 {
+        return Ok(&[]);
+    }
+
+    Ok(unsafe { slice::from_raw_parts(udata.data as *const u8, udata.size) })
+}
+
+// A drained message still owned by the core, freed on drop
+#[cfg(feature = "bytes")]
+struct NativeMessage(*mut UniversalData);
+
+// The drained copy is exclusively owned and never mutated
+#[cfg(feature = "bytes")]
+unsafe impl Send for NativeMessage {}
+
+#[cfg(feature = "bytes")]
+impl AsRef<[u8]> for NativeMessage {
+    fn as_ref(&self) -> &[u8] {
+        let udata = unsafe { &*self.0 };
+        // The core may leave `data` null for an empty message
+        if udata.size == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(udata.data as *const u8, udata.size) }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Drop for NativeMessage {
+    fn drop(&mut self) {
+        unsafe { free_universal_data(self.0) };
+    }
+}
+
+// Helper structures for better Rust ergonomics
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub available: bool,
+    pub has_cuda: bool,
+    pub has_opencl: bool,
+    pub has_compute: bool,
+    pub memory_size: usize,
+    pub compute_capability: i32,
+    pub max_threads: usize,
+}
+
+impl GpuInfo {
+    /// The device's compute capability as a comparable version
+    pub fn capability(&self) -> ComputeCapability {
+        ComputeCapability(self.compute_capability)
+    }
+
+    /// Whether an available compute device meets `min_capability`
+    /// 
+    /// `min_capability` uses the core's encoding, `major * 10 + minor`,
+    /// so `70` requires compute capability 7.0 or newer.
+    /// 
+    /// # Example
+    /// ```rust
+    /// if bus.get_gpu_info().supports_compute(70) {
+    ///     dispatch_tensor_kernel();
+    /// }
+    /// ```
+    pub fn supports_compute(&self, min_capability: i32) -> bool {
+        self.available && self.has_compute && self.capability() >= ComputeCapability(min_capability)
+    }
+}
+
+/// A GPU compute capability, encoded as `major * 10 + minor`
+/// 
+/// Orders by version, so capabilities can be compared directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComputeCapability(pub i32);
+
+impl ComputeCapability {
+    pub fn new(major: i32, minor: i32) -> Self {
+        ComputeCapability(major * 10 + minor)
+    }
+
+    pub fn major(&self) -> i32 {
+        self.0 / 10
+    }
+
+    pub fn minor(&self) -> i32 {
+        self.0 % 10
+    }
+}
+
+impl std::fmt::Display for ComputeCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major(), self.minor())
+    }
+}
+
+/// A message from [`DirectUniversalBus::receive_full`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedMessage {
+    pub data: Vec<u8>,
+    pub type_id: u32,
+    pub source_lang: LanguageType,
+}
+
+// Type id and sending language of a drained message. The drained
+// `UniversalData` reports the receiving language, whose allocator owns it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MessageOrigin {
+    pub(crate) type_id: u32,
+    pub(crate) source_lang: LanguageType,
+}
+
+/// Outcome of each step of [`DirectUniversalBus::self_test`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub created: bool,
+    pub sent: bool,
+    pub received: bool,
+    pub payload_intact: bool,
+    pub stats_consistent: bool,
+    pub destroyed: bool,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.created
+            && self.sent
+            && self.received
+            && self.payload_intact
+            && self.stats_consistent
+            && self.destroyed
+    }
+}
+
+/// Per-`type_id` counters from [`DirectUniversalBus::stats_by_type`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+/// Backlog above which [`DirectUniversalBus::health`] reports `Degraded`
+pub const DEFAULT_HEALTHY_PENDING_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthState {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+/// Result of [`DirectUniversalBus::health`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    pub state: HealthState,
+    pub handle_valid: bool,
+    /// GPU acceleration was initialized when the bus was created
+    pub gpu_enabled: bool,
+    /// Only checked when `gpu_enabled`; `false` otherwise
+    pub gpu_available: bool,
+    pub pending_messages: u32,
+    pub pending_limit: u32,
+}
+
+impl HealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        self.state == HealthState::Healthy
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScalingStatus {
+    pub optimal_producers: u32,
+    pub optimal_consumers: u32,
+    pub gpu_info: GpuInfo,
+}
+
+/// Why an [`AutoScalingBus`] is stopping its workers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// Producers stop; consumers drain the bus before exiting
+    Graceful,
+    /// Workers exit as soon as possible, dropping any message in flight
+    Immediate,
+    /// Workers exit after their current message so the pool can be resized
+    Rescale,
+}
+
+// Shared shutdown state: 0 while running, otherwise a ShutdownReason
+#[derive(Debug, Default)]
+struct ShutdownSignal(CachePadded<std::sync::atomic::AtomicU8>);
+
+impl ShutdownSignal {
+    fn reason(&self) -> Option<ShutdownReason> {
+        match self.0.load(Ordering::Acquire) {
+            1 => Some(ShutdownReason::Graceful),
+            2 => Some(ShutdownReason::Immediate),
+            3 => Some(ShutdownReason::Rescale),
+            _ => None,
+        }
+    }
+
+    fn signal(&self, reason: ShutdownReason) {
+        let state = match reason {
+            ShutdownReason::Graceful => 1,
+            ShutdownReason::Immediate => 2,
+            ShutdownReason::Rescale => 3,
+        };
+        self.0.store(state, Ordering::Release);
+    }
+
+    fn reset(&self) {
+        self.0.store(0, Ordering::Release);
+    }
+}
+
+type ShutdownHook = Box<dyn Fn(ShutdownReason) + Send + Sync>;
+type ConsumerFn = Arc<dyn Fn(Vec<u8>, u32) + Send + Sync>;
+
+// Per-worker counters shared between a worker thread and its bus
+#[derive(Debug, Default)]
+struct WorkerCounters {
+    worker_id: u32,
+    processed: CachePadded<AtomicU64>,
+    panics: CachePadded<AtomicU64>,
+    // Set by the worker thread as it returns
+    exited: std::sync::atomic::AtomicBool,
+}
+
+impl WorkerCounters {
+    fn new(worker_id: u32) -> Self {
+        WorkerCounters {
+            worker_id,
+            ..Self::default()
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        !self.exited.load(Ordering::Acquire)
+    }
+
+    fn stat(&self, role: WorkerRole) -> WorkerStat {
+        WorkerStat {
+            worker_id: self.worker_id,
+            role,
+            messages_processed: self.processed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Which side of an [`AutoScalingBus`] a worker runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WorkerRole {
+    Producer,
+    Consumer,
+}
+
+/// Messages handled by one worker, from [`AutoScalingBus::worker_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerStat {
+    /// The id passed to the worker's producer or consumer function
+    pub worker_id: u32,
+    pub role: WorkerRole,
+    /// Messages sent (producers) or handed to the consumer function
+    /// (consumers), including ones it panicked on
+    pub messages_processed: u64,
+}
+
+// Run a consumer callback, containing any panic to the current message
+fn run_isolated<F: FnOnce()>(f: F, worker_id: u32, counters: &WorkerCounters) {
+    if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        counters.panics.fetch_add(1, Ordering::Relaxed);
+        eprintln!("[Rust AutoScale] Consumer {} panicked: {}", worker_id, panic_message(&*payload));
+    }
+}
+
+// The message a panic was raised with, if it was a string
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+// Join a worker, waiting at most `timeout` (forever if None); returns
+// whether it finished
+fn join_within(worker: std::thread::JoinHandle<()>, timeout: Option<std::time::Duration>) -> bool {
+    if let Some(timeout) = timeout {
+        let deadline = std::time::Instant::now() + timeout;
+        while !worker.is_finished() {
+            if std::time::Instant::now() >= deadline {
+                return false; // Dropping the handle detaches the thread
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+    let _ = worker.join();
+    true
+}
+
+/// Outcome of [`AutoScalingBus::stop_timeout`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StopReport {
+    pub producers_joined: usize,
+    pub consumers_joined: usize,
+    /// Producers still running when their timeout expired
+    pub producers_detached: usize,
+    /// Consumers still running when their timeout expired
+    pub consumers_detached: usize,
+}
+
+impl StopReport {
+    /// Whether every worker stopped in time
+    pub fn is_clean(&self) -> bool {
+        self.producers_detached == 0 && self.consumers_detached == 0
+    }
+}
 
 /// Auto-scaling producer-consumer system for Rust
 pub struct AutoScalingBus {
-    bus: DirectUniversalBus,
+    bus: Arc<DirectUniversalBus>,
     producers: Vec<std::thread::JoinHandle<()>>,
     consumers: Vec<std::thread::JoinHandle<()>>,
-    shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    producer_counters: Vec<Arc<WorkerCounters>>,
+    consumer_counters: Vec<Arc<WorkerCounters>>,
+    shutdown: Arc<ShutdownSignal>,
+    on_shutdown: Option<ShutdownHook>,
+    paused: Arc<CachePadded<std::sync::atomic::AtomicBool>>,
+    consumer_idle_timeout: Option<std::time::Duration>,
+    active_consumers: Arc<CachePadded<AtomicU32>>,
+    receive_strategy: ReceiveStrategy,
+    watermarks: Option<(u32, u32)>,
+    // Shared by every consumer and read once per message, so replacing it
+    // takes effect without restarting them
+    consumer_fn: Arc<std::sync::RwLock<Option<ConsumerFn>>>,
+    consumer_latency: Arc<latency::LatencyRecorder>,
+    slow_consumer: latency::SlowConsumerCheck,
+}
+
+impl AutoScalingBus {
+    /// Create a new auto-scaling bus
+    pub fn new(
+        buffer_size: usize,
+        segment_count: u32,
+        gpu_preferred: bool,
+    ) -> Result<Self, BusError> {
+        let bus = DirectUniversalBus::new(buffer_size, segment_count, gpu_preferred, true)?;
+        
+        Ok(AutoScalingBus {
+            bus: Arc::new(bus),
+            producers: Vec::new(),
+            consumers: Vec::new(),
+            producer_counters: Vec::new(),
+            consumer_counters: Vec::new(),
+            shutdown: Arc::new(ShutdownSignal::default()),
+            on_shutdown: None,
+            paused: Arc::new(CachePadded::new(std::sync::atomic::AtomicBool::new(false))),
+            consumer_idle_timeout: None,
+            active_consumers: Arc::new(CachePadded::new(AtomicU32::new(0))),
+            receive_strategy: ReceiveStrategy::default(),
+            watermarks: None,
+            consumer_fn: Arc::new(std::sync::RwLock::new(None)),
+            consumer_latency: Arc::new(latency::LatencyRecorder::default()),
+            slow_consumer: latency::SlowConsumerCheck::default(),
+        })
+    }
+
+    /// Let consumers exit after receiving nothing for `timeout`
+    /// 
+    /// Applies to consumers started after the call. Time spent paused
+    /// doesn't count as idle. Once every consumer has timed out the pool
+    /// has scaled to zero (see [`AutoScalingBus::consumers_scaled_to_zero`])
+    /// and `start_auto_consumers` can be called again when work returns.
+    pub fn set_consumer_idle_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.consumer_idle_timeout = timeout;
+    }
+
+    /// Set how consumers pace polling while the bus is empty
+    /// 
+    /// Applies to consumers started after the call. The default polls
+    /// every 100µs.
+    pub fn set_receive_strategy(&mut self, strategy: ReceiveStrategy) {
+        self.receive_strategy = strategy;
+    }
+
+    /// Throttle producers while the backlog is high
+    /// 
+    /// A producer stops calling `producer_fn` once `pending_messages`
+    /// reaches `high` and resumes only after it falls below `low`, so the
+    /// backlog settles between the two instead of oscillating at the
+    /// buffer's full boundary. `low` is capped at `high`. Applies to
+    /// producers started after the call.
+    pub fn set_watermarks(&mut self, high: u32, low: u32) {
+        self.watermarks = Some((high, low.min(high)));
+    }
+
+    /// Flag consumer invocations that take longer than `threshold`
+    /// 
+    /// Each slow invocation is logged, or passed to the hook set with
+    /// [`AutoScalingBus::on_slow_consumer`]. Applies to consumers started
+    /// after the call; `None` turns the check off.
+    pub fn set_slow_consumer_threshold(&mut self, threshold: Option<std::time::Duration>) {
+        self.slow_consumer.threshold = threshold;
+    }
+
+    /// Call `f` with the worker id and duration of each slow invocation
+    /// instead of logging it
+    pub fn on_slow_consumer<F>(&mut self, f: F)
+    where
+        F: Fn(u32, std::time::Duration) + Send + Sync + 'static,
+    {
+        self.slow_consumer.hook = Some(Arc::new(f));
+    }
+
+    /// Durations of `consumer_fn` invocations across all consumers
+    /// 
+    /// A rising `p99` is an early sign of a handler that can't keep up,
+    /// well before producers start seeing `BufferFull`.
+    pub fn consumer_latency_stats(&self) -> LatencyStats {
+        self.consumer_latency.stats()
+    }
+
+    /// Number of consumer workers still running
+    pub fn active_consumer_count(&self) -> u32 {
+        self.active_consumers.load(Ordering::Relaxed)
+    }
+
+    /// Whether consumers were started and have all exited on idle timeout
+    pub fn consumers_scaled_to_zero(&self) -> bool {
+        !self.consumers.is_empty() && self.active_consumer_count() == 0
+    }
+
+    /// Start auto-scaling producers
+    /// 
+    /// # Arguments
+    /// * `producer_fn` - Function that generates data
+    /// * `count` - Number of producers (None = auto-determine)
+    pub fn start_auto_producers<F>(&mut self, producer_fn: F, count: Option<u32>)
+    where
+        F: Fn(u32) -> Option<Vec<u8>> + Send + Sync + Clone + 'static,
+    {
+        let count = count.unwrap_or_else(|| self.bus.get_scaling_status().optimal_producers);
+
+        for worker_id in 0..count {
+            let bus = Arc::clone(&self.bus);
+            let producer_fn = producer_fn.clone();
+            let shutdown = self.shutdown.clone();
+            let watermarks = self.watermarks;
+            let counters = Arc::new(WorkerCounters::new(worker_id));
+            self.producer_counters.push(Arc::clone(&counters));
+
+            let producer = std::thread::spawn(move || {
+                let mut throttled = false;
+                while shutdown.reason().is_none() {
+                    if let Some((high, low)) = watermarks {
+                        let pending = bus.stats().pending_messages;
+                        if pending >= high {
+                            throttled = true;
+                        } else if pending < low {
+                            throttled = false;
+                        }
+                        if throttled {
+                            std::thread::sleep(std::time::Duration::from_millis(1));
+                            continue;
+                        }
+                    }
+
+                    if let Some(data) = producer_fn(worker_id) {
+                        // Wait out a full buffer; stopping interrupts the
+                        // wait, including after this worker is detached
+                        let stopped = || shutdown.reason().is_some();
+                        match bus.send_blocking_until(&data, worker_id, stopped) {
+                            Ok(()) => {
+                                counters.processed.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(BusError::ShuttingDown) => break,
+                            Err(_) => {}
+                        }
+                    }
+                    std::thread::sleep(std::time::Duration::from_micros(100));
+                }
+                counters.exited.store(true, Ordering::Release);
+            });
+
+            self.producers.push(producer);
+        }
+
+        println!("Started {} auto-scaling producers", count);
+    }
+
+    /// Start auto-scaling consumers
+    /// 
+    /// A panic in `consumer_fn` is caught and logged, the message being
+    /// processed is dropped, and the worker carries on with the next one.
+    /// See [`AutoScalingBus::consumer_panic_counts`].
+    /// 
+    /// All consumers share one function: starting more consumers installs
+    /// `consumer_fn` for the ones already running too, as does
+    /// [`AutoScalingBus::set_consumer_fn`].
+    /// 
+    /// # Arguments
+    /// * `consumer_fn` - Function that processes data
+    /// * `count` - Number of consumers (None = auto-determine)
+    pub fn start_auto_consumers<F>(&mut self, consumer_fn: F, count: Option<u32>)
+    where
+        F: Fn(Vec<u8>, u32) + Send + Sync + Clone + 'static,
+    {
+        let count = count.unwrap_or_else(|| self.bus.get_scaling_status().optimal_consumers);
+        self.set_consumer_fn(consumer_fn);
+
+        for worker_id in 0..count {
+            let bus = Arc::clone(&self.bus);
+            let consumer_fn = Arc::clone(&self.consumer_fn);
+            let shutdown = self.shutdown.clone();
+            let paused = self.paused.clone();
+            let counters = Arc::new(WorkerCounters::new(worker_id));
+            self.consumer_counters.push(Arc::clone(&counters));
+            let idle_timeout = self.consumer_idle_timeout;
+            let active = Arc::clone(&self.active_consumers);
+            active.fetch_add(1, Ordering::Relaxed);
+            let mut poller = poll::Poller::new(self.receive_strategy);
+            let latency = Arc::clone(&self.consumer_latency);
+            let slow_consumer = self.slow_consumer.clone();
+
+            let consumer = std::thread::spawn(move || {
+                let mut last_active = std::time::Instant::now();
+                loop {
+                    let reason = shutdown.reason();
+                    if matches!(reason, Some(ShutdownReason::Immediate | ShutdownReason::Rescale)) {
+                        break;
+                    }
+
+                    // A pending shutdown overrides a pause so stop() can't hang
+                    if reason.is_none() && paused.load(Ordering::Relaxed) {
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        last_active = std::time::Instant::now();
+                        continue;
+                    }
+
+                    let received = bus.receive();
+                    poller.observe(received.is_some());
+                    if let Some(data) = received {
+                        // An immediate stop abandons the message in flight
+                        if shutdown.reason() == Some(ShutdownReason::Immediate) {
+                            bus.record_drop(&data, DropReason::Unflushed);
+                            break;
+                        }
+                        let started = std::time::Instant::now();
+                        let consumer_fn = consumer_fn
+                            .read()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .clone();
+                        let Some(consumer_fn) = consumer_fn else { continue };
+                        run_isolated(|| consumer_fn(data, worker_id), worker_id, &counters);
+                        counters.processed.fetch_add(1, Ordering::Relaxed);
+                        let elapsed = started.elapsed();
+                        latency.record(elapsed);
+                        slow_consumer.check(worker_id, elapsed);
+                        last_active = std::time::Instant::now();
+                    } else if reason == Some(ShutdownReason::Graceful) {
+                        break; // Drained
+                    } else if idle_timeout.is_some_and(|t| last_active.elapsed() >= t) {
+                        println!("[Rust AutoScale] Consumer {} idle, exiting", worker_id);
+                        break;
+                    } else {
+                        std::thread::sleep(poller.interval());
+                    }
+                }
+                counters.exited.store(true, Ordering::Release);
+                active.fetch_sub(1, Ordering::Relaxed);
+            });
+
+            self.consumers.push(consumer);
+        }
+
+        println!("Started {} auto-scaling consumers", count);
+    }
+
+    /// Replace the function running consumers process messages with
+    /// 
+    /// Each consumer picks up `consumer_fn` on its next message; a message
+    /// already being processed finishes with the old function.
+    pub fn set_consumer_fn<F>(&self, consumer_fn: F)
+    where
+        F: Fn(Vec<u8>, u32) + Send + Sync + 'static,
+    {
+        *self.consumer_fn.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(consumer_fn));
+    }
+
+    /// Stop consumers pulling messages without tearing them down
+    /// 
+    /// Consumers finish their current message and then idle, letting the
+    /// buffer back up so producers see natural backpressure. Producers are
+    /// not affected.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Let paused consumers pull messages again
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Number of panics caught in each running consumer, in start order
+    pub fn consumer_panic_counts(&self) -> Vec<u64> {
+        self.consumer_counters
+            .iter()
+            .map(|c| c.panics.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Messages handled by each running worker, producers first, each in
+    /// start order
+    /// 
+    /// Comparing the counts shows whether load is spread evenly or a few
+    /// workers do most of the work, which the bus-wide stats can't.
+    /// Workers that have exited on their own, such as consumers past their
+    /// idle timeout, are left out.
+    pub fn worker_stats(&self) -> Vec<WorkerStat> {
+        let producers = self.producer_counters.iter().map(|c| (c, WorkerRole::Producer));
+        let consumers = self.consumer_counters.iter().map(|c| (c, WorkerRole::Consumer));
+        producers
+            .chain(consumers)
+            .filter(|(c, _)| c.is_running())
+            .map(|(c, role)| c.stat(role))
+            .collect()
+    }
+
+    /// Register a callback run after workers stop, with the stop reason
+    pub fn on_shutdown<F>(&mut self, f: F)
+    where
+        F: Fn(ShutdownReason) + Send + Sync + 'static,
+    {
+        self.on_shutdown = Some(Box::new(f));
+    }
+
+    /// Stop all producers and consumers, draining pending messages first
+    pub fn stop(&mut self) {
+        self.stop_with(ShutdownReason::Graceful);
+    }
+
+    /// Stop all producers and consumers for the given reason
+    /// 
+    /// Once every worker has been joined the bus can be started again,
+    /// e.g. with a different worker count after a `Rescale`.
+    pub fn stop_with(&mut self, reason: ShutdownReason) {
+        self.shutdown_workers(reason, None);
+    }
+
+    /// Stop gracefully, giving up on workers that don't finish in time
+    /// 
+    /// Each worker gets up to `per_worker` to exit. Workers still running
+    /// after that are detached and counted in the report; they keep seeing
+    /// the stop signal, so they exit on their own if they ever get unstuck.
+    pub fn stop_timeout(&mut self, per_worker: std::time::Duration) -> StopReport {
+        self.shutdown_workers(ShutdownReason::Graceful, Some(per_worker))
+    }
+
+    fn shutdown_workers(
+        &mut self,
+        reason: ShutdownReason,
+        per_worker: Option<std::time::Duration>,
+    ) -> StopReport {
+        let mut report = StopReport::default();
+        if self.producers.is_empty() && self.consumers.is_empty() {
+            return report;
+        }
+
+        self.shutdown.signal(reason);
+
+        // Producers may be blocked on a full buffer that no consumer will
+        // drain; wake them so the joins below can't hang
+        self.bus.interrupt_blocking_sends(true);
+        while let Some(producer) = self.producers.pop() {
+            if join_within(producer, per_worker) {
+                report.producers_joined += 1;
+            } else {
+                report.producers_detached += 1;
+            }
+        }
+        self.bus.interrupt_blocking_sends(false);
+
+        while let Some(consumer) = self.consumers.pop() {
+            if join_within(consumer, per_worker) {
+                report.consumers_joined += 1;
+            } else {
+                report.consumers_detached += 1;
+            }
+        }
+        self.producer_counters.clear();
+        self.consumer_counters.clear();
+
+        if report.is_clean() {
+            self.shutdown.reset();
+        } else {
+            // Detached workers hold the old signal; leave it raised for them
+            self.shutdown = Arc::new(ShutdownSignal::default());
+            eprintln!(
+                "[Rust AutoScale] Detached {} producers and {} consumers that didn't stop in time",
+                report.producers_detached, report.consumers_detached
+            );
+        }
+        if let Some(hook) = &self.on_shutdown {
+            hook(reason);
+        }
+
+        println!("[Rust AutoScale] Stopped all workers ({:?})", reason);
+        report
+    }
 }
 
-impl AutoScalingBus {
-    /// Create a new auto-scaling bus
-    pub fn new(
-        buffer_size: usize,
-        segment_count: u32,
-        gpu_preferred: bool,
-    ) -> Result<Self, String> {
-        let bus = DirectUniversalBus::new(buffer_size, segment_count, gpu_preferred, true)?;
-        
-        Ok(AutoScalingBus {
-            bus,
-            producers: Vec::new(),
-            consumers: Vec::new(),
-            shutdown: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
-        })
+// Workers hold clones of the bus, so they are joined before the last
+// reference to it is released.
+impl Drop for AutoScalingBus {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Example usage and tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direct_bus_creation() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false);
+        assert!(bus.is_ok());
+    }
+
+    #[test]
+    fn test_send_receive() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        
+        // Send test data
+        assert!(bus.send("Hello Rust!", 1).is_ok());
+        assert!(bus.send([1, 2, 3, 4], 2).is_ok());
+        
+        // Receive data
+        if let Some(data) = bus.receive() {
+            println!("Received: {:?}", String::from_utf8_lossy(&data));
+        }
+    }
+
+    #[test]
+    fn test_gpu_info() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, true, false).unwrap();
+        let gpu_info = bus.get_gpu_info();
+        println!("GPU Info: {:?}", gpu_info);
+    }
+
+    #[test]
+    fn test_explicit_destroy() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        assert_eq!(bus.destroy(), Ok(()));
+    }
+
+    #[test]
+    fn test_bus_registry() {
+        let registry = BusRegistry::new();
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        registry.register("tenant-a", bus);
+
+        let bus = registry.get("tenant-a").unwrap();
+        assert!(bus.send("hello", 1).is_ok());
+        assert!(registry.get("tenant-b").is_none());
+        assert_eq!(registry.len(), 1);
+
+        assert!(registry.remove("tenant-a"));
+        assert!(!registry.remove("tenant-a"));
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_registry_aggregate_stats() {
+        let registry = BusRegistry::new();
+        let a = registry.register("a", DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+        let b = registry.register("b", DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+        a.send(b"one", 0).unwrap();
+        b.send(b"two", 0).unwrap();
+        b.send(b"three", 0).unwrap();
+
+        let per_bus = registry.stats_per_bus();
+        assert_eq!(per_bus.len(), 2);
+        assert_eq!(per_bus[0].0, "a");
+        assert_eq!(per_bus[1].1.pending_messages, 2);
+
+        let total = registry.aggregate_stats();
+        assert_eq!(total.pending_messages, 3);
+        assert_eq!(total.total_messages, per_bus[0].1.total_messages + per_bus[1].1.total_messages);
+    }
+
+    #[test]
+    fn test_headers_roundtrip() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        let headers = [("trace-id", "abc123"), ("content-type", "text/plain")];
+        assert!(bus.send_with_headers(b"payload", &headers, 1).is_ok());
+
+        let (payload, received) = bus.receive_with_headers().unwrap();
+        assert_eq!(payload, b"payload");
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], ("trace-id".to_string(), "abc123".to_string()));
+        assert_eq!(received[1].1, "text/plain");
+    }
+
+    #[test]
+    fn test_bus_selector() {
+        let idle = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        let busy = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        let mut selector = BusSelector::new(&[&idle, &busy]);
+
+        let timeout = std::time::Duration::from_millis(5);
+        assert_eq!(selector.select(timeout), None);
+
+        busy.send("ready", 1).unwrap();
+        assert_eq!(selector.select(timeout), Some(1));
+        idle.send("ready", 1).unwrap();
+        // Scanning resumes after the last ready bus
+        assert_eq!(selector.select(timeout), Some(0));
+        assert_eq!(selector.select(timeout), Some(1));
+    }
+
+    #[test]
+    fn test_fixed_size_messages() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        bus.send_fixed(&[7u8; 4], 1).unwrap();
+        assert_eq!(bus.receive_fixed::<4>(), Some([7u8; 4]));
+
+        bus.send_fixed(&[7u8; 4], 1).unwrap();
+        assert_eq!(bus.receive_fixed::<8>(), None);
+        assert!(bus.receive().is_none());
+    }
+
+    #[test]
+    fn test_self_test() {
+        let report = DirectUniversalBus::self_test().unwrap();
+        assert!(report.passed(), "{:?}", report);
+    }
+
+    #[test]
+    fn test_send_with_retry() {
+        // The core's capacity counts message headers as well as bytes, so
+        // cap the bus at one message to make the second send fail
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false)
+            .unwrap()
+            .with_message_capacity(1);
+        let backoff = std::time::Duration::from_millis(1);
+
+        assert!(bus.send_with_retry([0u8; 16], 0, 3, backoff).is_ok());
+        assert_eq!(bus.send_with_retry([0u8; 16], 0, 2, backoff), Err(BusError::BufferFull));
+
+        let mut destroyed = DirectUniversalBus::new(16, 1, false, false).unwrap();
+        destroyed.release();
+        assert_eq!(destroyed.send_with_retry(b"x", 0, 3, backoff), Err(BusError::InvalidHandle));
+    }
+
+    #[test]
+    fn test_receive_as() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        bus.send("polyglot", 1).unwrap();
+        assert_eq!(bus.receive_as(LanguageType::Go).unwrap(), b"polyglot");
+    }
+
+    #[test]
+    fn test_graceful_shutdown_drains() {
+        use std::sync::atomic::AtomicU32;
+
+        let mut auto = AutoScalingBus::new(1024 * 1024, 4, false).unwrap();
+        for i in 0..10u8 {
+            auto.bus.send([i], 1).unwrap();
+        }
+
+        let consumed = Arc::new(AtomicU32::new(0));
+        let stopped_with = Arc::new(Mutex::new(None));
+
+        let counter = Arc::clone(&consumed);
+        auto.start_auto_consumers(
+            move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            },
+            Some(1),
+        );
+        let slot = Arc::clone(&stopped_with);
+        auto.on_shutdown(move |reason| *slot.lock().unwrap() = Some(reason));
+
+        auto.stop();
+        assert_eq!(consumed.load(Ordering::SeqCst), 10);
+        assert_eq!(*stopped_with.lock().unwrap(), Some(ShutdownReason::Graceful));
+    }
+
+    #[test]
+    fn test_immediate_stop_accounts_for_messages() {
+        use std::sync::atomic::AtomicU32;
+
+        let mut auto = AutoScalingBus::new(1024 * 1024, 4, false).unwrap();
+        for i in 0..200u8 {
+            auto.bus.send([i], 1).unwrap();
+        }
+
+        let unflushed = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&unflushed);
+        auto.bus.on_drop(move |_, reason| {
+            assert_eq!(reason, DropReason::Unflushed);
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let consumed = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&consumed);
+        auto.start_auto_consumers(
+            move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_micros(200));
+            },
+            Some(4),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        auto.stop_with(ShutdownReason::Immediate);
+
+        // Every message was either processed, reported or left queued
+        let consumed = consumed.load(Ordering::SeqCst);
+        let unflushed = unflushed.load(Ordering::SeqCst);
+        assert_eq!(consumed + unflushed + auto.bus.len(), 200);
+    }
+
+    #[test]
+    fn test_abi_check() {
+        assert_eq!(DirectUniversalBus::check_abi(), Ok(()));
+
+        let err = BusError::AbiMismatch { expected: 1, found: 2 };
+        assert_eq!(err.to_string(), "Core ABI version mismatch (expected 1, found 2)");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_drain_as() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct LogRecord {
+            level: String,
+            code: u32,
+        }
+
+        let bus = DirectUniversalBus::new(1024 * 1024, 1, false, false).unwrap();
+        bus.send(r#"{"level":"warn","code":7}"#, 0).unwrap();
+        bus.send("not json", 0).unwrap();
+        bus.send(r#"{"level":"info","code":1}"#, 0).unwrap();
+
+        let records: Vec<_> = bus.drain_as::<LogRecord>().collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap().code, 7);
+        assert!(matches!(records[1], Err(DecodeError::Malformed(_))));
+        assert_eq!(records[2].as_ref().unwrap().level, "info");
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let mut auto = AutoScalingBus::new(1024 * 1024, 4, false).unwrap();
+        auto.pause();
+        assert!(auto.is_paused());
+        auto.start_auto_consumers(|_, _| {}, Some(1));
+
+        auto.bus.send("held", 1).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(auto.bus.stats().pending_messages, 1);
+
+        auto.resume();
+        let start = std::time::Instant::now();
+        while auto.bus.stats().pending_messages > 0 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(1));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        auto.stop();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_receive_auto() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 1, false, false).unwrap();
+        bus.send_tagged(WireFormat::Json, b"[1,2,3]", 0).unwrap();
+        bus.send_tagged(WireFormat::Raw, b"raw", 0).unwrap();
+        bus.send([9u8, 0], 0).unwrap();
+
+        assert_eq!(bus.receive_auto::<Vec<u32>>(), Some(Ok(vec![1, 2, 3])));
+        assert_eq!(bus.receive_auto::<Vec<u32>>(), Some(Err(DecodeError::UnsupportedFormat(0))));
+        assert_eq!(bus.receive_auto::<Vec<u32>>(), Some(Err(DecodeError::UnsupportedFormat(9))));
+        assert_eq!(bus.receive_auto::<Vec<u32>>(), None);
+
+        #[cfg(feature = "bincode")]
+        {
+            let body = bincode::serialize(&vec![4u32, 5]).unwrap();
+            bus.send_tagged(WireFormat::Bincode, &body, 0).unwrap();
+            assert_eq!(bus.receive_auto::<Vec<u32>>(), Some(Ok(vec![4, 5])));
+        }
+    }
+
+    #[test]
+    fn test_consumer_panic_isolation() {
+        use std::sync::atomic::AtomicU32;
+
+        let mut auto = AutoScalingBus::new(1024 * 1024, 1, false).unwrap();
+        for message in ["ok", "boom", "ok"] {
+            auto.bus.send(message, 0).unwrap();
+        }
+
+        let processed = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&processed);
+        auto.start_auto_consumers(
+            move |data, _| {
+                assert_ne!(data, b"boom", "bad message");
+                counter.fetch_add(1, Ordering::SeqCst);
+            },
+            Some(1),
+        );
+
+        let start = std::time::Instant::now();
+        while processed.load(Ordering::SeqCst) < 2 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(1));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(auto.consumer_panic_counts(), vec![1]);
+        auto.stop();
+    }
+
+    #[test]
+    fn test_receive_spin() {
+        let bus = Arc::new(DirectUniversalBus::new(1024 * 1024, 1, false, false).unwrap());
+        let producer = Arc::clone(&bus);
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            producer.send("late", 0).unwrap();
+        });
+
+        let data = bus.receive_spin(1000, std::time::Duration::from_micros(50));
+        assert_eq!(data, b"late");
+        handle.join().unwrap();
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_receive_bytes() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 1, false, false).unwrap();
+        bus.send("zero-copy", 0).unwrap();
+
+        let data = bus.receive_bytes().unwrap();
+        let tail = data.slice(5..);
+        drop(data);
+        assert_eq!(&tail[..], b"copy");
+        assert!(bus.receive_bytes().is_none());
+
+        bus.send(b"", 0).unwrap();
+        assert_eq!(&bus.receive_bytes().unwrap()[..], b"");
+
+        // Fair drain order applies here too
+        bus.send([0, 0], 0).unwrap();
+        bus.send([0, 1], 0).unwrap();
+        bus.send([1, 0], 1).unwrap();
+        bus.set_fair_drain(true);
+        let order: Vec<bytes::Bytes> = std::iter::from_fn(|| bus.receive_bytes()).collect();
+        assert_eq!(order, vec![&[0, 0][..], &[1, 0], &[0, 1]]);
+    }
+
+    #[test]
+    fn test_from_env() {
+        let vars: std::collections::HashMap<&str, &str> = [
+            ("UMSBB_BUFFER_SIZE", "65536"),
+            ("UMSBB_SEGMENT_COUNT", "2"),
+            ("UMSBB_AUTO_SCALE", "yes"),
+            ("UMSBB_MAX_CONSUMERS", "3"),
+        ]
+        .into_iter()
+        .collect();
+        let bus = DirectUniversalBus::from_lookup(|name| vars.get(name).map(|v| v.to_string()));
+        assert!(bus.is_ok());
+
+        let err = DirectUniversalBus::from_lookup(|name| {
+            (name == "UMSBB_GPU_PREFERRED").then(|| "maybe".to_string())
+        });
+        assert_eq!(
+            err.err(),
+            Some(BusError::InvalidEnv {
+                name: "UMSBB_GPU_PREFERRED".to_string(),
+                value: "maybe".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_inflated_size_is_rejected() {
+        // A core that reports more bytes than it drained
+        unsafe extern "C" fn inflating_drain(
+            handle: *mut c_void,
+            target_lang: LanguageType,
+            source_lang: *mut LanguageType,
+        ) -> *mut UniversalData {
+            let udata = umsbb_drain_direct_ex(handle, target_lang, source_lang);
+            if !udata.is_null() {
+                (*udata).size = usize::MAX / 2;
+            }
+            udata
+        }
+
+        let mut bus = DirectUniversalBus::new(1024, 1, false, false).unwrap();
+        bus.drain = inflating_drain;
+        let corrupted = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&corrupted);
+        bus.on_drop(move |data, reason| {
+            assert_eq!((data, reason), (&[][..], DropReason::Corrupted));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        bus.send(b"tiny", 0).unwrap();
+        assert_eq!(bus.try_receive(), Err(BusError::CorruptedData));
+        bus.send(b"tiny", 0).unwrap();
+        assert_eq!(bus.receive(), None);
+        #[cfg(feature = "bytes")]
+        {
+            bus.send(b"tiny", 0).unwrap();
+            assert!(bus.receive_bytes().is_none());
+        }
+        assert!(bus.is_empty());
+        // try_receive hands the error back instead of reporting a drop
+        let reported = if cfg!(feature = "bytes") { 2 } else { 1 };
+        assert_eq!(bus.drop_stats().corrupted, reported);
+        assert_eq!(corrupted.load(Ordering::SeqCst), reported);
+
+        // Sends are held to the same limit, so they can't produce such a message
+        assert_eq!(bus.max_message_size(), 1024);
+        assert_eq!(bus.send(vec![0u8; 1025], 0), Err(BusError::InvalidParams));
+
+        // The limit counts the segments the core picked for a count of 0
+        let bus = DirectUniversalBus::new(1024, 0, false, false).unwrap();
+        let limit = 1024 * bus.segment_count as usize;
+        assert!(bus.segment_count > 1);
+        assert_eq!(bus.max_message_size(), limit);
+        assert!(bus.send(vec![0u8; limit], 0).is_ok());
+        assert_eq!(bus.send(vec![0u8; limit + 1], 0), Err(BusError::InvalidParams));
+    }
+
+    #[test]
+    fn test_len_is_empty() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        assert!(bus.is_empty());
+
+        bus.send("one", 1).unwrap();
+        bus.send("two", 2).unwrap();
+        assert_eq!(bus.len(), 2);
+        assert!(!bus.is_empty());
+    }
+
+    #[test]
+    fn test_sequence_tracker() {
+        let mut tracker = SequenceTracker::starting_at(0);
+        assert_eq!(tracker.observe(0), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(1), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(4), SequenceEvent::Gap { missing: 2 });
+        assert_eq!(tracker.observe(2), SequenceEvent::Reordered);
+        assert_eq!(tracker.observe(2), SequenceEvent::Duplicate);
+        assert_eq!(tracker.observe(5), SequenceEvent::InOrder);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.received, 6);
+        assert_eq!(stats.gaps, 1);
+        assert_eq!(stats.missing, 1);
+        assert_eq!(stats.reordered, 1);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(tracker.next_expected(), Some(6));
+    }
+
+    #[test]
+    fn test_sequence_tracker_wraparound() {
+        let last = u32::MAX as u64;
+        let mut tracker = SequenceTracker::starting_at(last - 1).with_sequence_bits(32);
+        assert_eq!(tracker.observe(last - 1), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(last), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(0), SequenceEvent::InOrder);
+        assert_eq!(tracker.next_expected(), Some(1));
+
+        // A gap spanning the wrap, filled in late from both sides
+        let mut tracker = SequenceTracker::starting_at(last - 1).with_sequence_bits(32);
+        assert_eq!(tracker.observe(2), SequenceEvent::Gap { missing: 4 });
+        assert_eq!(tracker.observe(last), SequenceEvent::Reordered);
+        assert_eq!(tracker.observe(1), SequenceEvent::Reordered);
+        assert_eq!(tracker.observe(1), SequenceEvent::Duplicate);
+        assert_eq!(tracker.observe(last - 5), SequenceEvent::Duplicate);
+        assert_eq!(tracker.stats().missing, 2);
+
+        // A 64-bit tracker would see the same wrap as a huge jump back
+        let mut wide = SequenceTracker::starting_at(last);
+        wide.observe(last);
+        assert_eq!(wide.observe(0), SequenceEvent::Duplicate);
+
+        // ...but follows its own counter past u64::MAX
+        let mut wide = SequenceTracker::starting_at(u64::MAX);
+        assert_eq!(wide.observe(u64::MAX), SequenceEvent::InOrder);
+        assert_eq!(wide.observe(1), SequenceEvent::Gap { missing: 1 });
+        assert_eq!(wide.observe(0), SequenceEvent::Reordered);
+
+        // The width survives a reset
+        tracker.reset();
+        tracker.observe(last);
+        assert_eq!(tracker.observe(0), SequenceEvent::InOrder);
+    }
+
+    #[test]
+    fn test_send_from_reader() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        let input: Vec<u8> = (0..250u8).collect();
+
+        let sent = bus.send_from_reader(&mut &input[..], 100, 1).unwrap();
+        assert_eq!(sent, 250);
+
+        let chunks: Vec<Vec<u8>> = std::iter::from_fn(|| bus.receive()).collect();
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![100, 100, 50]);
+        assert_eq!(chunks.concat(), input);
+        assert_eq!(bus.send_from_reader(&mut &input[..], 0, 1), Err(BusError::InvalidParams));
+    }
+
+    #[test]
+    fn test_drain_to_writer() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        for part in [&b"alpha"[..], b"beta", b"gamma"] {
+            bus.send(part, 1).unwrap();
+        }
+
+        let mut out = Vec::new();
+        assert_eq!(bus.drain_to_writer(&mut out, Some(2)).unwrap(), 2);
+        assert_eq!(out, b"alphabeta");
+        assert_eq!(bus.drain_to_writer(&mut out, None).unwrap(), 1);
+        assert_eq!(out, b"alphabetagamma");
+        assert_eq!(bus.drain_to_writer(&mut out, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_segment_strategy() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_segment_strategy(SegmentStrategy::RoundRobin);
+        for _ in 0..4 {
+            bus.send(b"x", 0).unwrap();
+        }
+        // Every segment got one message despite the shared type id
+        assert_eq!(bus.stats().active_segments, 4);
+
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_segment_strategy(SegmentStrategy::HashTypeId);
+        for _ in 0..8 {
+            bus.send(b"x", 42).unwrap();
+        }
+        assert_eq!(bus.stats().active_segments, 1);
+
+        let bus = DirectUniversalBus::new(64 * 1024, 2, false, false)
+            .unwrap()
+            .with_segment_strategy(SegmentStrategy::LeastFull);
+        bus.send(vec![0u8; 100], 0).unwrap();
+        bus.send(b"small", 0).unwrap();
+        assert_eq!(bus.stats().active_segments, 2);
+
+        // With the segment count left to the core, strategies still apply
+        let bus = DirectUniversalBus::new(64 * 1024, 0, false, false)
+            .unwrap()
+            .with_segment_strategy(SegmentStrategy::RoundRobin);
+        assert!(bus.segment_count > 1);
+        for _ in 0..bus.segment_count {
+            bus.send(b"x", 0).unwrap();
+        }
+        assert_eq!(bus.stats().active_segments, bus.segment_count);
+    }
+
+    #[test]
+    fn test_raw_handle_roundtrip() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        bus.send(b"kept", 1).unwrap();
+
+        let handle = bus.into_raw_handle();
+        assert!(!handle.is_null());
+
+        // The message survives because dropping the first wrapper didn't
+        // destroy the handle
+        let bus = unsafe { DirectUniversalBus::from_raw_handle(handle, 64 * 1024) };
+        assert_eq!(bus.receive(), Some(b"kept".to_vec()));
+        assert!(bus.destroy().is_ok());
+    }
+
+    #[test]
+    fn test_coalescing_bus() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        let coalescing = CoalescingBus::new(bus, 64, std::time::Duration::from_secs(60));
+
+        // 3 entries of 8 + 12 bytes fit in 64; the fourth forces a flush
+        for i in 0..4u8 {
+            coalescing.send([i; 12], 7).unwrap();
+        }
+        assert_eq!(coalescing.inner().len(), 1);
+        assert_eq!(coalescing.pending_bytes(), 20);
+
+        coalescing.flush().unwrap();
+        assert_eq!(coalescing.inner().len(), 2);
+
+        let received: Vec<_> = std::iter::from_fn(|| coalescing.receive_with_type()).collect();
+        assert_eq!(received.len(), 4);
+        assert!(received.iter().enumerate().all(|(i, (t, d))| *t == 7 && *d == [i as u8; 12]));
+        assert_eq!(coalescing.send([0u8; 100], 7), Err(BusError::InvalidParams));
+    }
+
+    #[test]
+    fn test_coalescing_flush_on_drop() {
+        type Log = Arc<Mutex<Vec<(Vec<u8>, Option<DropReason>)>>>;
+
+        // Batches reach the bus through its validator; discards through the drop hook
+        fn observed_bus(log: &Log) -> DirectUniversalBus {
+            let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+            let submitted = Arc::clone(log);
+            bus.register_validator(7, move |batch| {
+                let entries = frame::decode_batch(batch).unwrap();
+                let mut log = submitted.lock().unwrap();
+                log.extend(entries.into_iter().map(|(_, data)| (data.to_vec(), None)));
+                Ok(())
+            });
+            let dropped = Arc::clone(log);
+            bus.on_drop(move |data, reason| dropped.lock().unwrap().push((data.to_vec(), Some(reason))));
+            bus
+        }
+
+        let log: Log = Arc::default();
+        let coalescing = CoalescingBus::new(observed_bus(&log), 64, std::time::Duration::from_secs(60));
+        coalescing.send(b"first", 7).unwrap();
+        coalescing.send(b"second", 7).unwrap();
+        drop(coalescing);
+        assert_eq!(*log.lock().unwrap(), vec![(b"first".to_vec(), None), (b"second".to_vec(), None)]);
+
+        let log: Log = Arc::default();
+        let coalescing = CoalescingBus::new(observed_bus(&log), 64, std::time::Duration::from_secs(60))
+            .with_flush_on_drop(false);
+        coalescing.send(b"first", 7).unwrap();
+        coalescing.send(b"second", 7).unwrap();
+        drop(coalescing);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                (b"first".to_vec(), Some(DropReason::Unflushed)),
+                (b"second".to_vec(), Some(DropReason::Unflushed)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_health() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        let health = bus.health();
+        assert!(health.is_healthy());
+        assert!(health.handle_valid);
+        assert!(!health.gpu_enabled);
+
+        bus.send(b"one", 0).unwrap();
+        bus.send(b"two", 0).unwrap();
+        let health = bus.health_with_limit(1);
+        assert_eq!(health.state, HealthState::Degraded);
+        assert_eq!(health.pending_messages, 2);
+    }
+
+    #[test]
+    fn test_consumer_idle_timeout() {
+        let mut auto_bus = AutoScalingBus::new(64 * 1024, 4, false).unwrap();
+        auto_bus.set_consumer_idle_timeout(Some(std::time::Duration::from_millis(20)));
+        auto_bus.start_auto_consumers(|_, _| {}, Some(2));
+        assert!(!auto_bus.consumers_scaled_to_zero());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while auto_bus.active_consumer_count() > 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(auto_bus.consumers_scaled_to_zero());
+        // Idle consumers have exited, so they no longer report stats
+        assert!(auto_bus.worker_stats().is_empty());
+        auto_bus.stop();
+    }
+
+    #[test]
+    fn test_new_isolated() {
+        let bus = DirectUniversalBus::new_isolated(64 * 1024, 2, false).unwrap();
+        bus.send(b"isolated", 1).unwrap();
+        assert_eq!(bus.receive(), Some(b"isolated".to_vec()));
+    }
+
+    #[test]
+    fn test_stop_interrupts_blocked_producers() {
+        // A message limit rather than a tiny buffer, so the slot freed by
+        // the receive below can be reused
+        let mut auto_bus = AutoScalingBus::new(64 * 1024, 1, false).unwrap();
+        Arc::get_mut(&mut auto_bus.bus).unwrap().message_capacity = Some(1);
+        while auto_bus.bus.send([0u8; 16], 0).is_ok() {}
+
+        auto_bus.start_auto_producers(|_| Some(vec![1u8; 16]), Some(2));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        let start = std::time::Instant::now();
+        auto_bus.stop();
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // The interruption is lifted once the producers have been joined
+        assert_eq!(auto_bus.bus.receive(), Some(vec![0u8; 16]));
+        assert!(auto_bus.bus.send_blocking([2u8; 16], 0).is_ok());
+    }
+
+    #[test]
+    fn test_detached_producer_stops_blocking() {
+        use std::sync::atomic::AtomicBool;
+
+        let mut auto_bus = AutoScalingBus::new(64, 1, false).unwrap();
+        while auto_bus.bus.send([0u8; 16], 0).is_ok() {}
+
+        // The producer is stuck in its own code when the stop comes
+        let entered = Arc::new(AtomicBool::new(false));
+        let release = Arc::new(AtomicBool::new(false));
+        let (entered_flag, release_flag) = (Arc::clone(&entered), Arc::clone(&release));
+        auto_bus.start_auto_producers(
+            move |_| {
+                entered_flag.store(true, Ordering::Release);
+                while !release_flag.load(Ordering::Acquire) {
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+                Some(vec![1u8; 16])
+            },
+            Some(1),
+        );
+        while !entered.load(Ordering::Acquire) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let report = auto_bus.stop_timeout(std::time::Duration::from_millis(20));
+        assert_eq!(report.producers_detached, 1);
+
+        // Its send then meets the full buffer and must give up rather
+        // than wait forever; exiting releases its clone of the bus
+        release.store(true, Ordering::Release);
+        let start = std::time::Instant::now();
+        while Arc::strong_count(&auto_bus.bus) > 1 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_scaling_config() {
+        let bus = DirectUniversalBus::new(64 * 1024, 2, false, true).unwrap();
+        // Other tests reconfigure the core concurrently, so only check that
+        // a coherent config comes back
+        let config = bus.scaling_config();
+        assert!(config.min_producers <= config.max_producers);
+        assert!(config.min_consumers <= config.max_consumers);
+        assert!(config.scale_threshold_percent <= 100);
+    }
+
+    #[test]
+    fn test_blob_channel() {
+        let bus = DirectUniversalBus::new(1024, 1, false, false).unwrap();
+        let blob = vec![7u8; 100_000];
+
+        let id = bus.send_blob(&blob, 0).unwrap();
+        bus.send(b"control", 0).unwrap();
+        assert_eq!(bus.pending_blobs(), 1);
+
+        let reference = bus.receive().unwrap();
+        assert_eq!(reference.len(), 12);
+        assert_eq!(BlobId::from_message(&reference), Some(id));
+        assert_eq!(BlobId::from_message(&bus.receive().unwrap()), None);
+
+        assert_eq!(bus.get_blob(id), Some(blob));
+        assert_eq!(bus.get_blob(id), None);
+
+        let bus = bus.with_blob_ttl(std::time::Duration::ZERO);
+        let id = bus.send_blob(b"expires", 0).unwrap();
+        assert_eq!(bus.get_blob(id), None);
+    }
+
+    #[test]
+    fn test_tap_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("umsbb_tap_{}.log", std::process::id()));
+        let tapped = TappedBus::new(DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+
+        tapped.send(b"before", 5).unwrap();
+        tapped.start_recording(&path).unwrap();
+        tapped.send(b"first", 1).unwrap();
+        tapped.send(b"second", 2).unwrap();
+        assert_eq!(tapped.receive(), Some(b"before".to_vec()));
+        tapped.stop_recording().unwrap();
+
+        let records = read_tap_log(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].direction, TapDirection::Sent);
+        assert_eq!(records[1].type_id, 2);
+        assert_eq!(records[2].direction, TapDirection::Received);
+        assert_eq!(records[2].payload, b"before");
+        assert_eq!(records[2].type_id, 5);
+
+        let target = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        assert_eq!(replay(&path, &target).unwrap(), 2);
+        assert_eq!(target.len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tap_record_failure() {
+        let tapped = TappedBus::new(DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap());
+        tapped.start_recording("/dev/full").unwrap();
+
+        // Larger than the write buffer, so the write fails straight away
+        let message = vec![7u8; 16 * 1024];
+        tapped.send(&message, 1).unwrap();
+        assert!(!tapped.is_recording());
+        assert_eq!(tapped.receive(), Some(message));
+
+        assert!(matches!(tapped.stop_recording(), Err(BusError::Io { .. })));
+        assert_eq!(tapped.stop_recording(), Ok(()));
+    }
+
+    #[test]
+    fn test_stats_by_type() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        bus.send(b"abc", 1).unwrap();
+        bus.send(b"de", 1).unwrap();
+        bus.send(vec![0u8; 10], 2).unwrap();
+
+        let stats = bus.stats_by_type();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[&1], TypeStats { messages: 2, bytes: 5 });
+        assert_eq!(stats[&2], TypeStats { messages: 1, bytes: 10 });
+    }
+
+    #[test]
+    fn test_stop_timeout_detaches_stuck_worker() {
+        let mut auto_bus = AutoScalingBus::new(64 * 1024, 4, false).unwrap();
+        auto_bus.bus.send(b"slow", 0).unwrap();
+        auto_bus.start_auto_consumers(
+            |_, _| std::thread::sleep(std::time::Duration::from_millis(500)),
+            Some(2),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        let start = std::time::Instant::now();
+        let report = auto_bus.stop_timeout(std::time::Duration::from_millis(20));
+        assert!(start.elapsed() < std::time::Duration::from_millis(400));
+        assert_eq!(report.consumers_joined, 1);
+        assert_eq!(report.consumers_detached, 1);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_ack_receipts() {
+        let bus = Arc::new(DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+        let token = bus.send_acked(b"confirm me", 1).unwrap();
+        bus.send(b"plain", 1).unwrap();
+
+        let consumer = {
+            let bus = Arc::clone(&bus);
+            std::thread::spawn(move || {
+                let message = bus.receive_acked().unwrap();
+                assert_eq!(message.payload, b"confirm me");
+                assert_eq!(message.token, Some(token));
+                bus.ack(&message);
+                bus.receive_acked().unwrap()
+            })
+        };
+
+        assert!(bus.wait_ack(token, std::time::Duration::from_secs(2)));
+        let plain = consumer.join().unwrap();
+        assert_eq!(plain, AckedMessage { payload: b"plain".to_vec(), token: None });
+
+        let unacked = bus.send_acked(b"ignored", 1).unwrap();
+        assert!(!bus.wait_ack(unacked, std::time::Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_transform_bus() {
+        let bus = TransformBus::new(DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap())
+            .map(|m| m.to_ascii_uppercase())
+            .filter(|m| !m.starts_with(b"SECRET"))
+            .map(|m| [b"app:".as_slice(), m].concat());
+
+        bus.send(b"hello", 1).unwrap();
+        bus.send(b"secret token", 1).unwrap();
+
+        assert_eq!(bus.receive(), Some(b"app:HELLO".to_vec()));
+        assert_eq!(bus.receive(), None);
+        assert_eq!(bus.filtered_count(), 1);
+    }
+
+    #[test]
+    fn test_capacity_reservation() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_message_capacity(4);
+        bus.send(b"existing", 0).unwrap();
+
+        let mut guard = bus.reserve_capacity(3).unwrap();
+        // Everything left is reserved, so plain sends and new reservations fail
+        assert_eq!(bus.send(b"sneaky", 0), Err(BusError::BufferFull));
+        assert!(bus.reserve_capacity(1).is_err());
+
+        guard.send(b"one", 0).unwrap();
+        guard.send(b"two", 0).unwrap();
+        assert_eq!(guard.remaining(), 1);
+        drop(guard);
+
+        // The unused slot is released on drop
+        bus.send(b"after", 0).unwrap();
+        assert_eq!(bus.len(), 4);
+        assert_eq!(bus.reserve_capacity(1).err(), Some(BusError::BufferFull));
+
+        // Racing senders can't overfill the cap between check and send
+        let bus = Arc::new(
+            DirectUniversalBus::new(64 * 1024, 4, false, false)
+                .unwrap()
+                .with_message_capacity(4),
+        );
+        let senders: Vec<_> = (0..8)
+            .map(|_| {
+                let bus = Arc::clone(&bus);
+                std::thread::spawn(move || (0..50).filter(|_| bus.send(b"race", 0).is_ok()).count())
+            })
+            .collect();
+        let sent: usize = senders.into_iter().map(|s| s.join().unwrap()).sum();
+        assert_eq!(sent, 4);
+        assert_eq!(bus.len(), 4);
+
+        // Reserved sends are deduplicated too, and a suppressed one keeps its slot
+        let window = std::time::Duration::from_secs(60);
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_message_capacity(4)
+            .with_dedup(8, window, DuplicateAction::Reject);
+        let mut guard = bus.reserve_capacity(2).unwrap();
+        guard.send(b"once", 0).unwrap();
+        assert_eq!(guard.send(b"once", 0), Err(BusError::Duplicate));
+        assert_eq!(guard.remaining(), 1);
+        assert_eq!(bus.send(b"once", 0), Err(BusError::Duplicate));
+        drop(guard);
+        assert_eq!(bus.len(), 1);
+        assert_eq!(bus.duplicates_suppressed(), 2);
+
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_dedup(8, window, DuplicateAction::Drop);
+        let mut guard = bus.reserve_capacity(2).unwrap();
+        guard.send(b"once", 0).unwrap();
+        guard.send(b"once", 0).unwrap();
+        assert_eq!(guard.remaining(), 1);
+        drop(guard);
+        assert_eq!(bus.len(), 1);
+    }
+
+    #[test]
+    fn test_receive_waker() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        bus.send(b"ready", 0).unwrap();
+        assert_eq!(bus.receive_timeout(std::time::Duration::from_millis(10)), Some(b"ready".to_vec()));
+
+        let waker = bus.receive_waker();
+        let start = std::time::Instant::now();
+        let wake = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            waker.wake();
+        });
+        assert_eq!(bus.receive_timeout(std::time::Duration::from_secs(10)), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        wake.join().unwrap();
+
+        // The wake outlives the call it interrupted until it is reset
+        let waker = bus.receive_waker();
+        assert!(waker.is_woken());
+        let start = std::time::Instant::now();
+        assert_eq!(bus.receive_timeout(std::time::Duration::from_secs(10)), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        bus.send(b"queued", 0).unwrap();
+        assert_eq!(bus.receive_timeout(std::time::Duration::from_secs(10)), Some(b"queued".to_vec()));
+
+        waker.reset();
+        assert_eq!(bus.receive_timeout(std::time::Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: std::time::Duration::from_millis(1),
+            max_backoff: std::time::Duration::from_millis(4),
+            multiplier: 2.0,
+            jitter: 0.0,
+        };
+        assert_eq!(policy.backoff(0), std::time::Duration::from_millis(1));
+        assert_eq!(policy.backoff(1), std::time::Duration::from_millis(2));
+        assert_eq!(policy.backoff(5), std::time::Duration::from_millis(4));
+
+        let jittered = RetryPolicy { jitter: 0.5, ..policy.clone() };
+        for _ in 0..20 {
+            let delay = jittered.backoff(1);
+            assert!(delay >= std::time::Duration::from_millis(1));
+            assert!(delay <= std::time::Duration::from_millis(3));
+        }
+
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false)
+            .unwrap()
+            .with_message_capacity(1)
+            .with_retry_policy(policy.clone());
+        assert_eq!(bus.retry_policy(), &policy);
+        assert!(bus.send_retrying([0u8; 16], 0).is_ok());
+        assert_eq!(bus.send_retrying([0u8; 16], 0), Err(BusError::BufferFull));
+        let once = RetryPolicy { max_attempts: 1, ..policy };
+        assert_eq!(bus.send_with_policy([0u8; 16], 0, &once), Err(BusError::BufferFull));
+    }
+
+    #[test]
+    fn test_type_weights() {
+        // Both types share segment 0
+        let bus = DirectUniversalBus::new(1024 * 1024, 2, false, false).unwrap();
+        for _ in 0..8 {
+            bus.send(vec![0u8; 4096], 0).unwrap();
+            bus.send(vec![2u8; 4096], 2).unwrap();
+        }
+
+        // Type 2 gets three messages for every one of type 0
+        bus.set_type_weights(HashMap::from([(0, 1), (2, 3)]));
+        let order: Vec<u8> = (0..8).map(|_| bus.receive().unwrap()[0]).collect();
+        assert_eq!(order, vec![0, 2, 2, 2, 0, 2, 2, 2]);
+
+        // What the scheduler holds is still pending
+        assert_eq!(bus.len(), 8);
+        assert_eq!(bus.stats().pending_messages, 8);
+
+        // Clearing the weights still delivers what the scheduler holds
+        bus.set_type_weights(HashMap::new());
+        let rest: Vec<u8> = std::iter::from_fn(|| bus.receive()).map(|m| m[0]).collect();
+        assert_eq!(rest.iter().filter(|&&t| t == 0).count(), 6);
+        assert_eq!(rest.len(), 8);
     }
 
-    /// Start auto-scaling producers
-    /// 
-    /// # Arguments
-    /// * `producer_fn` - Function that generates data
-    /// * `count` - Number of producers (None = auto-determine)
-    pub fn start_auto_producers<F>(&mut self, producer_fn: F, count: Option<u32>)
-    where
-        F: Fn(u32) -> Option<Vec<u8>> + Send + Sync + Clone + 'static,
-    {
-        let count = count.unwrap_or_else(|| self.bus.get_scaling_status().optimal_producers);
+    #[test]
+    fn test_fair_drain() {
+        // Both types share segment 0
+        let bus = DirectUniversalBus::new(64 * 1024, 2, false, false).unwrap();
+        for i in 0..4u8 {
+            bus.send([0, i], 0).unwrap();
+        }
+        bus.send([2, 0], 2).unwrap();
+        bus.send([2, 1], 2).unwrap();
 
-        for worker_id in 0..count {
-            let bus_handle = unsafe { std::ptr::read(&self.bus.handle) };
-            let producer_fn = producer_fn.clone();
-            let shutdown = self.shutdown.clone();
+        bus.set_fair_drain(true);
+        let order: Vec<Vec<u8>> = std::iter::from_fn(|| bus.receive()).collect();
+        assert_eq!(
+            order,
+            vec![vec![0, 0], vec![2, 0], vec![0, 1], vec![2, 1], vec![0, 2], vec![0, 3]]
+        );
 
-            let producer = std::thread::spawn(move || {
-                while !shutdown.load(std::sync::atomic::Ordering::Relaxed) {
-                    if let Some(data) = producer_fn(worker_id) {
-                        // Create a temporary bus instance for this thread
-                        let temp_bus = DirectUniversalBus {
-                            handle: bus_handle,
-                            buffer_size: 0,
-                            segment_count: 0,
-                            gpu_enabled: false,
-                        };
-                        let _ = temp_bus.send(&data, worker_id);
-                        std::mem::forget(temp_bus); // Don't drop the handle
-                    }
-                    std::thread::sleep(std::time::Duration::from_micros(100));
-                }
-            });
+        // The scheduler only holds a bounded window of the backlog
+        for _ in 0..fair::MAX_BUFFERED + 10 {
+            bus.send([0], 0).unwrap();
+        }
+        assert_eq!(bus.receive(), Some(vec![0]));
+        // A full window was pulled and one handed out; ten stay in the core
+        assert_eq!(bus.fair_buffered(), fair::MAX_BUFFERED as u32 - 1);
+        assert_eq!(bus.len(), fair::MAX_BUFFERED as u32 + 9);
+    }
 
-            self.producers.push(producer);
+    #[test]
+    fn test_remote_bus() {
+        let bus = Arc::new(DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap());
+        let server = serve(Arc::clone(&bus), "127.0.0.1:0").unwrap();
+
+        let remote = RemoteBus::connect(server.local_addr()).unwrap();
+        remote.send(b"over the wire", 3).unwrap();
+        remote.send(b"", 3).unwrap();
+        assert_eq!(remote.send(vec![0u8; 128 * 1024], 3), Err(BusError::InvalidParams));
+
+        assert_eq!(remote.stats().unwrap().pending_messages, 2);
+        assert_eq!(remote.receive(), Some(b"over the wire".to_vec()));
+        assert_eq!(remote.receive(), Some(Vec::new()));
+        assert_eq!(remote.try_receive().unwrap(), None);
+        server.stop();
+    }
+
+    #[test]
+    fn test_remote_bus_errors() {
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false)
+            .unwrap()
+            .with_dedup(8, std::time::Duration::from_secs(60), DuplicateAction::Reject);
+        bus.register_validator(4, |_| Err(ValidationError::new("schema mismatch")));
+        let server = serve(Arc::new(bus), "127.0.0.1:0").unwrap();
+        let remote = RemoteBus::connect(server.local_addr()).unwrap();
+
+        // Structured errors keep their fields over the wire
+        assert_eq!(
+            remote.send(b"{}", 4),
+            Err(BusError::Validation { type_id: 4, reason: "schema mismatch".to_string() })
+        );
+        remote.send(b"once", 1).unwrap();
+        assert_eq!(remote.send(b"once", 1), Err(BusError::Duplicate));
+        server.stop();
+    }
+
+    #[test]
+    fn test_pipe_to_sender() {
+        let bus = Arc::new(DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        let subscription = bus.pipe_to_sender(tx);
+
+        bus.send(b"one", 0).unwrap();
+        bus.send(b"two", 0).unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), b"one".to_vec());
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), b"two".to_vec());
+
+        // The forwarder exits on the first send after the receiver is gone
+        drop(rx);
+        bus.send(b"three", 0).unwrap();
+        let deadline = std::time::Instant::now() + timeout;
+        while !subscription.is_finished() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
         }
+        assert!(subscription.is_finished());
+    }
 
-        println!("Started {} auto-scaling producers", count);
+    #[test]
+    fn test_validators() {
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap();
+        bus.register_validator(1, |data| {
+            if data.starts_with(b"{") {
+                Ok(())
+            } else {
+                Err(ValidationError::new("expected a JSON object"))
+            }
+        });
+
+        assert_eq!(
+            bus.send(b"plain", 1),
+            Err(BusError::Validation { type_id: 1, reason: "expected a JSON object".to_string() })
+        );
+        assert!(bus.is_empty());
+
+        bus.send(b"{}", 1).unwrap();
+        bus.send(b"plain", 2).unwrap();
+        bus.send_unvalidated(b"plain", 1).unwrap();
+        assert_eq!(bus.len(), 3);
+
+        bus.unregister_validator(1);
+        bus.send(b"plain", 1).unwrap();
     }
 
-    /// Start auto-scaling consumers
-    /// 
-    /// # Arguments
-    /// * `consumer_fn` - Function that processes data
-    /// * `count` - Number of consumers (None = auto-determine)
-    pub fn start_auto_consumers<F>(&mut self, consumer_fn: F, count: Option<u32>)
-    where
-        F: Fn(Vec<u8>, u32) + Send + Sync + Clone + 'static,
-    {
-        let count = count.unwrap_or_else(|| self.bus.get_scaling_status().optimal_consumers);
+    #[test]
+    fn test_hot_atomics_padded() {
+        // Producer and consumer counters must land on separate cache lines
+        let counters = FfiCounters::default();
+        let submit = &counters.submit as *const _ as usize;
+        let drain = &counters.drain as *const _ as usize;
+        assert!(drain.abs_diff(submit) >= 64);
+        assert_eq!(std::mem::align_of::<CachePadded<AtomicU32>>(), 64);
+    }
 
-        for worker_id in 0..count {
-            let bus_handle = unsafe { std::ptr::read(&self.bus.handle) };
-            let consumer_fn = consumer_fn.clone();
-            let shutdown = self.shutdown.clone();
+    #[test]
+    fn test_sharded_bus() {
+        let mut sharded = ShardedBus::new();
+        assert_eq!(sharded.send("key", b"x", 0), Err(BusError::InvalidParams));
+        for _ in 0..4 {
+            sharded.add_shard(DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap());
+        }
 
-            let consumer = std::thread::spawn(move || {
-                while !shutdown.load(std::sync::atomic::Ordering::Relaxed) {
-                    // Create a temporary bus instance for this thread
-                    let temp_bus = DirectUniversalBus {
-                        handle: bus_handle,
-                        buffer_size: 0,
-                        segment_count: 0,
-                        gpu_enabled: false,
-                    };
-                    
-                    if let Some(data) = temp_bus.receive() {
-                        consumer_fn(data, worker_id);
-                    } else {
-                        std::thread::sleep(std::time::Duration::from_micros(100));
-                    }
-                    std::mem::forget(temp_bus); // Don't drop the handle
+        let keys: Vec<String> = (0..200).map(|i| format!("key-{}", i)).collect();
+        let before: Vec<u32> = keys.iter().map(|k| sharded.shard_for(k.as_str()).unwrap()).collect();
+        assert!((0..4).all(|id| before.contains(&id)));
+
+        // Only keys owned by the removed shard move
+        sharded.remove_shard(2).unwrap();
+        for (key, &old) in keys.iter().zip(&before) {
+            let new = sharded.shard_for(key.as_str()).unwrap();
+            if old != 2 {
+                assert_eq!(new, old);
+            }
+        }
+
+        sharded.send("key-1", b"routed", 0).unwrap();
+        let owner = sharded.shard_for("key-1").unwrap();
+        assert_eq!(sharded.shard(owner).unwrap().len(), 1);
+        assert_eq!(sharded.receive(), Some(b"routed".to_vec()));
+        assert_eq!(sharded.receive(), None);
+    }
+
+    #[test]
+    fn test_adaptive_poll_interval() {
+        let min = std::time::Duration::from_micros(10);
+        let max = std::time::Duration::from_millis(5);
+        let mut poller = poll::Poller::new(ReceiveStrategy::Adaptive { min, max });
+        assert_eq!(poller.interval(), max);
+
+        // A burst of hits pulls the interval down toward `min`
+        for _ in 0..30 {
+            poller.observe(true);
+        }
+        let busy = poller.interval();
+        assert!(busy < std::time::Duration::from_micros(100), "{:?}", busy);
+
+        // Then idling stretches it back out
+        for _ in 0..30 {
+            poller.observe(false);
+        }
+        let idle = poller.interval();
+        assert!(idle > std::time::Duration::from_millis(4), "{:?}", idle);
+
+        let fixed = poll::Poller::new(ReceiveStrategy::default());
+        assert_eq!(fixed.interval(), std::time::Duration::from_micros(100));
+    }
+
+    #[test]
+    fn test_dedup() {
+        let bus = DirectUniversalBus::new(1024, 1, false, false)
+            .unwrap()
+            .with_dedup(2, std::time::Duration::from_secs(60), DuplicateAction::Reject);
+
+        bus.send(b"event-1", 0).unwrap();
+        assert_eq!(bus.send(b"event-1", 0), Err(BusError::Duplicate));
+        bus.send(b"event-1", 1).unwrap(); // Different type, different message
+
+        // Only the last two admitted messages are remembered
+        bus.send(b"event-2", 0).unwrap();
+        bus.send(b"event-1", 0).unwrap();
+        assert_eq!(bus.duplicates_suppressed(), 1);
+
+        // A send that fails isn't remembered, so it can be retried
+        assert_eq!(bus.send(vec![0u8; 2048], 0), Err(BusError::InvalidParams));
+        assert_eq!(bus.send(vec![0u8; 2048], 0), Err(BusError::InvalidParams));
+
+        let dropping = DirectUniversalBus::new(1024, 1, false, false)
+            .unwrap()
+            .with_dedup(8, std::time::Duration::from_secs(60), DuplicateAction::Drop);
+        dropping.send(b"event", 0).unwrap();
+        dropping.send(b"event", 0).unwrap();
+        assert_eq!(dropping.len(), 1);
+        assert_eq!(dropping.duplicates_suppressed(), 1);
+    }
+
+    #[test]
+    fn test_compute_capability() {
+        let mut info = GpuInfo {
+            available: true,
+            has_cuda: true,
+            has_opencl: false,
+            has_compute: true,
+            memory_size: 0,
+            compute_capability: 75,
+            max_threads: 1024,
+        };
+        assert_eq!(info.capability(), ComputeCapability::new(7, 5));
+        assert_eq!(info.capability().to_string(), "7.5");
+        assert!(ComputeCapability::new(8, 0) > info.capability());
+        assert!(info.supports_compute(70));
+        assert!(!info.supports_compute(80));
+
+        info.available = false;
+        assert!(!info.supports_compute(70));
+    }
+
+    #[test]
+    fn test_receive_full() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        // Lands on segment 2, so the type id can't be read off the segment
+        bus.send(b"complete", 6).unwrap();
+
+        let message = bus.receive_full().unwrap();
+        assert_eq!(message.data, b"complete");
+        assert_eq!(message.type_id, 6);
+        assert_eq!(message.source_lang, LanguageType::Rust);
+        assert!(bus.receive_full().is_none());
+    }
+
+    #[test]
+    fn test_send_timeout() {
+        let bus = Arc::new(DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+        bus.send_timeout(b"deadline", 0, std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(bus.receive().unwrap(), b"deadline");
+        assert_eq!(BusError::Timeout.to_string(), "Operation timed out");
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let bus = DirectUniversalBus::with_defaults().unwrap();
+        bus.send(b"defaults", 0).unwrap();
+        assert_eq!(bus.receive().unwrap(), b"defaults");
+    }
+
+    #[test]
+    fn test_latency_recorder() {
+        let recorder = latency::LatencyRecorder::default();
+        assert_eq!(recorder.stats(), LatencyStats::default());
+
+        for ms in 1..=100 {
+            recorder.record(std::time::Duration::from_millis(ms));
+        }
+        let stats = recorder.stats();
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.min, std::time::Duration::from_millis(1));
+        assert_eq!(stats.max, std::time::Duration::from_millis(100));
+        assert_eq!(stats.p50, std::time::Duration::from_millis(50));
+        assert_eq!(stats.p99, std::time::Duration::from_millis(99));
+    }
+
+    #[test]
+    fn test_slow_consumer_hook() {
+        let mut auto = AutoScalingBus::new(64 * 1024, 1, false).unwrap();
+        let slow = Arc::new(AtomicU32::new(0));
+        let seen = Arc::clone(&slow);
+        auto.set_slow_consumer_threshold(Some(std::time::Duration::from_millis(5)));
+        auto.on_slow_consumer(move |_, _| {
+            seen.fetch_add(1, Ordering::Relaxed);
+        });
+        auto.start_auto_consumers(
+            |data, _| {
+                if data == b"slow" {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
                 }
-            });
+            },
+            Some(1),
+        );
 
-            self.consumers.push(consumer);
+        auto.bus.send(b"fast", 0).unwrap();
+        auto.bus.send(b"slow", 0).unwrap();
+        auto.stop();
+
+        assert_eq!(auto.consumer_latency_stats().count, 2);
+        assert_eq!(slow.load(Ordering::Relaxed), 1);
+    }
+
+    crate::define_message_types! {
+        #[derive(Debug, PartialEq)]
+        enum TestMessage {
+            TEST_RAW = 10 => Raw(Vec<u8>),
+            TEST_TEXT = 21 => Text(String),
         }
+    }
 
-        println!("Started {} auto-scaling consumers", count);
+    #[test]
+    fn test_define_message_types() {
+        assert_eq!(TestMessage::TYPE_IDS, &[TEST_RAW, TEST_TEXT]);
+
+        // Neither id matches the segment it lands on
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        bus.send("hello", TEST_TEXT).unwrap();
+        bus.send([0xff], TEST_RAW).unwrap();
+        let mut messages = Vec::new();
+        while let Some(received) = bus.receive_full() {
+            messages.push(TestMessage::dispatch(received.type_id, &received.data).unwrap());
+        }
+        assert_eq!(messages.len(), 2);
+        assert!(messages.contains(&TestMessage::Text("hello".to_string())));
+        assert!(messages.contains(&TestMessage::Raw(vec![0xff])));
+        assert_eq!(TestMessage::Text(String::new()).type_id(), TEST_TEXT);
+
+        assert_eq!(TestMessage::dispatch(TEST_RAW, &[0xff]).unwrap(), TestMessage::Raw(vec![0xff]));
+        assert!(matches!(TestMessage::dispatch(TEST_TEXT, &[0xff]), Err(DecodeError::Malformed(_))));
+        assert_eq!(TestMessage::dispatch(9, b""), Err(DecodeError::UnknownType(9)));
     }
 
-    /// Stop all producers and consumers
-    pub fn stop(&mut self) {
-        self.shutdown.store(true, std::sync::atomic::Ordering::Relaxed);
+    #[test]
+    fn test_producer_watermarks() {
+        let mut auto = AutoScalingBus::new(64 * 1024, 1, false).unwrap();
+        auto.set_watermarks(5, 2);
+        auto.start_auto_producers(|_| Some(vec![0u8; 8]), Some(1));
 
-        // Wait for all producers to finish
-        while let Some(producer) = self.producers.pop() {
-            let _ = producer.join();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(auto.bus.stats().pending_messages, 5);
+
+        // Draining to the low watermark isn't enough to resume
+        for _ in 0..3 {
+            auto.bus.receive().unwrap();
         }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(auto.bus.stats().pending_messages, 2);
 
-        // Wait for all consumers to finish
-        while let Some(consumer) = self.consumers.pop() {
-            let _ = consumer.join();
+        auto.bus.receive().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(auto.bus.stats().pending_messages, 5);
+        auto.stop_with(ShutdownReason::Immediate);
+    }
+
+    #[test]
+    fn test_worker_stats() {
+        let mut auto = AutoScalingBus::new(64 * 1024, 4, false).unwrap();
+        let remaining = Arc::new(std::sync::atomic::AtomicU32::new(10));
+        let budget = Arc::clone(&remaining);
+        auto.start_auto_producers(
+            move |_| {
+                budget
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .ok()
+                    .map(|_| b"work".to_vec())
+            },
+            Some(1),
+        );
+        auto.start_auto_consumers(|_, _| {}, Some(2));
+
+        let consumed = |auto: &AutoScalingBus| -> u64 {
+            auto.worker_stats()
+                .iter()
+                .filter(|s| s.role == WorkerRole::Consumer)
+                .map(|s| s.messages_processed)
+                .sum()
+        };
+        let start = std::time::Instant::now();
+        while consumed(&auto) < 10 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(30));
+            std::thread::sleep(std::time::Duration::from_millis(1));
         }
 
-        println!("[Rust AutoScale] Stopped all workers");
+        let stats = auto.worker_stats();
+        let roles: Vec<_> = stats.iter().map(|s| (s.role, s.worker_id)).collect();
+        assert_eq!(
+            roles,
+            vec![(WorkerRole::Producer, 0), (WorkerRole::Consumer, 0), (WorkerRole::Consumer, 1)]
+        );
+        assert_eq!(stats[0].messages_processed, 10);
+        auto.stop();
+        assert!(auto.worker_stats().is_empty());
     }
-}
 
-impl Drop for AutoScalingBus {
-    fn drop(&mut self) {
-        self.stop();
+    #[test]
+    fn test_mirrored_bus() {
+        let new_bus = |size| DirectUniversalBus::new(size, 1, false, false).unwrap();
+        let mut mirrored = MirroredBus::new(new_bus(1024));
+        mirrored.add_mirror(new_bus(1024));
+        let small = mirrored.add_mirror(new_bus(256));
+
+        // Too big for the small mirror, but best effort still delivers it
+        let report = mirrored.send([7u8; 300], 1);
+        assert_eq!(report.primary, Some(Ok(())));
+        assert_eq!(report.mirrors, vec![Ok(()), Err(BusError::InvalidParams)]);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.result(), Ok(()));
+        assert_eq!(mirrored.mirror(0).unwrap().receive().unwrap(), [7u8; 300]);
+        assert_eq!(mirrored.receive().unwrap(), [7u8; 300]);
+        assert!(mirrored.mirror(small).unwrap().is_empty());
+
+        // Strict stops before the primary, but the first mirror keeps its copy
+        let mirrored = mirrored.with_policy(MirrorPolicy::Strict);
+        let report = mirrored.send([7u8; 300], 1);
+        assert_eq!(report.primary, None);
+        assert_eq!(report.mirrors, vec![Ok(()), Err(BusError::InvalidParams)]);
+        assert_eq!(report.result(), Err(BusError::InvalidParams));
+        assert!(mirrored.primary().is_empty());
+        assert_eq!(mirrored.mirror(0).unwrap().len(), 1);
+        mirrored.mirror(0).unwrap().receive().unwrap();
+
+        assert!(mirrored.send(b"fits", 1).all_succeeded());
+        assert_eq!(mirrored.receive().unwrap(), b"fits");
+
+        // A failing primary is reported alongside the mirrors
+        let mut mirrored = MirroredBus::new(new_bus(256));
+        mirrored.add_mirror(new_bus(1024));
+        let report = mirrored.send([7u8; 300], 1);
+        assert_eq!(report.primary, Some(Err(BusError::InvalidParams)));
+        assert_eq!(report.mirrors, vec![Ok(())]);
+        assert!(!report.all_succeeded());
+        assert_eq!(report.result(), Err(BusError::InvalidParams));
     }
-}
 
-// Example usage and tests
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn test_drain_into_arena() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        let mut arena = bumpalo::Bump::new();
+        assert!(bus.drain_into_arena(&arena).is_empty());
+
+        for i in 0..3u8 {
+            bus.send([i; 4], 0).unwrap();
+        }
+        let messages = bus.drain_into_arena(&arena);
+        assert_eq!(messages, vec![&[0u8; 4][..], &[1; 4], &[2; 4]]);
+        assert!(bus.is_empty());
+        assert!(arena.allocated_bytes() >= 12);
+
+        arena.reset();
+        bus.send(b"next batch", 0).unwrap();
+        assert_eq!(bus.drain_into_arena(&arena), vec![&b"next batch"[..]]);
+
+        // A corrupted message in the middle doesn't end the batch
+        let mut bus = DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap();
+        bus.send([1], 0).unwrap();
+        bus.send([2; 4], 0).unwrap();
+        bus.send([3], 0).unwrap();
+        // Shrinking the limit makes the 4-byte message look implausible
+        bus.buffer_size = 2;
+        assert_eq!(bus.drain_into_arena(&arena), vec![&[1u8][..], &[3]]);
+        assert_eq!(bus.drop_stats().corrupted, 1);
+    }
 
+    #[cfg(feature = "zerocopy")]
     #[test]
-    fn test_direct_bus_creation() {
-        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false);
-        assert!(bus.is_ok());
+    fn test_receive_as_struct() {
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap();
+        let words = [1u32, 2, 3, 4];
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+
+        bus.send(&bytes, 0).unwrap();
+        assert_eq!(bus.receive_as_struct::<[u32; 4]>(), Some(Ok(words)));
+
+        bus.send(&bytes, 0).unwrap();
+        let sum = bus.receive_struct_with(|w: &[u32; 4]| w.iter().sum::<u32>());
+        assert_eq!(sum, Some(Ok(10)));
+
+        bus.send(&bytes[..6], 0).unwrap();
+        assert!(matches!(bus.receive_as_struct::<[u32; 4]>(), Some(Err(DecodeError::Malformed(_)))));
+        assert_eq!(bus.receive_as_struct::<u64>(), None);
     }
 
     #[test]
-    fn test_send_receive() {
+    fn test_set_consumer_fn() {
+        let mut auto = AutoScalingBus::new(64 * 1024, 1, false).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let old_tx = tx.clone();
+        auto.start_auto_consumers(move |data, _| old_tx.send(("old", data)).unwrap(), Some(1));
+
+        auto.bus.send(b"first", 0).unwrap();
+        assert_eq!(rx.recv().unwrap(), ("old", b"first".to_vec()));
+
+        auto.set_consumer_fn(move |data, _| tx.send(("new", data)).unwrap());
+        auto.bus.send(b"second", 0).unwrap();
+        assert_eq!(rx.recv().unwrap(), ("new", b"second".to_vec()));
+        auto.stop();
+    }
+
+    #[test]
+    fn test_send_receive_ordered() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        assert_eq!(bus.send_ordered(b"a", 1).unwrap(), 0);
+        assert_eq!(bus.send_ordered(b"b", 1).unwrap(), 1);
+        // Type 0 lands in an earlier segment, so it's drained first
+        assert_eq!(bus.send_ordered(b"c", 0).unwrap(), 2);
+
+        let mut tracker = SequenceTracker::starting_at(0);
+        let (seq, data) = bus.receive_ordered().unwrap();
+        assert_eq!((seq, data.as_slice()), (2, &b"c"[..]));
+        assert_eq!(tracker.observe(seq), SequenceEvent::Gap { missing: 2 });
+        assert_eq!(bus.receive_ordered().unwrap(), (0, b"a".to_vec()));
+
+        bus.send(b"short", 0).unwrap();
+        assert_eq!(bus.receive_ordered().unwrap(), (1, b"b".to_vec()));
+        assert!(bus.receive_ordered().is_none());
+    }
+
+    #[test]
+    fn test_end_of_stream() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap();
+        assert_eq!(bus.receive_or_eof(), ReceiveResult::Empty);
+
+        bus.send(b"last", 3).unwrap();
+        bus.send_eof().unwrap();
+        // The marker's segment drains first, but "last" is still delivered
+        assert_eq!(bus.receive_or_eof(), ReceiveResult::Message(b"last".to_vec()));
+        assert_eq!(bus.receive_or_eof(), ReceiveResult::EndOfStream);
+        assert_eq!(bus.receive_or_eof(), ReceiveResult::EndOfStream);
+    }
+
+    #[test]
+    fn test_new_on_numa_node() {
+        for node in [None, Some(0), Some(u32::MAX)] {
+            let bus = DirectUniversalBus::new_on_numa_node(64 * 1024, 4, false, false, node).unwrap();
+            bus.send(b"local", 0).unwrap();
+            assert_eq!(bus.receive().unwrap(), b"local");
+        }
+    }
+
+    #[test]
+    fn test_reader_writer_halves() {
+        let (reader, writer) = DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap().into_halves();
+        writer.send(b"one way", 0).unwrap();
+        writer.send_eof().unwrap();
+
+        let consumer = std::thread::spawn(move || reader.receive_or_eof());
+        assert_eq!(consumer.join().unwrap(), ReceiveResult::Message(b"one way".to_vec()));
+        // Only the end-of-stream marker is left
+        assert_eq!(writer.stats().pending_messages, 1);
+    }
+
+    #[test]
+    fn test_drop_hook() {
+        let bus = DirectUniversalBus::new(64 * 1024, 4, false, false)
+            .unwrap()
+            .with_dedup(8, std::time::Duration::from_secs(60), DuplicateAction::Drop)
+            .with_blob_ttl(std::time::Duration::ZERO);
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&dropped);
+        bus.on_drop(move |data, reason| seen.lock().unwrap().push((data.to_vec(), reason)));
+
+        bus.register_validator(1, |_| Err(ValidationError::new("never valid")));
+        assert!(bus.send(b"rejected", 1).is_err());
+        bus.send(b"twice", 0).unwrap();
+        bus.send(b"twice", 0).unwrap();
+        let id = bus.send_blob(b"stale", 2).unwrap();
+        assert_eq!(bus.get_blob(id), None);
+
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            vec![
+                (b"rejected".to_vec(), DropReason::Validation),
+                (b"twice".to_vec(), DropReason::Duplicate),
+                (b"stale".to_vec(), DropReason::Expired),
+            ]
+        );
+        let stats = bus.drop_stats();
+        assert_eq!(stats.get(DropReason::Duplicate), 1);
+        assert_eq!(stats.corrupted, 0);
+        assert_eq!(stats.total(), 3);
+
+        bus.send(b"too long", 3).unwrap();
+        assert_eq!(bus.receive_fixed::<4>(), None);
+        assert_eq!(bus.stats().dropped, DropStats { malformed: 1, ..stats });
+
+        // Messages still buffered when a coalescing bus goes away
+        let coalescing = CoalescingBus::new(bus, 1024, std::time::Duration::from_secs(60))
+            .with_flush_on_drop(false);
+        coalescing.send(b"lost", 4).unwrap();
+        coalescing.send(b"also lost", 4).unwrap();
+        drop(coalescing);
+        let dropped = dropped.lock().unwrap();
+        assert_eq!(dropped[4..], [
+            (b"lost".to_vec(), DropReason::Unflushed),
+            (b"also lost".to_vec(), DropReason::Unflushed),
+        ]);
+    }
+
+    #[test]
+    fn test_drop_hook_fair_queued() {
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap();
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&dropped);
+        bus.on_drop(move |data, reason| seen.lock().unwrap().push((data.to_vec(), reason)));
+
+        bus.send(b"first", 0).unwrap();
+        bus.send(b"second", 1).unwrap();
+        bus.set_fair_drain(true);
+        assert_eq!(bus.receive(), Some(b"first".to_vec()));
+
+        // The scheduler pulled "second" out of the core before the drop
+        drop(bus);
+        assert_eq!(*dropped.lock().unwrap(), vec![(b"second".to_vec(), DropReason::Unflushed)]);
+    }
+
+    #[test]
+    fn test_consume_loop() {
+        let bus = Arc::new(DirectUniversalBus::new(64 * 1024, 4, false, false).unwrap());
+        let shutdown = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let worker = {
+            let (bus, shutdown) = (Arc::clone(&bus), Arc::clone(&shutdown));
+            std::thread::spawn(move || {
+                let mut handled = Vec::new();
+                let report = bus.consume_loop(shutdown, |data| {
+                    assert_ne!(data, b"bad", "handler rejects bad input");
+                    handled.push(data);
+                });
+                (report, handled)
+            })
+        };
+
+        bus.send(b"first", 0).unwrap();
+        bus.send(b"bad", 0).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        // Queued before shutdown, so still handled
+        bus.send(b"last", 0).unwrap();
+        shutdown.store(true, Ordering::Release);
+
+        let (report, handled) = worker.join().unwrap();
+        assert_eq!(handled, vec![b"first".to_vec(), b"last".to_vec()]);
+        assert_eq!(report.processed, 3);
+        assert_eq!(report.panics, 1);
+        assert_eq!(report.latency.count, 3);
+        assert!(bus.is_empty());
+    }
+
+    #[test]
+    fn test_send_vectored() {
         let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
-        
-        // Send test data
-        assert!(bus.send("Hello Rust!", 1).is_ok());
-        assert!(bus.send(&[1, 2, 3, 4], 2).is_ok());
-        
-        // Receive data
-        if let Some(data) = bus.receive() {
-            println!("Received: {:?}", String::from_utf8_lossy(&data));
+        let header = [0xAAu8, 0xBB];
+        assert!(bus.send_vectored(&[&header, b"body"], 1).is_ok());
+
+        let data = bus.receive().unwrap();
+        assert_eq!(data, b"\xAA\xBBbody");
+    }
+
+    #[test]
+    fn test_data_pool_send() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false)
+            .unwrap()
+            .with_data_pool(2);
+        for i in 0..4u8 {
+            assert!(bus.send([i; 8], 1).is_ok());
         }
+        assert_eq!(bus.receive().unwrap(), [0u8; 8]);
+
+        #[cfg(feature = "ffi-stats")]
+        assert_eq!(bus.ffi_call_stats().create_data_calls, 0);
     }
 
+    #[cfg(feature = "ffi-stats")]
     #[test]
-    fn test_gpu_info() {
-        let bus = DirectUniversalBus::new(1024 * 1024, 4, true, false).unwrap();
-        let gpu_info = bus.get_gpu_info();
-        println!("GPU Info: {:?}", gpu_info);
+    fn test_ffi_call_stats() {
+        let bus = DirectUniversalBus::new(1024 * 1024, 4, false, false).unwrap();
+        bus.send("counted", 1).unwrap();
+        assert!(bus.receive().is_some());
+
+        let stats = bus.ffi_call_stats();
+        assert_eq!(stats.create_data_calls, 1);
+        assert_eq!(stats.submit_calls, 1);
+        assert_eq!(stats.drain_calls, 1);
+        assert_eq!(stats.free_data_calls, 2);
+
+        #[cfg(feature = "bytes")]
+        {
+            bus.send("counted", 1).unwrap();
+            let message = bus.receive_bytes().unwrap();
+            drop(message);
+
+            let stats = bus.ffi_call_stats();
+            assert_eq!(stats.drain_calls, 2);
+            assert_eq!(stats.free_data_calls, 4);
+        }
     }
-}
 
-// Example usage
-fn main() -> Result<(), String> {
-    // Direct usage example
-    let bus = DirectUniversalBus::new(1024 * 1024, 0, true, true)?;
-    
-    println!("GPU Info: {:?}", bus.get_gpu_info());
-    println!("Scaling Status: {:?}", bus.get_scaling_status());
-    
-    // Send test data
-    bus.send("Hello from Rust!", 1)?;
-    bus.send(&[1, 2, 3, 4, 5], 2)?;
-    
-    // Receive data
-    while let Some(data) = bus.receive() {
-        println!("Received: {:?}", String::from_utf8_lossy(&data));
+    #[test]
+    fn test_send_owned() {
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false).unwrap();
+        let mut frame = Vec::with_capacity(4096);
+        frame.extend_from_slice(&[5u8; 1000]);
+        bus.send_owned(frame, 3).unwrap();
+        bus.send_owned(Vec::new(), 4).unwrap();
+        assert_eq!(bus.stats_by_type()[&3].bytes, 1000);
+
+        assert_eq!(bus.receive_full().map(|m| (m.data, m.type_id)), Some((vec![5u8; 1000], 3)));
+        assert_eq!(bus.receive_full().map(|m| (m.data, m.type_id)), Some((Vec::new(), 4)));
+
+        // Too big to ever be received: rejected, and the buffer is dropped
+        assert_eq!(
+            bus.send_owned(vec![0u8; bus.max_message_size() + 1], 3),
+            Err(BusError::InvalidParams)
+        );
+
+        bus.register_validator(9, |data| {
+            if data.is_empty() {
+                Err(ValidationError::new("empty frame"))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(
+            bus.send_owned(Vec::new(), 9),
+            Err(BusError::Validation { type_id: 9, reason: "empty frame".to_string() })
+        );
+
+        // The core adopted the buffer rather than copying it into a message
+        #[cfg(feature = "ffi-stats")]
+        {
+            let stats = bus.ffi_call_stats();
+            assert_eq!(stats.create_data_calls, 0);
+            assert_eq!(stats.submit_calls, 2);
+        }
+
+        // Still-queued buffers are released when the bus is destroyed
+        bus.send_owned(vec![1u8; 100], 3).unwrap();
+        bus.send_owned(vec![2u8; 100], 3).unwrap();
+        drop(bus);
+
+        let bus = DirectUniversalBus::new(64 * 1024, 1, false, false)
+            .unwrap()
+            .with_dedup(8, std::time::Duration::from_secs(60), DuplicateAction::Reject);
+        bus.send_owned(b"once".to_vec(), 1).unwrap();
+        assert_eq!(bus.send_owned(b"once".to_vec(), 1), Err(BusError::Duplicate));
+        assert_eq!(bus.len(), 1);
+
+        let (reader, writer) = bus.into_halves();
+        writer.send_owned(b"twice".to_vec(), 1).unwrap();
+        assert_eq!(reader.receive().unwrap(), b"once");
+        assert_eq!(reader.receive().unwrap(), b"twice");
     }
-    
-    Ok(())
-}
\ No newline at end of file
+}
+
@@ -0,0 +1,98 @@
+//! Declarative registry of message types and their payloads
+
+use crate::DecodeError;
+
+/// A payload type that [`define_message_types!`] can decode from raw bytes
+pub trait MessagePayload: Sized {
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError>;
+}
+
+impl MessagePayload for Vec<u8> {
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl MessagePayload for String {
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        String::from_utf8(bytes.to_vec()).map_err(|e| DecodeError::Malformed(e.to_string()))
+    }
+}
+
+/// Declare a set of message types with unique ids and a typed dispatcher
+///
+/// Each entry `CONST = id => Variant(Payload)` defines a `u32` constant
+/// holding the type id and a variant of the generated enum carrying the
+/// decoded payload, which must implement [`MessagePayload`]. Declaring the
+/// same id twice fails to compile.
+///
+/// The enum gets:
+/// * `dispatch(type_id, bytes)` - Decode a received message into the
+///   variant for its type id, or `DecodeError::UnknownType`
+/// * `type_id(&self)` - The id of a decoded message's type
+/// * `TYPE_IDS` - Every declared id, in declaration order
+///
+/// # Example
+/// ```rust
+/// define_message_types! {
+///     pub enum Message {
+///         ORDER_PLACED = 1 => OrderPlaced(OrderEvent),
+///         LOG_LINE = 2 => LogLine(String),
+///     }
+/// }
+///
+/// bus.send(serialize(&order), ORDER_PLACED)?;
+///
+/// if let Some(received) = bus.receive_full() {
+///     match Message::dispatch(received.type_id, &received.data)? {
+///         Message::OrderPlaced(order) => fulfil(order),
+///         Message::LogLine(line) => println!("{}", line),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_message_types {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($id_name:ident = $id:expr => $variant:ident($payload:ty)),+ $(,)?
+        }
+    ) => {
+        $($vis const $id_name: u32 = $id;)+
+
+        const _: () = {
+            let ids: &[u32] = &[$($id_name),+];
+            let mut i = 0;
+            while i < ids.len() {
+                let mut j = i + 1;
+                while j < ids.len() {
+                    assert!(ids[i] != ids[j], "duplicate type_id in define_message_types!");
+                    j += 1;
+                }
+                i += 1;
+            }
+        };
+
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($payload)),+
+        }
+
+        impl $name {
+            pub const TYPE_IDS: &'static [u32] = &[$($id_name),+];
+
+            pub fn dispatch(type_id: u32, bytes: &[u8]) -> Result<Self, $crate::DecodeError> {
+                match type_id {
+                    $($id_name => <$payload as $crate::MessagePayload>::decode(bytes).map($name::$variant),)+
+                    _ => Err($crate::DecodeError::UnknownType(type_id)),
+                }
+            }
+
+            pub fn type_id(&self) -> u32 {
+                match self {
+                    $($name::$variant(_) => $id_name,)+
+                }
+            }
+        }
+    };
+}
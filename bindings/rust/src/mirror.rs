@@ -0,0 +1,134 @@
+//! Writing every message to standby buses as well as the primary
+
+use crate::{BusError, DirectUniversalBus};
+
+/// Whether a failed mirror write fails a [`MirroredBus::send`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MirrorPolicy {
+    /// Every mirror must accept the message before the primary gets it
+    Strict,
+    /// Mirror failures are reported but the send still succeeds
+    #[default]
+    BestEffort,
+}
+
+/// Per-target outcome of a [`MirroredBus::send`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MirrorReport {
+    /// Outcome on the primary, or `None` if a mirror failure under
+    /// [`MirrorPolicy::Strict`] stopped the send before reaching it
+    pub primary: Option<Result<(), BusError>>,
+    /// Outcome on each mirror that was attempted, in mirror order
+    pub mirrors: Vec<Result<(), BusError>>,
+}
+
+impl MirrorReport {
+    /// Whether the primary and every attempted mirror got the message
+    pub fn all_succeeded(&self) -> bool {
+        matches!(self.primary, Some(Ok(()))) && self.mirrors.iter().all(Result::is_ok)
+    }
+
+    /// Number of mirrors that didn't get the message
+    pub fn failed(&self) -> usize {
+        self.mirrors.iter().filter(|r| r.is_err()).count()
+    }
+
+    /// The send's overall outcome: the primary's error if it failed, or the
+    /// mirror error that kept it from being written
+    pub fn result(&self) -> Result<(), BusError> {
+        match &self.primary {
+            Some(result) => result.clone(),
+            None => self.mirrors.iter().find_map(|r| r.clone().err()).map_or(Ok(()), Err),
+        }
+    }
+}
+
+/// A primary bus whose writes are copied to one or more mirror buses
+///
+/// Consumers read from the primary only; the mirrors hold the same
+/// messages so a standby consumer can take over from one of them if the
+/// primary's consumer fails.
+///
+/// # Example
+/// ```rust
+/// let mut mirrored = MirroredBus::new(DirectUniversalBus::with_defaults()?)
+///     .with_policy(MirrorPolicy::Strict);
+/// mirrored.add_mirror(DirectUniversalBus::with_defaults()?);
+/// mirrored.send(b"replicated", 1).result()?;
+/// ```
+pub struct MirroredBus {
+    primary: DirectUniversalBus,
+    mirrors: Vec<DirectUniversalBus>,
+    policy: MirrorPolicy,
+}
+
+impl MirroredBus {
+    pub fn new(primary: DirectUniversalBus) -> Self {
+        MirroredBus {
+            primary,
+            mirrors: Vec::new(),
+            policy: MirrorPolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: MirrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Add a mirror, returning its index
+    pub fn add_mirror(&mut self, bus: DirectUniversalBus) -> usize {
+        self.mirrors.push(bus);
+        self.mirrors.len() - 1
+    }
+
+    pub fn primary(&self) -> &DirectUniversalBus {
+        &self.primary
+    }
+
+    pub fn mirror(&self, index: usize) -> Option<&DirectUniversalBus> {
+        self.mirrors.get(index)
+    }
+
+    pub fn mirror_count(&self) -> usize {
+        self.mirrors.len()
+    }
+
+    /// Send to every mirror and to the primary
+    ///
+    /// Mirrors are written first. Under [`MirrorPolicy::Strict`] the first
+    /// mirror failure ends the send before the primary is written, so
+    /// consumers never see a message the standbys lack. Under
+    /// [`MirrorPolicy::BestEffort`] every mirror is attempted and the
+    /// primary is written regardless. Use [`MirrorReport::result`] for the
+    /// overall outcome.
+    ///
+    /// Writes are not rolled back. Mirrors that accepted the message keep
+    /// it when a later mirror or the primary fails, leaving them ahead of
+    /// the primary, and resending the whole message duplicates it on them.
+    /// The report says exactly which targets have it.
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> MirrorReport {
+        let data = data.as_ref();
+        let mut report = MirrorReport::default();
+        for mirror in &self.mirrors {
+            let result = mirror.send(data, type_id);
+            let stop = result.is_err() && self.policy == MirrorPolicy::Strict;
+            report.mirrors.push(result);
+            if stop {
+                return report;
+            }
+        }
+        report.primary = Some(self.primary.send(data, type_id));
+        report
+    }
+
+    /// Receive from the primary
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.primary.receive()
+    }
+
+    /// Take the buses apart, primary first
+    pub fn into_parts(self) -> (DirectUniversalBus, Vec<DirectUniversalBus>) {
+        (self.primary, self.mirrors)
+    }
+}
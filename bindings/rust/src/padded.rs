@@ -0,0 +1,27 @@
+//! Cache-line padding for contended shared state
+
+use std::ops::Deref;
+
+/// Aligns and pads `T` to its own 64-byte cache line
+///
+/// Atomics written by one set of threads and read or written by another
+/// should not share a line with unrelated hot data; otherwise every write
+/// invalidates the line for all of them (false sharing). 64 bytes covers
+/// current x86-64 and most ARM cores.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        CachePadded(value)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
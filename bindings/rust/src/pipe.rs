@@ -0,0 +1,82 @@
+//! Forwarding received messages into a channel
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::DirectUniversalBus;
+
+// How long the forwarding thread waits for a message before checking
+// whether it was cancelled
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A running [`DirectUniversalBus::pipe_to_sender`] forwarder
+///
+/// Dropping it stops the forwarding thread and waits for it to exit.
+pub struct Subscription {
+    stopped: Arc<AtomicBool>,
+    forwarder: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    /// Whether the forwarding thread has exited because the channel's
+    /// receiver was dropped
+    pub fn is_finished(&self) -> bool {
+        self.forwarder.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(forwarder) = self.forwarder.take() {
+            let _ = forwarder.join();
+        }
+    }
+}
+
+impl DirectUniversalBus {
+    /// Forward every received message into `tx` from a background thread
+    ///
+    /// Bridges the polling bus into channel-based code such as actor
+    /// mailboxes. Forwarding stops when the returned [`Subscription`] is
+    /// dropped, or once a send fails because the receiver is gone; the
+    /// message whose send failed is lost. The thread competes with any
+    /// other consumers of the bus.
+    ///
+    /// # Example
+    /// ```rust
+    /// let (tx, rx) = std::sync::mpsc::channel();
+    /// let _subscription = bus.pipe_to_sender(tx);
+    /// for message in rx {
+    ///     actor.handle(message);
+    /// }
+    /// ```
+    pub fn pipe_to_sender(self: &Arc<Self>, tx: Sender<Vec<u8>>) -> Subscription {
+        let bus = Arc::clone(self);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stopped);
+
+        let forwarder = std::thread::spawn(move || {
+            while !flag.load(Ordering::SeqCst) {
+                let Some(message) = bus.receive_timeout(STOP_CHECK_INTERVAL) else {
+                    // A wake left set makes receive_timeout return at once
+                    if bus.wake_state.is_set() {
+                        std::thread::sleep(STOP_CHECK_INTERVAL);
+                    }
+                    continue;
+                };
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Subscription {
+            stopped,
+            forwarder: Some(forwarder),
+        }
+    }
+}
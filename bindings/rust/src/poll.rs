@@ -0,0 +1,59 @@
+//! Poll pacing for consumers waiting on an empty bus
+
+use std::time::Duration;
+
+// Weight of the newest poll in the hit-rate average
+const HIT_RATE_ALPHA: f64 = 0.2;
+
+/// How a polling consumer paces itself while the bus is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiveStrategy {
+    /// Sleep the same interval after every empty poll
+    Fixed(Duration),
+    /// Sleep between `min` and `max`, tracking a moving average of how
+    /// often recent polls found a message
+    ///
+    /// While messages arrive frequently the interval stays near `min` to
+    /// keep latency low; as the bus goes idle it stretches toward `max`
+    /// to save CPU.
+    Adaptive { min: Duration, max: Duration },
+}
+
+impl Default for ReceiveStrategy {
+    fn default() -> Self {
+        ReceiveStrategy::Fixed(Duration::from_micros(100))
+    }
+}
+
+// Per-consumer pacing state for a ReceiveStrategy
+#[derive(Debug)]
+pub(crate) struct Poller {
+    strategy: ReceiveStrategy,
+    hit_rate: f64,
+}
+
+impl Poller {
+    pub(crate) fn new(strategy: ReceiveStrategy) -> Self {
+        Poller {
+            strategy,
+            hit_rate: 0.0,
+        }
+    }
+
+    /// Record whether a poll returned a message
+    pub(crate) fn observe(&mut self, hit: bool) {
+        let sample = if hit { 1.0 } else { 0.0 };
+        self.hit_rate += HIT_RATE_ALPHA * (sample - self.hit_rate);
+    }
+
+    /// How long to sleep after an empty poll
+    pub(crate) fn interval(&self) -> Duration {
+        match self.strategy {
+            ReceiveStrategy::Fixed(interval) => interval,
+            ReceiveStrategy::Adaptive { min, max } => {
+                let max = max.max(min);
+                min + (max - min).mul_f64(1.0 - self.hit_rate)
+            }
+        }
+    }
+}
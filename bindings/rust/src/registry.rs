@@ -0,0 +1,102 @@
+//! Named registry of buses for processes that manage many of them
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::{BusStats, DirectUniversalBus};
+
+/// Thread-safe registry of named buses
+///
+/// Buses are handed out as `Arc`s so lookups don't hold the registry lock.
+/// Removing (or replacing) a bus drops the registry's reference; the native
+/// handle is destroyed once the last outstanding reference is gone.
+#[derive(Default)]
+pub struct BusRegistry {
+    buses: RwLock<HashMap<String, Arc<DirectUniversalBus>>>,
+}
+
+impl BusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a bus under `name`, replacing any bus already registered
+    pub fn register(&self, name: impl Into<String>, bus: DirectUniversalBus) -> Arc<DirectUniversalBus> {
+        let bus = Arc::new(bus);
+        self.buses
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.into(), Arc::clone(&bus));
+        bus
+    }
+
+    /// Look up a bus by name
+    pub fn get(&self, name: &str) -> Option<Arc<DirectUniversalBus>> {
+        self.buses
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    /// Remove a bus, returning whether it was registered
+    pub fn remove(&self, name: &str) -> bool {
+        let removed = self
+            .buses
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name);
+        // Dropped outside the lock so destroying the handle can't stall lookups
+        removed.is_some()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.buses
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Stats of every registered bus, sorted by name
+    pub fn stats_per_bus(&self) -> Vec<(String, BusStats)> {
+        // Snapshot the buses so querying the core doesn't hold the lock
+        let mut buses: Vec<_> = self
+            .buses
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, bus)| (name.clone(), Arc::clone(bus)))
+            .collect();
+        buses.sort_by(|a, b| a.0.cmp(&b.0));
+
+        buses
+            .into_iter()
+            .map(|(name, bus)| (name, bus.stats()))
+            .collect()
+    }
+
+    /// Stats summed across every registered bus
+    ///
+    /// Counters saturate rather than wrap.
+    pub fn aggregate_stats(&self) -> BusStats {
+        self.stats_per_bus()
+            .into_iter()
+            .fold(BusStats::default(), |total, (_, stats)| BusStats {
+                total_messages: total.total_messages.saturating_add(stats.total_messages),
+                total_bytes: total.total_bytes.saturating_add(stats.total_bytes),
+                pending_messages: total.pending_messages.saturating_add(stats.pending_messages),
+                active_segments: total.active_segments.saturating_add(stats.active_segments),
+                dropped: total.dropped.saturating_add(&stats.dropped),
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.buses.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
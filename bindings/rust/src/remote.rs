@@ -0,0 +1,411 @@
+//! Access to a bus in another process over TCP
+//!
+//! Requests and responses are stream frames (see the framing layer), one
+//! response per request, in order:
+//!
+//! ```text
+//! request  = [u8 op] SEND: [u32 LE type id][payload]
+//!                    RECEIVE, STATS: (empty)
+//! response = [u8 0] SEND: (empty)
+//!                   RECEIVE: [u8 has message][payload]
+//!                   STATS: [u64 LE total messages][u64 LE total bytes]
+//!                          [u32 LE pending messages][u32 LE active segments]
+//!                          [u64 LE dropped] x6, in `DropReason` order
+//!          | [u8 1][u8 error code][detail]
+//! ```
+//!
+//! Every `BusError` variant has its own code. The detail carries the
+//! variant's fields:
+//!
+//! ```text
+//! ABI_MISMATCH: [u32 LE expected][u32 LE found]
+//! INVALID_ENV:  [u32 LE name length][UTF-8 name][UTF-8 value]
+//! IO:           [u8 kind][UTF-8 message]
+//! VALIDATION:   [u32 LE type id][UTF-8 reason]
+//! others:       [UTF-8 description], informational only
+//! ```
+
+use std::io::{self, BufReader, BufWriter};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::frame::{read_frame, write_frame};
+use crate::{BusError, BusStats, DirectUniversalBus, DropStats};
+
+const OP_SEND: u8 = 1;
+const OP_RECEIVE: u8 = 2;
+const OP_STATS: u8 = 3;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERR: u8 = 1;
+
+// How often the accept loop checks whether the server was stopped
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+const ERR_INVALID_PARAMS: u8 = 1;
+const ERR_BUFFER_FULL: u8 = 2;
+const ERR_INVALID_HANDLE: u8 = 3;
+const ERR_MEMORY_ALLOCATION: u8 = 4;
+const ERR_CORRUPTED_DATA: u8 = 5;
+const ERR_SHUTTING_DOWN: u8 = 6;
+const ERR_CREATION_FAILED: u8 = 7;
+const ERR_SCALING_CONFIG_FAILED: u8 = 8;
+const ERR_ABI_MISMATCH: u8 = 9;
+const ERR_INVALID_ENV: u8 = 10;
+const ERR_IO: u8 = 11;
+const ERR_VALIDATION: u8 = 12;
+const ERR_DUPLICATE: u8 = 13;
+const ERR_TIMEOUT: u8 = 14;
+
+// I/O error kinds by their wire index; kinds not listed travel as `Other`
+const IO_KINDS: [io::ErrorKind; 20] = [
+    io::ErrorKind::Other,
+    io::ErrorKind::NotFound,
+    io::ErrorKind::PermissionDenied,
+    io::ErrorKind::ConnectionRefused,
+    io::ErrorKind::ConnectionReset,
+    io::ErrorKind::ConnectionAborted,
+    io::ErrorKind::NotConnected,
+    io::ErrorKind::AddrInUse,
+    io::ErrorKind::AddrNotAvailable,
+    io::ErrorKind::BrokenPipe,
+    io::ErrorKind::AlreadyExists,
+    io::ErrorKind::WouldBlock,
+    io::ErrorKind::InvalidInput,
+    io::ErrorKind::InvalidData,
+    io::ErrorKind::TimedOut,
+    io::ErrorKind::WriteZero,
+    io::ErrorKind::Interrupted,
+    io::ErrorKind::Unsupported,
+    io::ErrorKind::UnexpectedEof,
+    io::ErrorKind::OutOfMemory,
+];
+
+// Append the error code and detail of an error response
+fn encode_error(e: &BusError, out: &mut Vec<u8>) {
+    let description = e.to_string();
+    let (code, detail) = match e {
+        BusError::InvalidParams => (ERR_INVALID_PARAMS, description.into_bytes()),
+        BusError::BufferFull => (ERR_BUFFER_FULL, description.into_bytes()),
+        BusError::InvalidHandle => (ERR_INVALID_HANDLE, description.into_bytes()),
+        BusError::MemoryAllocation => (ERR_MEMORY_ALLOCATION, description.into_bytes()),
+        BusError::CorruptedData => (ERR_CORRUPTED_DATA, description.into_bytes()),
+        BusError::ShuttingDown => (ERR_SHUTTING_DOWN, description.into_bytes()),
+        BusError::CreationFailed => (ERR_CREATION_FAILED, description.into_bytes()),
+        BusError::ScalingConfigFailed => (ERR_SCALING_CONFIG_FAILED, description.into_bytes()),
+        BusError::Duplicate => (ERR_DUPLICATE, description.into_bytes()),
+        BusError::Timeout => (ERR_TIMEOUT, description.into_bytes()),
+        BusError::AbiMismatch { expected, found } => {
+            let mut detail = expected.to_le_bytes().to_vec();
+            detail.extend_from_slice(&found.to_le_bytes());
+            (ERR_ABI_MISMATCH, detail)
+        }
+        BusError::InvalidEnv { name, value } => {
+            let mut detail = (name.len() as u32).to_le_bytes().to_vec();
+            detail.extend_from_slice(name.as_bytes());
+            detail.extend_from_slice(value.as_bytes());
+            (ERR_INVALID_ENV, detail)
+        }
+        BusError::Io { kind, message } => {
+            let index = IO_KINDS.iter().position(|k| k == kind).unwrap_or(0);
+            let mut detail = vec![index as u8];
+            detail.extend_from_slice(message.as_bytes());
+            (ERR_IO, detail)
+        }
+        BusError::Validation { type_id, reason } => {
+            let mut detail = type_id.to_le_bytes().to_vec();
+            detail.extend_from_slice(reason.as_bytes());
+            (ERR_VALIDATION, detail)
+        }
+    };
+    out.push(code);
+    out.extend_from_slice(&detail);
+}
+
+// Rebuild the error an error response describes; `None` if its detail is
+// malformed
+fn decode_error(code: u8, detail: &[u8]) -> Option<BusError> {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    let u32_at = |i: usize| Some(u32::from_le_bytes(detail.get(i..i + 4)?.try_into().ok()?));
+
+    let error = match code {
+        ERR_INVALID_PARAMS => BusError::InvalidParams,
+        ERR_BUFFER_FULL => BusError::BufferFull,
+        ERR_INVALID_HANDLE => BusError::InvalidHandle,
+        ERR_MEMORY_ALLOCATION => BusError::MemoryAllocation,
+        ERR_CORRUPTED_DATA => BusError::CorruptedData,
+        ERR_SHUTTING_DOWN => BusError::ShuttingDown,
+        ERR_CREATION_FAILED => BusError::CreationFailed,
+        ERR_SCALING_CONFIG_FAILED => BusError::ScalingConfigFailed,
+        ERR_DUPLICATE => BusError::Duplicate,
+        ERR_TIMEOUT => BusError::Timeout,
+        ERR_ABI_MISMATCH if detail.len() == 8 => BusError::AbiMismatch {
+            expected: u32_at(0)?,
+            found: u32_at(4)?,
+        },
+        ERR_INVALID_ENV => {
+            let name_len = u32_at(0)? as usize;
+            let name = detail.get(4..4usize.checked_add(name_len)?)?;
+            BusError::InvalidEnv {
+                name: text(name),
+                value: text(&detail[4 + name_len..]),
+            }
+        }
+        ERR_IO => {
+            let (&kind, message) = detail.split_first()?;
+            BusError::Io {
+                kind: *IO_KINDS.get(kind as usize)?,
+                message: text(message),
+            }
+        }
+        ERR_VALIDATION => BusError::Validation {
+            type_id: u32_at(0)?,
+            reason: text(&detail[4..]),
+        },
+        // A code from a newer server
+        _ => BusError::Io {
+            kind: io::ErrorKind::Other,
+            message: text(detail),
+        },
+    };
+    Some(error)
+}
+
+fn protocol_error(message: &str) -> BusError {
+    BusError::Io {
+        kind: io::ErrorKind::InvalidData,
+        message: message.to_string(),
+    }
+}
+
+/// Client for a bus exposed with [`serve`]
+///
+/// Mirrors the core send/receive/stats methods of [`DirectUniversalBus`],
+/// with network failures reported as [`BusError::Io`]. Calls from several
+/// threads are serialized over the one connection.
+///
+/// # Example
+/// ```rust
+/// let bus = RemoteBus::connect("10.0.0.5:7000")?;
+/// bus.send(b"hello", 1)?;
+/// ```
+pub struct RemoteBus {
+    connection: Mutex<Connection>,
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl RemoteBus {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, BusError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(RemoteBus {
+            connection: Mutex::new(Connection {
+                reader: BufReader::new(stream.try_clone()?),
+                writer: BufWriter::new(stream),
+            }),
+        })
+    }
+
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        let data = data.as_ref();
+        let mut request = Vec::with_capacity(5 + data.len());
+        request.push(OP_SEND);
+        request.extend_from_slice(&type_id.to_le_bytes());
+        request.extend_from_slice(data);
+        self.call(&request)?;
+        Ok(())
+    }
+
+    /// Receive the next message; failures are logged and reported as `None`
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        match self.try_receive() {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("[Rust Remote] Receive failed: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn try_receive(&self) -> Result<Option<Vec<u8>>, BusError> {
+        let response = self.call(&[OP_RECEIVE])?;
+        match response.split_first() {
+            Some((0, _)) => Ok(None),
+            Some((1, payload)) => Ok(Some(payload.to_vec())),
+            _ => Err(protocol_error("malformed receive response")),
+        }
+    }
+
+    pub fn stats(&self) -> Result<BusStats, BusError> {
+        let response = self.call(&[OP_STATS])?;
+        decode_stats(&response).ok_or_else(|| protocol_error("malformed stats response"))
+    }
+
+    // Send one request and return the body of a successful response
+    fn call(&self, request: &[u8]) -> Result<Vec<u8>, BusError> {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        write_frame(&mut connection.writer, request)?;
+        let response = read_frame(&mut connection.reader)?.ok_or_else(|| BusError::Io {
+            kind: io::ErrorKind::UnexpectedEof,
+            message: "server closed the connection".to_string(),
+        })?;
+
+        match response.split_first() {
+            Some((&STATUS_OK, body)) => Ok(body.to_vec()),
+            Some((&STATUS_ERR, [code, detail @ ..])) => {
+                Err(decode_error(*code, detail).unwrap_or_else(|| protocol_error("malformed error response")))
+            }
+            _ => Err(protocol_error("malformed response")),
+        }
+    }
+}
+
+fn encode_stats(stats: &BusStats, out: &mut Vec<u8>) {
+    out.extend_from_slice(&stats.total_messages.to_le_bytes());
+    out.extend_from_slice(&stats.total_bytes.to_le_bytes());
+    out.extend_from_slice(&stats.pending_messages.to_le_bytes());
+    out.extend_from_slice(&stats.active_segments.to_le_bytes());
+    for count in stats.dropped.counts() {
+        out.extend_from_slice(&count.to_le_bytes());
+    }
+}
+
+fn decode_stats(body: &[u8]) -> Option<BusStats> {
+    let body: &[u8; 72] = body.try_into().ok()?;
+    let u64_at = |i: usize| u64::from_le_bytes(body[i..i + 8].try_into().unwrap());
+    let u32_at = |i: usize| u32::from_le_bytes(body[i..i + 4].try_into().unwrap());
+    Some(BusStats {
+        total_messages: u64_at(0),
+        total_bytes: u64_at(8),
+        pending_messages: u32_at(16),
+        active_segments: u32_at(20),
+        dropped: DropStats::from_counts(std::array::from_fn(|i| u64_at(24 + i * 8))),
+    })
+}
+
+/// A running [`serve`] listener
+///
+/// Dropping it (or calling [`RemoteServer::stop`]) stops accepting new
+/// connections. Connections already open are served until their client
+/// disconnects.
+pub struct RemoteServer {
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl RemoteServer {
+    /// The address the server is listening on
+    ///
+    /// Useful after binding to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Expose `bus` to [`RemoteBus`] clients on `addr`
+///
+/// Connections are accepted on a background thread and each one is served
+/// on its own thread.
+///
+/// # Example
+/// ```rust
+/// let server = serve(Arc::new(DirectUniversalBus::with_defaults()?), "0.0.0.0:7000")?;
+/// println!("Serving on {}", server.local_addr());
+/// ```
+pub fn serve<A: ToSocketAddrs>(
+    bus: Arc<DirectUniversalBus>,
+    addr: A,
+) -> Result<RemoteServer, BusError> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    // Non-blocking so the accept loop can notice `stop`
+    listener.set_nonblocking(true)?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stopped);
+    let acceptor = std::thread::spawn(move || {
+        while !flag.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let bus = Arc::clone(&bus);
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve_connection(&bus, stream) {
+                            eprintln!("[Rust Remote] Connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+                Err(e) => eprintln!("[Rust Remote] Accept failed: {}", e),
+            }
+        }
+    });
+
+    Ok(RemoteServer {
+        local_addr,
+        stopped,
+        acceptor: Some(acceptor),
+    })
+}
+
+fn serve_connection(bus: &DirectUniversalBus, stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    while let Some(request) = read_frame(&mut reader)? {
+        let mut response = vec![STATUS_OK];
+        let result = match request.split_first() {
+            Some((&OP_SEND, [t0, t1, t2, t3, payload @ ..])) => {
+                bus.send(payload, u32::from_le_bytes([*t0, *t1, *t2, *t3]))
+            }
+            Some((&OP_RECEIVE, [])) => bus.try_receive().map(|message| match message {
+                Some(payload) => {
+                    response.push(1);
+                    response.extend_from_slice(&payload);
+                }
+                None => response.push(0),
+            }),
+            Some((&OP_STATS, [])) => {
+                encode_stats(&bus.stats(), &mut response);
+                Ok(())
+            }
+            _ => Err(BusError::InvalidParams),
+        };
+
+        if let Err(e) = result {
+            response.clear();
+            response.push(STATUS_ERR);
+            encode_error(&e, &mut response);
+        }
+        write_frame(&mut writer, &response)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Shared retry and backoff configuration
+
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::{BusError, DirectUniversalBus};
+
+/// Exponential backoff settings for retrying operations
+///
+/// The delay before retry `n` (starting at 0) is
+/// `initial_backoff * multiplier^n`, capped at `max_backoff`, then spread
+/// by up to `±jitter` of itself so many producers don't retry in lockstep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; `1` disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each delay to randomize by, between 0.0 and 1.0
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_micros(100),
+            max_backoff: Duration::from_millis(10),
+            multiplier: 2.0,
+            jitter: 0.1,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy retrying `retries` times with a constant delay and no jitter
+    pub fn fixed(retries: u32, backoff: Duration) -> Self {
+        RetryPolicy {
+            max_attempts: retries.saturating_add(1),
+            initial_backoff: backoff,
+            max_backoff: backoff,
+            multiplier: 1.0,
+            jitter: 0.0,
+        }
+    }
+
+    /// Delay to wait before retry number `retry` (0-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(retry.min(i32::MAX as u32) as i32);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let spread = if jitter > 0.0 {
+            // Uniform in [-jitter, jitter]
+            (unit_random() * 2.0 - 1.0) * jitter
+        } else {
+            0.0
+        };
+        Duration::from_secs_f64((capped * (1.0 + spread)).max(0.0))
+    }
+}
+
+// A random value in [0, 1) without pulling in an RNG crate; each
+// `RandomState` is seeded from fresh process randomness
+fn unit_random() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl DirectUniversalBus {
+    /// Set the default policy for retrying operations on this bus
+    /// 
+    /// Used by [`DirectUniversalBus::send_retrying`] and for the waits in
+    /// [`DirectUniversalBus::send_blocking`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Send data, retrying transient failures under the bus's policy
+    pub fn send_retrying<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        self.send_with_policy(data, type_id, &self.retry_policy)
+    }
+
+    /// Send data, retrying transient failures under `policy`
+    /// 
+    /// `BufferFull` and `MemoryAllocation` are retried until
+    /// `policy.max_attempts` attempts have been made; the last error is
+    /// returned if every attempt fails. Permanent errors such as
+    /// `InvalidParams` and `InvalidHandle` are returned immediately.
+    pub fn send_with_policy<T: AsRef<[u8]>>(
+        &self,
+        data: T,
+        type_id: u32,
+        policy: &RetryPolicy,
+    ) -> Result<(), BusError> {
+        let bytes = data.as_ref();
+        let mut retry = 0;
+        loop {
+            match self.send(bytes, type_id) {
+                Err(e) if e.is_transient() && retry + 1 < policy.max_attempts => {
+                    std::thread::sleep(policy.backoff(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
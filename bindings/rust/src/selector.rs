@@ -0,0 +1,70 @@
+//! Waiting on several buses from a single consumer loop
+
+use std::time::{Duration, Instant};
+
+use crate::DirectUniversalBus;
+
+/// Polls a set of buses and reports which one has data
+///
+/// Selection is level-triggered: a bus is reported for as long as it has
+/// pending messages, whether or not the caller drained it after the last
+/// `select`. It is also fair: scanning resumes after the bus reported last
+/// time, so a busy bus can't starve the others.
+pub struct BusSelector<'a> {
+    buses: Vec<&'a DirectUniversalBus>,
+    next: usize,
+}
+
+impl<'a> BusSelector<'a> {
+    pub fn new(buses: &[&'a DirectUniversalBus]) -> Self {
+        BusSelector {
+            buses: buses.to_vec(),
+            next: 0,
+        }
+    }
+
+    /// Add a bus, returning the index `select` will report for it
+    pub fn add(&mut self, bus: &'a DirectUniversalBus) -> usize {
+        self.buses.push(bus);
+        self.buses.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.buses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buses.is_empty()
+    }
+
+    /// Wait up to `timeout` for any bus to have pending messages
+    ///
+    /// Returns the index of the ready bus, or `None` on timeout.
+    pub fn select(&mut self, timeout: Duration) -> Option<usize> {
+        let start = Instant::now();
+        loop {
+            if let Some(index) = self.poll() {
+                return Some(index);
+            }
+
+            if start.elapsed() >= timeout {
+                return None;
+            }
+
+            std::thread::sleep(Duration::from_micros(100));
+        }
+    }
+
+    // One fair scan over all buses
+    fn poll(&mut self) -> Option<usize> {
+        let count = self.buses.len();
+        for offset in 0..count {
+            let index = (self.next + offset) % count;
+            if self.buses[index].stats().pending_messages > 0 {
+                self.next = (index + 1) % count;
+                return Some(index);
+            }
+        }
+        None
+    }
+}
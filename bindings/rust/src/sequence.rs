@@ -0,0 +1,200 @@
+//! Consumer-side detection of lost, duplicated and reordered messages
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
+
+use crate::{BusError, DirectUniversalBus, DropReason};
+
+// Bytes of the sequence prefix added by `send_ordered`
+const SEQUENCE_LEN: usize = 8;
+
+// Upper bound on remembered missing sequence numbers; older ones are
+// considered lost for good and a late arrival counts as a duplicate
+const MAX_TRACKED_MISSING: usize = 65536;
+
+/// Counters accumulated by a [`SequenceTracker`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// Sequence numbers observed, including duplicates
+    pub received: u64,
+    /// Number of forward jumps past the expected sequence number
+    pub gaps: u64,
+    /// Sequence numbers skipped by a gap that have not (yet) arrived
+    pub missing: u64,
+    /// Sequence numbers seen more than once
+    pub duplicates: u64,
+    /// Sequence numbers that arrived after a later one
+    pub reordered: u64,
+}
+
+/// Classification of a single observed sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceEvent {
+    InOrder,
+    /// Jumped ahead, skipping `missing` sequence numbers
+    Gap { missing: u64 },
+    Duplicate,
+    /// Filled in a previously reported gap
+    Reordered,
+}
+
+/// Tracks monotonically increasing sequence numbers on the receive side
+///
+/// Feed it the sequence field of every received message (e.g. carried in
+/// a header via `send_with_headers`). Anything still in `missing` once the
+/// producer has finished was dropped, for instance after `BufferFull`.
+///
+/// Sequence numbers are compared with serial number arithmetic: one
+/// within half the sequence space ahead of the expected number is ahead,
+/// anything else is behind. A producer whose counter wraps, such as a
+/// `u32` going from `u32::MAX` back to 0, is therefore followed across the
+/// wrap instead of being reported as a huge gap; see
+/// [`SequenceTracker::with_sequence_bits`].
+#[derive(Debug)]
+pub struct SequenceTracker {
+    // Positions are unwrapped so they keep increasing across a wrap
+    next_expected: Option<u64>,
+    outstanding: BTreeSet<u64>,
+    stats: SequenceStats,
+    mask: u64,
+}
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        SequenceTracker {
+            next_expected: None,
+            outstanding: BTreeSet::new(),
+            stats: SequenceStats::default(),
+            mask: u64::MAX,
+        }
+    }
+}
+
+impl SequenceTracker {
+    /// Create a tracker that accepts whatever sequence number arrives first
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a tracker that expects the stream to begin at `first`
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next_expected: Some(first),
+            ..Self::default()
+        }
+    }
+
+    /// Track sequence numbers that are `bits` wide and wrap to 0
+    ///
+    /// Use 32 for a producer with a `u32` counter. Numbers passed to
+    /// [`SequenceTracker::observe`] are truncated to `bits`, which is
+    /// clamped to 1..=64; the default is 64.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut tracker = SequenceTracker::new().with_sequence_bits(32);
+    /// tracker.observe(u32::MAX as u64);
+    /// assert_eq!(tracker.observe(0), SequenceEvent::InOrder);
+    /// ```
+    pub fn with_sequence_bits(mut self, bits: u32) -> Self {
+        self.mask = u64::MAX >> (64 - bits.clamp(1, 64));
+        if let Some(next) = &mut self.next_expected {
+            *next &= self.mask;
+        }
+        self
+    }
+
+    /// Record a received sequence number
+    pub fn observe(&mut self, seq: u64) -> SequenceEvent {
+        self.stats.received += 1;
+
+        let seq = seq & self.mask;
+        let expected = *self.next_expected.get_or_insert(seq);
+        let ahead = seq.wrapping_sub(expected) & self.mask;
+        if ahead == 0 {
+            self.next_expected = Some(expected.wrapping_add(1));
+            return SequenceEvent::InOrder;
+        }
+
+        if ahead <= self.mask / 2 {
+            let missing = ahead;
+            self.stats.gaps += 1;
+            self.stats.missing += missing;
+            // Only remember the most recent gaps so a huge jump can't exhaust memory
+            let position = expected.wrapping_add(ahead);
+            let start = expected.max(position.saturating_sub(MAX_TRACKED_MISSING as u64));
+            self.outstanding.extend(start..position);
+            while self.outstanding.len() > MAX_TRACKED_MISSING {
+                self.outstanding.pop_first();
+            }
+            self.next_expected = Some(position.wrapping_add(1));
+            return SequenceEvent::Gap { missing };
+        }
+
+        let behind = expected.wrapping_sub(seq) & self.mask;
+        if self.outstanding.remove(&expected.wrapping_sub(behind)) {
+            self.stats.missing -= 1;
+            self.stats.reordered += 1;
+            SequenceEvent::Reordered
+        } else {
+            self.stats.duplicates += 1;
+            SequenceEvent::Duplicate
+        }
+    }
+
+    pub fn stats(&self) -> SequenceStats {
+        self.stats
+    }
+
+    /// Sequence number expected next, if any message has been observed
+    pub fn next_expected(&self) -> Option<u64> {
+        self.next_expected.map(|next| next & self.mask)
+    }
+
+    /// Forget everything observed, keeping the sequence width
+    pub fn reset(&mut self) {
+        *self = Self {
+            mask: self.mask,
+            ..Self::default()
+        };
+    }
+}
+
+impl DirectUniversalBus {
+    /// Send with a bus-wide sequence number stamped in front of the payload
+    ///
+    /// Sequence numbers start at 0 and increase by one per call, across
+    /// all producers of this `DirectUniversalBus`. With several segments or
+    /// consumers, messages may be received out of this order; pair with
+    /// [`DirectUniversalBus::receive_ordered`] and a [`SequenceTracker`] to
+    /// detect it. A failed send still uses up its number, so it shows up
+    /// as a gap. Validators see the stamped payload.
+    ///
+    /// # Returns
+    /// The sequence number assigned to the message
+    pub fn send_ordered<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<u64, BusError> {
+        let seq = self.send_sequence.fetch_add(1, Ordering::Relaxed);
+        self.send_vectored(&[&seq.to_le_bytes(), data.as_ref()], type_id)?;
+        Ok(seq)
+    }
+
+    /// Receive a message sent with [`DirectUniversalBus::send_ordered`],
+    /// along with its sequence number
+    ///
+    /// A message too short to carry a sequence number is logged and
+    /// discarded, so ordered and plain messages should not be mixed on the
+    /// same bus.
+    pub fn receive_ordered(&self) -> Option<(u64, Vec<u8>)> {
+        loop {
+            let mut data = self.receive()?;
+            if data.len() < SEQUENCE_LEN {
+                eprintln!("[Rust Direct] Discarding message without a sequence number");
+                self.record_drop(&data, DropReason::Malformed);
+                continue;
+            }
+            let payload = data.split_off(SEQUENCE_LEN);
+            let seq = u64::from_le_bytes(data.try_into().ok()?);
+            return Some((seq, payload));
+        }
+    }
+}
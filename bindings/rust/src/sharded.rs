@@ -0,0 +1,127 @@
+//! Key-based sharding across several buses
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{BusError, DirectUniversalBus};
+
+// Points each shard places on the hash ring; more points spread keys more
+// evenly between shards
+const VIRTUAL_NODES: u32 = 64;
+
+fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Routes messages to one of several buses by key
+///
+/// Keys are placed on a consistent-hash ring, so adding or removing a
+/// shard only remaps the keys that land on (or leave) that shard; every
+/// other key keeps its bus. Messages with the same key always go to the
+/// same shard while the shard set is unchanged, preserving their order.
+///
+/// # Example
+/// ```rust
+/// let mut sharded = ShardedBus::new();
+/// for _ in 0..4 {
+///     sharded.add_shard(DirectUniversalBus::with_defaults()?);
+/// }
+/// sharded.send("customer-42", b"order placed", 1)?;
+/// ```
+#[derive(Default)]
+pub struct ShardedBus {
+    shards: Vec<(u32, DirectUniversalBus)>,
+    ring: BTreeMap<u64, u32>,
+    next_shard_id: u32,
+    next_receive: AtomicUsize,
+}
+
+impl ShardedBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a shard, returning its id
+    ///
+    /// Ids are never reused, so a key's shard id stays meaningful across
+    /// later additions and removals.
+    pub fn add_shard(&mut self, bus: DirectUniversalBus) -> u32 {
+        let id = self.next_shard_id;
+        self.next_shard_id += 1;
+        for vnode in 0..VIRTUAL_NODES {
+            self.ring.insert(hash_of(&(id, vnode)), id);
+        }
+        self.shards.push((id, bus));
+        id
+    }
+
+    /// Remove a shard, handing back its bus with any undelivered messages
+    pub fn remove_shard(&mut self, id: u32) -> Option<DirectUniversalBus> {
+        let index = self.shards.iter().position(|(shard, _)| *shard == id)?;
+        self.ring.retain(|_, shard| *shard != id);
+        Some(self.shards.remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// The id of the shard `key` routes to, or `None` without shards
+    pub fn shard_for<K: Hash + ?Sized>(&self, key: &K) -> Option<u32> {
+        let point = hash_of(key);
+        self.ring
+            .range(point..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, &id)| id)
+    }
+
+    pub fn shard(&self, id: u32) -> Option<&DirectUniversalBus> {
+        self.shards
+            .iter()
+            .find(|(shard, _)| *shard == id)
+            .map(|(_, bus)| bus)
+    }
+
+    /// Send to the shard that owns `key`
+    ///
+    /// # Errors
+    /// * `InvalidParams` - There are no shards
+    /// * Any error from [`DirectUniversalBus::send`] on the chosen shard
+    pub fn send<K, T>(&self, key: &K, data: T, type_id: u32) -> Result<(), BusError>
+    where
+        K: Hash + ?Sized,
+        T: AsRef<[u8]>,
+    {
+        let id = self.shard_for(key).ok_or(BusError::InvalidParams)?;
+        self.shard(id).ok_or(BusError::InvalidParams)?.send(data, type_id)
+    }
+
+    /// Receive from the shards in turn, starting after the last one that
+    /// returned a message
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        let count = self.shards.len();
+        let start = self.next_receive.load(Ordering::Relaxed);
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            if let Some(data) = self.shards[index].1.receive() {
+                self.next_receive.store(index + 1, Ordering::Relaxed);
+                return Some(data);
+            }
+        }
+        None
+    }
+
+    /// Receive only from the shard with `id`
+    pub fn receive_from(&self, id: u32) -> Option<Vec<u8>> {
+        self.shard(id)?.receive()
+    }
+}
@@ -0,0 +1,233 @@
+//! Traffic capture and replay for regression testing
+//!
+//! A tap log is a sequence of records:
+//!
+//! ```text
+//! [u8 direction][u64 LE offset µs][u32 LE type id][u32 LE length][payload]
+//! ```
+//!
+//! `direction` is 0 for sent and 1 for received messages; the offset is
+//! measured from when recording started.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{BusError, DirectUniversalBus, LanguageType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapDirection {
+    Sent = 0,
+    Received = 1,
+}
+
+/// One message captured by a [`TappedBus`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapRecord {
+    pub direction: TapDirection,
+    /// Time since recording started
+    pub offset: Duration,
+    pub type_id: u32,
+    pub payload: Vec<u8>,
+}
+
+struct Recording {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct TapState {
+    recording: Option<Recording>,
+    // Why the last recording was abandoned, until reported
+    failure: Option<BusError>,
+}
+
+/// Wraps a bus and optionally records all traffic through it to a file
+///
+/// Recording never gets in the way of the traffic itself. If writing to
+/// the log fails, the failure is logged, recording stops, and the error is
+/// returned by the next [`TappedBus::stop_recording`].
+pub struct TappedBus {
+    bus: DirectUniversalBus,
+    state: Mutex<TapState>,
+}
+
+impl TappedBus {
+    pub fn new(bus: DirectUniversalBus) -> Self {
+        TappedBus {
+            bus,
+            state: Mutex::new(TapState::default()),
+        }
+    }
+
+    /// Start recording to `path`, truncating it
+    ///
+    /// Replaces any recording already in progress, and clears the error of
+    /// an abandoned one.
+    pub fn start_recording<P: AsRef<Path>>(&self, path: P) -> Result<(), BusError> {
+        let writer = BufWriter::new(File::create(path)?);
+        *self.lock_state() = TapState {
+            recording: Some(Recording {
+                writer,
+                started: Instant::now(),
+            }),
+            failure: None,
+        };
+        Ok(())
+    }
+
+    /// Stop recording and flush the log
+    ///
+    /// Returns the write error if the recording was abandoned part way.
+    pub fn stop_recording(&self) -> Result<(), BusError> {
+        let mut state = self.lock_state();
+        if let Some(e) = state.failure.take() {
+            return Err(e);
+        }
+        match state.recording.take() {
+            Some(mut recording) => Ok(recording.writer.flush()?),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.lock_state().recording.is_some()
+    }
+
+    /// Send through the bus, recording the message if it was accepted
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        let data = data.as_ref();
+        self.bus.send(data, type_id)?;
+        self.record(TapDirection::Sent, type_id, data);
+        Ok(())
+    }
+
+    /// Receive from the bus, recording the message
+    ///
+    /// Received records carry the type id the message was sent with, so a
+    /// replayed log keeps its per-type routing.
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        let (type_id, data) = self
+            .bus
+            .drain_with(LanguageType::Rust, |origin, data| (origin.type_id, data.to_vec()))?;
+        self.record(TapDirection::Received, type_id, &data);
+        Some(data)
+    }
+
+    pub fn inner(&self) -> &DirectUniversalBus {
+        &self.bus
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, TapState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // A failed write leaves a torn record, so the rest of the log is
+    // abandoned rather than appended to
+    fn record(&self, direction: TapDirection, type_id: u32, payload: &[u8]) {
+        let mut state = self.lock_state();
+        let Some(recording) = state.recording.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = write_record(recording, direction, type_id, payload) {
+            eprintln!("[Rust Direct] Tap recording stopped: {}", e);
+            state.recording = None;
+            state.failure = Some(e);
+        }
+    }
+}
+
+fn write_record(
+    recording: &mut Recording,
+    direction: TapDirection,
+    type_id: u32,
+    payload: &[u8],
+) -> Result<(), BusError> {
+    let offset = recording.started.elapsed().as_micros() as u64;
+    let len = u32::try_from(payload.len()).map_err(|_| BusError::InvalidParams)?;
+    let writer = &mut recording.writer;
+    writer.write_all(&[direction as u8])?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&type_id.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)?;
+    Ok(())
+}
+
+impl Drop for TappedBus {
+    fn drop(&mut self) {
+        if let Some(mut recording) = self.lock_state().recording.take() {
+            if let Err(e) = recording.writer.flush() {
+                eprintln!("[Rust Direct] Failed to flush tap log: {}", e);
+            }
+        }
+    }
+}
+
+/// Read every record from a tap log
+///
+/// Returns `CorruptedData` if the log ends mid-record or has an unknown
+/// direction byte.
+pub fn read_tap_log<P: AsRef<Path>>(path: P) -> Result<Vec<TapRecord>, BusError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+
+    loop {
+        let mut direction = [0u8; 1];
+        if reader.read(&mut direction)? == 0 {
+            return Ok(records);
+        }
+        let direction = match direction[0] {
+            0 => TapDirection::Sent,
+            1 => TapDirection::Received,
+            _ => return Err(BusError::CorruptedData),
+        };
+
+        let mut header = [0u8; 16];
+        read_record_part(&mut reader, &mut header)?;
+        let offset = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let type_id = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let len = u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize;
+
+        let mut payload = vec![0u8; len];
+        read_record_part(&mut reader, &mut payload)?;
+
+        records.push(TapRecord {
+            direction,
+            offset: Duration::from_micros(offset),
+            type_id,
+            payload,
+        });
+    }
+}
+
+// A short read inside a record means the log was truncated
+fn read_record_part<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), BusError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => BusError::CorruptedData,
+        _ => e.into(),
+    })
+}
+
+/// Re-submit the sent messages of a tap log to `bus`
+///
+/// Messages are spaced out to match their recorded offsets. Received
+/// records are skipped. Returns the number of messages sent.
+pub fn replay<P: AsRef<Path>>(path: P, bus: &DirectUniversalBus) -> Result<usize, BusError> {
+    let records = read_tap_log(path)?;
+    let started = Instant::now();
+    let mut sent = 0;
+
+    for record in records.iter().filter(|r| r.direction == TapDirection::Sent) {
+        if let Some(wait) = record.offset.checked_sub(started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+        bus.send(&record.payload, record.type_id)?;
+        sent += 1;
+    }
+    Ok(sent)
+}
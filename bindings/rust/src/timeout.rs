@@ -0,0 +1,45 @@
+//! Hard deadlines on submits to a core that may block
+
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{BusError, DirectUniversalBus};
+
+impl DirectUniversalBus {
+    /// Send, giving up with [`BusError::Timeout`] if the submit takes longer
+    /// than `timeout`
+    ///
+    /// Unlike the full-buffer retries of [`DirectUniversalBus::send_with_retry`],
+    /// this guards against the core itself blocking, e.g. on an internal
+    /// lock. The submit runs on a helper thread that is abandoned, not
+    /// cancelled, when the deadline passes: a timed-out message may still
+    /// be delivered later, and the helper keeps the bus alive until it
+    /// returns. Each call spawns a thread, so keep this off hot paths.
+    ///
+    /// # Example
+    /// ```rust
+    /// let bus = Arc::new(DirectUniversalBus::with_defaults()?);
+    /// match bus.send_timeout(b"reading", 1, Duration::from_millis(50)) {
+    ///     Err(BusError::Timeout) => metrics.count_wedged_submit(),
+    ///     result => result?,
+    /// }
+    /// ```
+    pub fn send_timeout<T: AsRef<[u8]>>(
+        self: &Arc<Self>,
+        data: T,
+        type_id: u32,
+        timeout: Duration,
+    ) -> Result<(), BusError> {
+        let bus = Arc::clone(self);
+        let data = data.as_ref().to_vec();
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            // The caller may have stopped waiting; nothing to report then
+            let _ = tx.send(bus.send(data, type_id));
+        });
+
+        rx.recv_timeout(timeout).unwrap_or(Err(BusError::Timeout))
+    }
+}
@@ -0,0 +1,92 @@
+//! Send-side middleware for rewriting or dropping outgoing messages
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{BusError, DirectUniversalBus};
+
+type MapFn = Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+type FilterFn = Box<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+enum Stage {
+    Map(MapFn),
+    Filter(FilterFn),
+}
+
+/// Applies a pipeline of transforms and filters to every sent message
+///
+/// Stages run in the order they were added, so a filter sees the output
+/// of the maps before it. Receiving is unaffected.
+///
+/// # Example
+/// ```rust
+/// let bus = TransformBus::new(DirectUniversalBus::with_defaults()?)
+///     .filter(|m| !m.is_empty())
+///     .map(|m| [b"app:".as_slice(), m].concat());
+/// bus.send(b"hello", 1)?;
+/// ```
+pub struct TransformBus {
+    bus: DirectUniversalBus,
+    stages: Vec<Stage>,
+    filtered: AtomicU64,
+}
+
+impl TransformBus {
+    pub fn new(bus: DirectUniversalBus) -> Self {
+        TransformBus {
+            bus,
+            stages: Vec::new(),
+            filtered: AtomicU64::new(0),
+        }
+    }
+
+    /// Append a stage that rewrites each message
+    pub fn map<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Map(Box::new(f)));
+        self
+    }
+
+    /// Append a stage that drops messages for which `f` returns false
+    pub fn filter<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Filter(Box::new(f)));
+        self
+    }
+
+    /// Run the pipeline and send the result
+    ///
+    /// A message dropped by a filter is not an error: `Ok(())` is returned
+    /// and it is counted in [`TransformBus::filtered_count`].
+    pub fn send<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        let mut message = std::borrow::Cow::Borrowed(data.as_ref());
+        for stage in &self.stages {
+            match stage {
+                Stage::Map(f) => message = std::borrow::Cow::Owned(f(&message)),
+                Stage::Filter(f) => {
+                    if !f(&message) {
+                        self.filtered.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        self.bus.send(&message[..], type_id)
+    }
+
+    /// Number of messages dropped by filters so far
+    pub fn filtered_count(&self) -> u64 {
+        self.filtered.load(Ordering::Relaxed)
+    }
+
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.bus.receive()
+    }
+
+    pub fn inner(&self) -> &DirectUniversalBus {
+        &self.bus
+    }
+}
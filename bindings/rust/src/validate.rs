@@ -0,0 +1,106 @@
+//! Producer-side schema validation per type id
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{BusError, DirectUniversalBus, DropReason};
+
+type ValidatorFn = dyn Fn(&[u8]) -> Result<(), ValidationError> + Send + Sync;
+
+/// Why a validator rejected a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    reason: String,
+}
+
+impl ValidationError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        ValidationError {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Default)]
+pub(crate) struct Validators {
+    by_type: Mutex<HashMap<u32, Arc<ValidatorFn>>>,
+}
+
+impl DirectUniversalBus {
+    /// Check every message sent with `type_id` before it is submitted
+    ///
+    /// `send`, `send_owned` and the wrappers built on them run the validator
+    /// and fail with [`BusError::Validation`] without submitting when it
+    /// rejects the payload. Replaces any validator already registered for
+    /// `type_id`.
+    /// Use [`DirectUniversalBus::send_unvalidated`] to skip validation on
+    /// trusted fast paths.
+    ///
+    /// # Example
+    /// ```rust
+    /// bus.register_validator(1, |data| {
+    ///     std::str::from_utf8(data)
+    ///         .map(|_| ())
+    ///         .map_err(|_| ValidationError::new("not UTF-8"))
+    /// });
+    /// ```
+    pub fn register_validator<F>(&self, type_id: u32, validator: F)
+    where
+        F: Fn(&[u8]) -> Result<(), ValidationError> + Send + Sync + 'static,
+    {
+        self.validators
+            .by_type
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(type_id, Arc::new(validator));
+    }
+
+    /// Remove the validator for `type_id`, if any
+    pub fn unregister_validator(&self, type_id: u32) {
+        self.validators
+            .by_type
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&type_id);
+    }
+
+    /// Send without running the validator registered for `type_id`
+    pub fn send_unvalidated<T: AsRef<[u8]>>(&self, data: T, type_id: u32) -> Result<(), BusError> {
+        self.send_unreserved(data.as_ref(), type_id)
+    }
+
+    pub(crate) fn validate(&self, data: &[u8], type_id: u32) -> Result<(), BusError> {
+        // Run the validator outside the lock so a slow one can't block
+        // registration or other senders
+        let validator = self
+            .validators
+            .by_type
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&type_id)
+            .cloned();
+
+        let Some(validator) = validator else {
+            return Ok(());
+        };
+        validator(data).map_err(|e| {
+            self.record_drop(data, DropReason::Validation);
+            BusError::Validation {
+                type_id,
+                reason: e.reason,
+            }
+        })
+    }
+}
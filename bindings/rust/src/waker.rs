@@ -0,0 +1,104 @@
+//! Cross-thread wakeup for blocked receives
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::DirectUniversalBus;
+
+// Poll interval while waiting for a message
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+// Set by a wake and cleared only by an explicit reset
+#[derive(Default)]
+pub(crate) struct WakeState {
+    set: Mutex<bool>,
+    woken: Condvar,
+}
+
+impl WakeState {
+    fn set(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.set.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        *self.set()
+    }
+
+    pub(crate) fn reset(&self) {
+        *self.set() = false;
+    }
+}
+
+/// Handle for waking receivers blocked in `receive_timeout`
+///
+/// Cheap to clone and safe to use from any thread. A wake stays set until
+/// [`ReceiveWaker::reset`], so one that lands between two
+/// `receive_timeout` calls still cuts the next one short.
+#[derive(Clone)]
+pub struct ReceiveWaker {
+    state: Arc<WakeState>,
+}
+
+impl ReceiveWaker {
+    /// Make `receive_timeout` on the bus return `None` once the bus is
+    /// empty, for calls in progress and every later call until reset
+    pub fn wake(&self) {
+        *self.state.set() = true;
+        self.state.woken.notify_all();
+    }
+
+    /// Clear a wake so `receive_timeout` waits out its timeout again
+    pub fn reset(&self) {
+        self.state.reset();
+    }
+
+    pub fn is_woken(&self) -> bool {
+        self.state.is_set()
+    }
+}
+
+impl DirectUniversalBus {
+    /// Get a handle that interrupts blocked receives on this bus
+    /// 
+    /// # Example
+    /// ```rust
+    /// let waker = bus.receive_waker();
+    /// std::thread::spawn(move || {
+    ///     wait_for_shutdown_request();
+    ///     waker.wake();
+    /// });
+    /// while let Some(data) = bus.receive_timeout(Duration::from_secs(30)) {
+    ///     handle(data);
+    /// }
+    /// ```
+    pub fn receive_waker(&self) -> ReceiveWaker {
+        ReceiveWaker {
+            state: Arc::clone(&self.wake_state),
+        }
+    }
+
+    /// Wait up to `timeout` for a message
+    /// 
+    /// Returns `None` on timeout, or as soon as there is no message and a
+    /// [`ReceiveWaker`] for this bus has been woken and not reset.
+    pub fn receive_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(data) = self.receive() {
+                return Some(data);
+            }
+
+            let set = self.wake_state.set();
+            if *set {
+                return None;
+            }
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            // Sleeps on the condvar so a wake cuts the wait short
+            let _ = self
+                .wake_state
+                .woken
+                .wait_timeout(set, remaining.min(POLL_INTERVAL));
+        }
+    }
+}
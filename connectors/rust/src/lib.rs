@@ -1,9 +1,14 @@
 // Rust Connector for UMSBB WebAssembly Core
 // Direct memory binding without API overhead
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
 use std::os::raw::{c_char, c_int, c_uint, c_void};
 
 // Error codes matching the C interface
@@ -17,6 +22,7 @@ pub enum UMSBBError {
     InvalidHandle = -4,
     MemoryAllocation = -5,
     CorruptedData = -6,
+    Io = -7,
 }
 
 impl UMSBBError {
@@ -29,6 +35,7 @@ impl UMSBBError {
             UMSBBError::InvalidHandle => "Invalid buffer handle",
             UMSBBError::MemoryAllocation => "Memory allocation failed",
             UMSBBError::CorruptedData => "Corrupted data detected",
+            UMSBBError::Io => "I/O error",
         }
     }
 }
@@ -41,15 +48,307 @@ impl std::fmt::Display for UMSBBError {
 
 impl std::error::Error for UMSBBError {}
 
+/// Which implementation a buffer operation ran against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The in-process mock used when the WebAssembly core isn't loaded
+    Mock,
+    /// The WebAssembly core through FFI
+    Native,
+}
+
+impl UMSBBError {
+    /// Attach the backend that produced this error, for logging
+    ///
+    /// # Example
+    /// ```rust
+    /// buffer.write(data).map_err(|e| e.with_backend(buffer.backend()))?;
+    /// ```
+    pub fn with_backend(self, backend: Backend) -> BackendError {
+        BackendError {
+            error: self,
+            backend,
+        }
+    }
+}
+
+/// An error tagged with the backend it came from
+///
+/// Displays as e.g. `Buffer is full (backend=Mock)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackendError {
+    pub error: UMSBBError,
+    pub backend: Backend,
+}
+
+impl std::fmt::Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (backend={:?})", self.error, self.backend)
+    }
+}
+
+impl std::error::Error for BackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<BackendError> for UMSBBError {
+    fn from(e: BackendError) -> Self {
+        e.error
+    }
+}
+
+impl From<std::io::Error> for UMSBBError {
+    fn from(_: std::io::Error) -> Self {
+        UMSBBError::Io
+    }
+}
+
 // Buffer statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct BufferStats {
     pub total_messages: u64,
     pub total_bytes: u64,
     pub pending_messages: u32,
     pub active_segments: u32,
+    /// Messages discarded by the [`OverflowPolicy`], evicted or rejected
+    pub dropped_messages: u64,
+    /// Messages dropped unread once their TTL passed
+    pub expired_messages: u64,
+    /// Bytes held by pending messages
+    pub used_bytes: u64,
+    /// Bytes the buffer can hold
+    pub capacity_bytes: u64,
+}
+
+impl BufferStats {
+    /// Size of the wire encoding produced by [`BufferStats::to_bytes`]
+    pub const ENCODED_LEN: usize = 56;
+
+    /// Messages discarded so far for `reason`
+    pub fn dropped(&self, reason: DropReason) -> u64 {
+        match reason {
+            DropReason::Overflow => self.dropped_messages,
+            DropReason::Expired => self.expired_messages,
+        }
+    }
+
+    /// Fraction of the byte capacity in use, from 0.0 to 1.0
+    ///
+    /// 0.0 when the capacity is unknown.
+    pub fn fill_ratio(&self) -> f64 {
+        if self.capacity_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f64 / self.capacity_bytes as f64
+    }
+
+    /// Encode as fixed little-endian fields for transmission
+    ///
+    /// Layout: `total_messages: u64`, `total_bytes: u64`,
+    /// `pending_messages: u32`, `active_segments: u32`,
+    /// `dropped_messages: u64`, `used_bytes: u64`, `capacity_bytes: u64`,
+    /// `expired_messages: u64`, with no padding.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        bytes[0..8].copy_from_slice(&self.total_messages.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.total_bytes.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.pending_messages.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.active_segments.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.dropped_messages.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.used_bytes.to_le_bytes());
+        bytes[40..48].copy_from_slice(&self.capacity_bytes.to_le_bytes());
+        bytes[48..56].copy_from_slice(&self.expired_messages.to_le_bytes());
+        bytes
+    }
+
+    /// Decode stats produced by [`BufferStats::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let bytes: &[u8; Self::ENCODED_LEN] = bytes.try_into().map_err(|_| ParseError::InvalidLength {
+            expected: Self::ENCODED_LEN,
+            found: bytes.len(),
+        })?;
+
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        Ok(BufferStats {
+            total_messages: u64_at(0),
+            total_bytes: u64_at(8),
+            pending_messages: u32_at(16),
+            active_segments: u32_at(20),
+            dropped_messages: u64_at(24),
+            expired_messages: u64_at(48),
+            used_bytes: u64_at(32),
+            capacity_bytes: u64_at(40),
+        })
+    }
+}
+
+/// Message counts in consecutive one-second windows
+///
+/// Unlike an average over a whole run, the spread of per-second rates
+/// tells a steady load from a bursty one. Seconds with no messages count
+/// as zero-rate windows; the most recent window may be partial.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateHistogram {
+    windows: Vec<u64>,
+}
+
+impl RateHistogram {
+    /// Messages in each window, oldest first
+    pub fn windows(&self) -> &[u64] {
+        &self.windows
+    }
+
+    /// The per-second rate that `percent`% of windows are at or below
+    ///
+    /// 0 when there are no windows.
+    pub fn percentile(&self, percent: u32) -> u64 {
+        let mut sorted = self.windows.clone();
+        sorted.sort_unstable();
+        let index = sorted.len().saturating_sub(1) * percent.min(100) as usize / 100;
+        sorted.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn p50(&self) -> u64 {
+        self.percentile(50)
+    }
+
+    pub fn p99(&self) -> u64 {
+        self.percentile(99)
+    }
+
+    /// Highest per-second rate seen
+    pub fn peak(&self) -> u64 {
+        self.windows.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Source of the current time for TTL expiry, timeouts and write rates
+///
+/// Buffers use [`SystemClock`] unless given another clock with
+/// [`UMSBBBuffer::set_clock`]; tests can substitute a `MockClock` (with
+/// the `test-util` feature) to control time explicitly.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when advanced
+///
+/// # Example
+/// ```rust
+/// let clock = Arc::new(MockClock::new());
+/// buffer.set_clock(clock.clone());
+/// buffer.write_with_ttl(b"stale", Duration::from_secs(5))?;
+/// clock.advance(Duration::from_secs(6));
+/// assert_eq!(buffer.read()?, None);
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<Instant>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// Start at the current real time
+    pub fn new() -> Self {
+        MockClock {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *lock_mock(&self.now) += by;
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *lock_mock(&self.now)
+    }
+}
+
+/// What a write does when the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discard the incoming message and report success
+    DropNewest,
+    /// Evict the oldest queued messages until the new one fits
+    ///
+    /// Nothing is evicted for a write that fails anyway, whether because
+    /// evicting everything still wouldn't make room or because another
+    /// check rejects it.
+    DropOldest,
+    /// Wait until a reader makes room
+    Block,
+    /// Reject the write with `BufferFull`
+    #[default]
+    Error,
+}
+
+/// Why a buffer discarded a message, as passed to [`UMSBBBuffer::on_drop`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// Evicted or rejected by a dropping [`OverflowPolicy`], or not taken
+    /// back by a full core after a failed [`UMSBBBuffer::drain_to_file`]
+    Overflow,
+    /// Skipped by a read after its TTL passed
+    Expired,
+}
+
+impl DropReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropReason::Overflow => "overflow",
+            DropReason::Expired => "expired",
+        }
+    }
+}
+
+impl std::fmt::Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Errors decoding wire-encoded structures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseError {
+    InvalidLength { expected: usize, found: usize },
 }
 
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidLength { expected, found } => {
+                write!(f, "Invalid length: expected {} bytes, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 // Result type for UMSBB operations
 pub type UMSBBResult<T> = Result<T, UMSBBError>;
 
@@ -57,14 +356,303 @@ pub type UMSBBResult<T> = Result<T, UMSBBError>;
 lazy_static::lazy_static! {
     static ref MOCK_BUFFERS: Arc<Mutex<HashMap<u32, MockBuffer>>> = Arc::new(Mutex::new(HashMap::new()));
     static ref NEXT_HANDLE: Arc<Mutex<u32>> = Arc::new(Mutex::new(1));
+    static ref FREE_HANDLES: Arc<Mutex<Vec<BufferHandle>>> = Arc::new(Mutex::new(Vec::new()));
+}
+
+// Cap on bytes held across all mock buffers; u64::MAX when unlimited
+static MOCK_MEMORY_LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Cap the bytes held across every mock buffer in the process
+///
+/// Writes that would take the total over `limit` fail with
+/// `MemoryAllocation`, keeping test processes that create many buffers
+/// bounded. `None` removes the cap. Messages already held are unaffected.
+pub fn set_mock_memory_limit(limit: Option<usize>) {
+    let limit = limit.map_or(u64::MAX, |bytes| bytes as u64);
+    MOCK_MEMORY_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// Bytes currently held across every mock buffer in the process
+pub fn mock_total_memory() -> usize {
+    mock_held_total(&lock_mock(&MOCK_BUFFERS)) as usize
+}
+
+fn mock_held_total(handles: &HashMap<u32, MockBuffer>) -> u64 {
+    handles.values().map(MockBuffer::held_bytes).sum()
+}
+
+// Whether holding `total` bytes across all mock buffers is within the cap
+fn within_mock_memory_limit(total: u64) -> bool {
+    total <= MOCK_MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Identifies a live buffer
+///
+/// Buffer ids are recycled once a buffer is destroyed, so each handle
+/// also carries the generation of the id it was issued for. A handle
+/// kept past its buffer's destruction no longer matches the recycled
+/// id's generation and is rejected with `InvalidHandle` rather than
+/// silently addressing whichever buffer reused the id. Deliberately not
+/// `Copy` or `Clone`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct BufferHandle {
+    id: u32,
+    generation: u32,
+}
+
+impl BufferHandle {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+// Lock mock state, recovering from poisoning so that one thread panicking
+// while holding the lock doesn't break every buffer in the process
+fn lock_mock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Look up a mock buffer, rejecting handles from an earlier generation
+fn mock_buffer<'a>(
+    handles: &'a mut HashMap<u32, MockBuffer>,
+    handle: &BufferHandle,
+) -> Option<&'a mut MockBuffer> {
+    handles
+        .get_mut(&handle.id)
+        .filter(|buffer| buffer.generation == handle.generation)
 }
 
 #[derive(Debug)]
 struct MockBuffer {
+    generation: u32,
     size_mb: u32,
-    messages: Vec<Vec<u8>>,
+    overflow: OverflowPolicy,
+    dropped_messages: u64,
+    expired_messages: u64,
+    messages: Vec<MockMessage>,
     total_messages: u64,
     total_bytes: u64,
+    pending_bytes: u64,
+    groups: HashMap<String, MockGroup>,
+    broadcast: Option<MockBroadcast>,
+    faults: MockFaults,
+    write_rates: RateRecorder,
+}
+
+impl MockBuffer {
+    fn capacity_bytes(&self) -> u64 {
+        self.size_mb as u64 * 1024 * 1024
+    }
+
+    // Bytes currently held, whichever queue they sit in
+    fn held_bytes(&self) -> u64 {
+        match &self.broadcast {
+            Some(broadcast) => broadcast.log_bytes,
+            None => self.pending_bytes,
+        }
+    }
+
+    // Whether one more message of `incoming` bytes fits
+    fn has_room_for(&self, incoming: u64) -> bool {
+        self.held_bytes() + incoming <= self.capacity_bytes()
+    }
+
+    // How many queued messages to evict from the head for `incoming` bytes
+    // to fit, and the bytes that frees; `None` if evicting every queued
+    // message still isn't enough
+    fn eviction_for(&self, incoming: u64) -> Option<(usize, u64)> {
+        let (mut count, mut freed) = (0, 0);
+        while self.held_bytes() - freed + incoming > self.capacity_bytes() {
+            freed += self.messages.get(count)?.data.len() as u64;
+            count += 1;
+        }
+        Some((count, freed))
+    }
+
+    // Evict the first `count` queued messages, handing them to `evicted`
+    fn evict_front(&mut self, count: usize, evicted: &mut Vec<Vec<u8>>) {
+        for message in self.messages.drain(..count) {
+            self.pending_bytes -= message.data.len() as u64;
+            self.dropped_messages += 1;
+            evicted.push(message.data);
+        }
+    }
+
+    // Append a message without checking capacity
+    fn push(&mut self, data: &[u8], expires_at: Option<Instant>, now: Instant) {
+        self.total_messages += 1;
+        self.total_bytes += data.len() as u64;
+        self.write_rates.record(now);
+
+        if let Some(broadcast) = &mut self.broadcast {
+            if !broadcast.cursors.is_empty() {
+                broadcast.log.push_back(data.to_vec());
+                broadcast.log_bytes += data.len() as u64;
+            } else {
+                broadcast.base_seq += 1;
+            }
+            return;
+        }
+
+        self.messages.push(MockMessage {
+            data: data.to_vec(),
+            expires_at,
+        });
+        self.pending_bytes += data.len() as u64;
+    }
+
+    fn pending_messages(&self) -> usize {
+        if let Some(broadcast) = &self.broadcast {
+            return broadcast.log.len();
+        }
+        self.messages.len() + self.groups.values().map(MockGroup::assigned_messages).sum::<usize>()
+    }
+}
+
+// Seed for the failure-injection RNG, reset by every `mock_set_failure_rate`
+#[cfg(any(test, feature = "test-util"))]
+const MOCK_FAULT_SEED: u64 = 0x5EED_5EED_5EED_5EED;
+
+#[derive(Debug, Clone, Copy)]
+enum MockOp {
+    Read,
+    Write,
+}
+
+// Latency and failures injected into mock operations for resilience tests
+#[derive(Debug, Default)]
+struct MockFaults {
+    read_latency: Duration,
+    write_latency: Duration,
+    failure_rate: f32,
+    rng: u64,
+}
+
+impl MockFaults {
+    // How long to delay `op` and whether it should fail
+    fn next(&mut self, op: MockOp) -> (Duration, bool) {
+        let latency = match op {
+            MockOp::Read => self.read_latency,
+            MockOp::Write => self.write_latency,
+        };
+        if self.failure_rate <= 0.0 {
+            return (latency, false);
+        }
+
+        // splitmix64: tiny, and deterministic for a given seed
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let sample = (z >> 40) as f32 / (1u64 << 24) as f32;
+        (latency, sample < self.failure_rate)
+    }
+}
+
+// Most recent one-second windows kept by a RateRecorder
+const MAX_RATE_WINDOWS: usize = 3600;
+
+// Counts events into one-second windows starting at the first event
+#[derive(Debug, Default)]
+struct RateRecorder {
+    started: Option<Instant>,
+    // Index, counted from `started`, of the front window
+    first_window: u64,
+    windows: VecDeque<u64>,
+}
+
+impl RateRecorder {
+    fn record(&mut self, now: Instant) {
+        let started = *self.started.get_or_insert(now);
+        let index = now.saturating_duration_since(started).as_secs();
+        let last = self.first_window + self.windows.len() as u64;
+        // Open windows up to `index`, leaving idle seconds at zero
+        for _ in last..=index {
+            self.windows.push_back(0);
+            if self.windows.len() > MAX_RATE_WINDOWS {
+                self.windows.pop_front();
+                self.first_window += 1;
+            }
+        }
+        if let Some(count) = self.windows.back_mut() {
+            *count += 1;
+        }
+    }
+
+    fn histogram(&self) -> RateHistogram {
+        RateHistogram {
+            windows: self.windows.iter().copied().collect(),
+        }
+    }
+}
+
+// A queued message, optionally dropped unread once its deadline passes
+#[derive(Debug)]
+struct MockMessage {
+    data: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl MockMessage {
+    fn new(data: Vec<u8>) -> Self {
+        MockMessage {
+            data,
+            expires_at: None,
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|deadline| now >= deadline)
+    }
+}
+
+// Broadcast state: a shared log with one read cursor per subscriber
+#[derive(Debug, Default)]
+struct MockBroadcast {
+    base_seq: u64,
+    log: VecDeque<Vec<u8>>,
+    log_bytes: u64,
+    next_subscriber_id: u32,
+    cursors: HashMap<u32, u64>,
+}
+
+impl MockBroadcast {
+    // Drop log entries every subscriber has advanced past
+    fn reclaim(&mut self) {
+        let oldest = self
+            .cursors
+            .values()
+            .copied()
+            .min()
+            .unwrap_or(self.base_seq + self.log.len() as u64);
+        while self.base_seq < oldest {
+            let Some(message) = self.log.pop_front() else {
+                break;
+            };
+            self.log_bytes -= message.len() as u64;
+            self.base_seq += 1;
+        }
+    }
+}
+
+// Consumer group state: messages are handed out to members round-robin
+#[derive(Debug, Default)]
+struct MockGroup {
+    next_member_id: u32,
+    members: Vec<u32>,
+    next_turn: usize,
+    assigned: HashMap<u32, Vec<Vec<u8>>>,
+}
+
+impl MockGroup {
+    fn assigned_messages(&self) -> usize {
+        self.assigned.values().map(Vec::len).sum()
+    }
 }
 
 // WebAssembly external functions (will be linked when WASM module is available)
@@ -75,12 +663,35 @@ extern "C" {
     fn umsbb_get_total_messages(handle: u32) -> u64;
     fn umsbb_get_total_bytes(handle: u32) -> u64;
     fn umsbb_get_pending_messages(handle: u32) -> u32;
+    fn umsbb_reset_stats(handle: u32) -> c_int;
     fn umsbb_destroy_buffer(handle: u32) -> c_int;
 }
 
 pub struct UMSBBBuffer {
-    handle: u32,
+    handle: BufferHandle,
     use_mock: bool,
+    hooks: TransitionHooks,
+    on_expire: Option<ExpireHook>,
+    on_drop: Option<DropHook>,
+    overflow: OverflowPolicy,
+    clock: Arc<dyn Clock>,
+}
+
+type TransitionHook = Box<dyn Fn() + Send + Sync>;
+type ExpireHook = Box<dyn Fn(&[u8]) + Send + Sync>;
+type DropHook = Box<dyn Fn(&[u8], DropReason) + Send + Sync>;
+
+// Edge-triggered callbacks fired on empty <-> non-empty transitions
+#[derive(Default)]
+struct TransitionHooks {
+    on_readable: Option<TransitionHook>,
+    on_empty: Option<TransitionHook>,
+}
+
+impl TransitionHooks {
+    fn is_set(&self) -> bool {
+        self.on_readable.is_some() || self.on_empty.is_some()
+    }
 }
 
 impl UMSBBBuffer {
@@ -97,53 +708,203 @@ impl UMSBBBuffer {
             (handle, true)
         };
 
-        if handle == 0 {
+        if handle.id == 0 {
             return Err(UMSBBError::MemoryAllocation);
         }
 
-        Ok(UMSBBBuffer { handle, use_mock })
+        Ok(UMSBBBuffer {
+            handle,
+            use_mock,
+            hooks: TransitionHooks::default(),
+            on_expire: None,
+            on_drop: None,
+            overflow: OverflowPolicy::Error,
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Create a buffer in broadcast (pub-sub) mode
+    ///
+    /// Every [`Subscriber`] receives its own copy of each message written
+    /// after it subscribed. A message is reclaimed once all current
+    /// subscribers have read past it, so messages written while nobody is
+    /// subscribed are discarded. Plain [`UMSBBBuffer::read`] is rejected
+    /// with `InvalidParams` on a broadcast buffer.
+    pub fn new_broadcast(size_mb: u32) -> UMSBBResult<Self> {
+        let buffer = Self::new(size_mb)?;
+        if buffer.use_mock {
+            Self::mock_enable_broadcast(&buffer.handle);
+        }
+        Ok(buffer)
+    }
+
+    /// Create a buffer that applies `policy` when a write finds it full
+    ///
+    /// Applies to single-message writes; [`UMSBBBuffer::write_batch_atomic`]
+    /// always fails with `BufferFull` rather than dropping. Discarded
+    /// messages are counted in [`BufferStats::dropped_messages`] and passed
+    /// to the [`UMSBBBuffer::on_drop`] callback.
+    pub fn with_overflow_policy(size_mb: u32, policy: OverflowPolicy) -> UMSBBResult<Self> {
+        let mut buffer = Self::new(size_mb)?;
+        match (buffer.use_mock, policy) {
+            (true, _) => Self::mock_set_overflow_policy(&buffer.handle, policy),
+            // The core can only reject; blocking is handled on this side
+            (false, OverflowPolicy::Block | OverflowPolicy::Error) => {}
+            (false, _) => return Err(UMSBBError::InvalidParams),
+        }
+        buffer.overflow = policy;
+        Ok(buffer)
+    }
+
+    /// Whether operations on this buffer run against the mock or the core
+    pub fn backend(&self) -> Backend {
+        if self.use_mock {
+            Backend::Mock
+        } else {
+            Backend::Native
+        }
+    }
+
+    /// The handle identifying this buffer
+    pub fn handle(&self) -> &BufferHandle {
+        &self.handle
     }
 
     pub fn write(&self, data: &[u8]) -> UMSBBResult<()> {
+        self.write_expiring(data, None)
+    }
+
+    /// Write a message that is dropped unread if still queued after `ttl`
+    ///
+    /// Expired messages are skipped by [`UMSBBBuffer::read`], which hands
+    /// each one to the [`UMSBBBuffer::on_expire`] callback. Once a message
+    /// has been dealt to a consumer group member it is delivered regardless
+    /// of its TTL. Returns `InvalidParams` on a broadcast buffer.
+    pub fn write_with_ttl(&self, data: &[u8], ttl: Duration) -> UMSBBResult<()> {
+        if !self.use_mock {
+            // The core has no per-message deadlines
+            return Err(UMSBBError::InvalidParams);
+        }
+        self.write_expiring(data, Some(self.clock.now() + ttl))
+    }
+
+    fn write_expiring(&self, data: &[u8], expires_at: Option<Instant>) -> UMSBBResult<()> {
         if data.len() > 65536 {
             return Err(UMSBBError::InvalidParams);
         }
 
-        let result = if self.use_mock {
-            Self::mock_write_message(self.handle, data)
+        let pending_before = self.pending_before_op();
+        let mut overflowed = Vec::new();
+        let mut result = self.write_once(data, expires_at, &mut overflowed);
+        while result == -2 && self.overflow == OverflowPolicy::Block {
+            std::thread::sleep(Duration::from_micros(100));
+            result = self.write_once(data, expires_at, &mut overflowed);
+        }
+        self.fire_drop_hook(&overflowed, DropReason::Overflow);
+
+        match result {
+            0 => {
+                self.fire_transition_hooks(pending_before);
+                Ok(())
+            }
+            -1 => Err(UMSBBError::InvalidParams),
+            -2 => Err(UMSBBError::BufferFull),
+            -4 => Err(UMSBBError::InvalidHandle),
+            -5 => Err(UMSBBError::MemoryAllocation),
+            _ => Err(UMSBBError::CorruptedData),
+        }
+    }
+
+    fn write_once(&self, data: &[u8], expires_at: Option<Instant>, overflowed: &mut Vec<Vec<u8>>) -> c_int {
+        if self.use_mock {
+            if Self::mock_inject_faults(&self.handle, MockOp::Write) {
+                return -5; // Injected failure
+            }
+            Self::mock_write_message(&self.handle, data, expires_at, self.clock.now(), overflowed)
         } else {
             unsafe {
                 umsbb_write_message(
-                    self.handle,
+                    self.handle.id,
                     data.as_ptr() as *const c_void,
                     data.len() as u32,
                 )
             }
-        };
+        }
+    }
+
+    pub fn write_string(&self, data: &str) -> UMSBBResult<()> {
+        self.write(data.as_bytes())
+    }
+
+    /// Write all of `messages` or none of them
+    ///
+    /// Room for the whole batch is checked before anything is written; if
+    /// it doesn't all fit, `BufferFull` is returned and the buffer is left
+    /// untouched. Other writers never see part of the batch.
+    pub fn write_batch_atomic(&self, messages: &[&[u8]]) -> UMSBBResult<()> {
+        if messages.iter().any(|m| m.len() > 65536) {
+            return Err(UMSBBError::InvalidParams);
+        }
+        if !self.use_mock {
+            // The core only accepts one message per call
+            return Err(UMSBBError::InvalidParams);
+        }
 
+        let pending_before = self.pending_before_op();
+        let result = if Self::mock_inject_faults(&self.handle, MockOp::Write) {
+            -5 // Injected failure
+        } else {
+            Self::mock_write_batch(&self.handle, messages, self.clock.now())
+        };
         match result {
-            0 => Ok(()),
-            -1 => Err(UMSBBError::InvalidParams),
+            0 => {
+                self.fire_transition_hooks(pending_before);
+                Ok(())
+            }
             -2 => Err(UMSBBError::BufferFull),
             -4 => Err(UMSBBError::InvalidHandle),
+            -5 => Err(UMSBBError::MemoryAllocation),
             _ => Err(UMSBBError::CorruptedData),
         }
     }
 
-    pub fn write_string(&self, data: &str) -> UMSBBResult<()> {
-        self.write(data.as_bytes())
-    }
-
     pub fn read(&self) -> UMSBBResult<Option<Vec<u8>>> {
         let mut buffer = vec![0u8; 65536]; // 64KB buffer
+        Ok(self.read_into(&mut buffer)?.map(|len| {
+            buffer.truncate(len);
+            buffer
+        }))
+    }
+
+    /// Read the next message into a caller-provided buffer
+    ///
+    /// Avoids the 64KB allocation `read` makes per call, so a fixed stack
+    /// buffer can be reused. Returns the message length, or
+    /// `InvalidParams` if the next message doesn't fit in `buffer`, in
+    /// which case it stays queued.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut buf = [0u8; 256];
+    /// while let Some(len) = buffer.read_into(&mut buf)? {
+    ///     handle(&buf[..len]);
+    /// }
+    /// ```
+    pub fn read_into(&self, buffer: &mut [u8]) -> UMSBBResult<Option<usize>> {
         let mut actual_size: u32 = 0;
+        let mut expired = Vec::new();
 
+        let pending_before = self.pending_before_op();
         let result = if self.use_mock {
-            Self::mock_read_message(self.handle, &mut buffer, &mut actual_size)
+            if Self::mock_inject_faults(&self.handle, MockOp::Read) {
+                return Err(UMSBBError::CorruptedData);
+            }
+            let now = self.clock.now();
+            Self::mock_read_message(&self.handle, buffer, &mut actual_size, &mut expired, now)
         } else {
             unsafe {
                 umsbb_read_message(
-                    self.handle,
+                    self.handle.id,
                     buffer.as_mut_ptr() as *mut c_void,
                     buffer.len() as u32,
                     &mut actual_size,
@@ -151,12 +912,26 @@ impl UMSBBBuffer {
             }
         };
 
+        // Run callbacks only after the mock lock has been released
+        if let Some(hook) = &self.on_expire {
+            for message in &expired {
+                hook(message);
+            }
+        }
+        self.fire_drop_hook(&expired, DropReason::Expired);
+
         match result {
             0 => {
-                buffer.truncate(actual_size as usize);
-                Ok(Some(buffer))
+                self.fire_transition_hooks(pending_before);
+                Ok(Some(actual_size as usize))
+            }
+            -3 => {
+                // Dropping expired messages may have emptied the buffer
+                if !expired.is_empty() {
+                    self.fire_transition_hooks(pending_before);
+                }
+                Ok(None) // Buffer empty
             }
-            -3 => Ok(None), // Buffer empty
             -1 => Err(UMSBBError::InvalidParams),
             -4 => Err(UMSBBError::InvalidHandle),
             _ => Err(UMSBBError::CorruptedData),
@@ -173,21 +948,135 @@ impl UMSBBBuffer {
         }
     }
 
+    /// Read a message as text, replacing invalid UTF-8 with U+FFFD
+    pub fn read_string_lossy(&self) -> UMSBBResult<Option<String>> {
+        Ok(self
+            .read()?
+            .map(|data| String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// Read the next message that is valid UTF-8
+    ///
+    /// Messages that aren't valid UTF-8 are discarded, so one bad payload
+    /// doesn't stop a text consumer. Errors from the buffer itself are
+    /// still returned.
+    pub fn read_skip_corrupt(&self) -> UMSBBResult<Option<String>> {
+        while let Some(data) = self.read()? {
+            if let Ok(s) = String::from_utf8(data) {
+                return Ok(Some(s));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Drain every pending message into `path`, for a durable shutdown
+    ///
+    /// Messages are written to a temporary file next to `path` which is
+    /// renamed over it once complete, so `path` never holds a partial
+    /// drain. If saving fails, the drained messages are put back at the
+    /// head of the buffer in their original order and the `Io` error is
+    /// returned. A mock buffer takes them back regardless of its capacity;
+    /// the core only takes what fits, and the rest are passed to the
+    /// [`UMSBBBuffer::on_drop`] hook as [`DropReason::Overflow`]. Reload a
+    /// saved drain with [`UMSBBBuffer::load_from_file`].
+    ///
+    /// # Returns
+    /// Number of messages saved
+    pub fn drain_to_file(&self, path: &Path) -> UMSBBResult<usize> {
+        let mut drained = Vec::new();
+        while let Some(message) = self.read()? {
+            drained.push(message);
+        }
+
+        if let Err(e) = save_messages(path, &drained) {
+            self.requeue_front(drained);
+            return Err(e.into());
+        }
+        Ok(drained.len())
+    }
+
+    // Put drained messages back ahead of anything written since. The mock
+    // already counted them in its totals, so only the queue changes.
+    fn requeue_front(&self, messages: Vec<Vec<u8>>) {
+        if !self.use_mock {
+            // The core can only append, and may be full by now
+            let lost: Vec<Vec<u8>> = messages
+                .into_iter()
+                .filter(|message| self.write(message).is_err())
+                .collect();
+            self.fire_drop_hook(&lost, DropReason::Overflow);
+            return;
+        }
+
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        if let Some(buffer) = mock_buffer(&mut handles, &self.handle) {
+            buffer.pending_bytes += messages.iter().map(|m| m.len() as u64).sum::<u64>();
+            buffer.messages.splice(0..0, messages.into_iter().map(MockMessage::new));
+        }
+    }
+
+    /// Re-enqueue messages saved by [`UMSBBBuffer::drain_to_file`]
+    ///
+    /// A file with the wrong header is rejected with `CorruptedData`
+    /// before anything is written. A message cut off at the end of the
+    /// file, as left by an interrupted write, is skipped and the complete
+    /// messages before it are loaded.
+    ///
+    /// # Returns
+    /// Number of messages loaded
+    pub fn load_from_file(&self, path: &Path) -> UMSBBResult<usize> {
+        let messages = load_messages(path)?;
+        for message in &messages {
+            self.write(message)?;
+        }
+        Ok(messages.len())
+    }
+
     pub fn get_stats(&self) -> BufferStats {
+        let mut stats = BufferStats::default();
+        self.stats_into(&mut stats);
+        stats
+    }
+
+    /// Fill a caller-owned `BufferStats`, for high-frequency polling loops
+    pub fn stats_into(&self, out: &mut BufferStats) {
         if self.use_mock {
-            Self::mock_get_stats(self.handle)
+            Self::mock_stats_into(&self.handle, out);
         } else {
             unsafe {
-                BufferStats {
-                    total_messages: umsbb_get_total_messages(self.handle),
-                    total_bytes: umsbb_get_total_bytes(self.handle),
-                    pending_messages: umsbb_get_pending_messages(self.handle),
-                    active_segments: 0, // TODO: Implement in core
-                }
+                out.total_messages = umsbb_get_total_messages(self.handle.id);
+                out.total_bytes = umsbb_get_total_bytes(self.handle.id);
+                out.pending_messages = umsbb_get_pending_messages(self.handle.id);
+                out.active_segments = 0; // TODO: Implement in core
+                out.dropped_messages = 0; // The core never drops
+                out.expired_messages = 0;
+                out.used_bytes = 0; // TODO: Implement in core
+                out.capacity_bytes = 0; // TODO: Implement in core
             }
         }
     }
 
+    /// Zero the cumulative counters
+    ///
+    /// Resets `total_messages`, `total_bytes`, `dropped_messages` and
+    /// `expired_messages` so the next
+    /// [`UMSBBBuffer::get_stats`] reports traffic since this call.
+    /// `pending_messages` and `active_segments` describe what the buffer
+    /// currently holds and are unaffected, as are the queued messages.
+    pub fn reset_stats(&self) -> UMSBBResult<()> {
+        let result = if self.use_mock {
+            Self::mock_reset_stats(&self.handle)
+        } else {
+            unsafe { umsbb_reset_stats(self.handle.id) }
+        };
+
+        match result {
+            0 => Ok(()),
+            -4 => Err(UMSBBError::InvalidHandle),
+            _ => Err(UMSBBError::InvalidParams),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.get_stats().pending_messages == 0
     }
@@ -196,53 +1085,401 @@ impl UMSBBBuffer {
         self.get_stats().pending_messages
     }
 
+    /// Per-second write rates over the buffer's last hour of activity
+    ///
+    /// Windows start at the first write. Always empty natively, where
+    /// the core keeps no timeline.
+    pub fn rate_histogram(&self) -> RateHistogram {
+        if !self.use_mock {
+            return RateHistogram::default();
+        }
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        mock_buffer(&mut handles, &self.handle)
+            .map(|buffer| buffer.write_rates.histogram())
+            .unwrap_or_default()
+    }
+
+    /// Whether a `size`-byte message would currently be accepted
+    ///
+    /// Checks the buffer's byte and message capacity, and the mock memory
+    /// limit, without writing anything. The answer can be stale by the
+    /// time of the next write if other threads share the buffer. The core
+    /// exposes no occupancy, so natively only the message size limit is
+    /// checked.
+    pub fn can_send(&self, size: usize) -> bool {
+        if size > 65536 {
+            return false;
+        }
+        if !self.use_mock {
+            return true;
+        }
+
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let total_held = mock_held_total(&handles);
+        mock_buffer(&mut handles, &self.handle).is_some_and(|buffer| {
+            buffer.has_room_for(size as u64) && within_mock_memory_limit(total_held + size as u64)
+        })
+    }
+
+    /// Wait up to `timeout` for consumers to drain the buffer
+    ///
+    /// A barrier for phased pipelines: a producer calls this after its
+    /// last send of a phase. Returns whether the buffer emptied in time.
+    pub fn wait_empty(&self, timeout: Duration) -> UMSBBResult<bool> {
+        let deadline = self.clock.now() + timeout;
+        while !self.is_empty() {
+            if self.clock.now() >= deadline {
+                return Ok(false);
+            }
+            std::thread::sleep(Duration::from_micros(100));
+        }
+        Ok(true)
+    }
+
+    /// Register a callback fired when the buffer goes from empty to non-empty
+    ///
+    /// Transitions are detected by comparing `pending_messages` before and
+    /// after each `write`/`read` on this buffer, so with concurrent writers
+    /// and readers a transition may be observed by either side.
+    pub fn on_readable<F>(&mut self, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.on_readable = Some(Box::new(f));
+    }
+
+    /// Register a callback fired when the buffer drains to empty
+    pub fn on_empty<F>(&mut self, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.on_empty = Some(Box::new(f));
+    }
+
+    /// Populate the mock queue directly, as if `messages` had been written
+    ///
+    /// Intended for setting up test fixtures in one call: the messages are
+    /// appended in order and the totals updated, bypassing the capacity
+    /// checks and transition hooks of [`UMSBBBuffer::write`]. Panics on a
+    /// buffer that is not backed by the mock or is in broadcast mode.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn mock_preload(&self, messages: Vec<Vec<u8>>) {
+        assert!(self.use_mock, "mock_preload requires a mock buffer");
+
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let buffer = mock_buffer(&mut handles, &self.handle).expect("buffer was destroyed");
+        assert!(buffer.broadcast.is_none(), "mock_preload does not support broadcast buffers");
+
+        for data in messages {
+            buffer.total_messages += 1;
+            buffer.total_bytes += data.len() as u64;
+            buffer.pending_bytes += data.len() as u64;
+            buffer.messages.push(MockMessage::new(data));
+        }
+    }
+
+    /// Delay every mock read and write by a fixed amount
+    ///
+    /// Simulates a slow backend for exercising consumer timeouts. The
+    /// sleep happens outside the mock lock, so other buffers are not
+    /// slowed down. Panics on a buffer that is not backed by the mock.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn mock_set_latency(&self, read: Duration, write: Duration) {
+        self.with_mock_faults(|faults| {
+            faults.read_latency = read;
+            faults.write_latency = write;
+        });
+    }
+
+    /// Fail a `rate` fraction of mock reads and writes
+    ///
+    /// Failed writes return `MemoryAllocation` and failed reads
+    /// `CorruptedData`, leaving the queue untouched. Failures are drawn
+    /// from an RNG reseeded with a fixed seed on every call, so the same
+    /// sequence of operations fails the same way on every run. Panics on
+    /// a buffer that is not backed by the mock.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn mock_set_failure_rate(&self, rate: f32) {
+        self.with_mock_faults(|faults| {
+            faults.failure_rate = rate.clamp(0.0, 1.0);
+            faults.rng = MOCK_FAULT_SEED;
+        });
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    fn with_mock_faults(&self, f: impl FnOnce(&mut MockFaults)) {
+        assert!(self.use_mock, "fault injection requires a mock buffer");
+
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let buffer = mock_buffer(&mut handles, &self.handle).expect("buffer was destroyed");
+        f(&mut buffer.faults);
+    }
+
+    /// Use `clock` for TTL deadlines, timeouts and write rates on this buffer
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Register a callback invoked with each message dropped by TTL expiry
+    ///
+    /// The callback runs inside [`UMSBBBuffer::read`], once per expired
+    /// message skipped on the way to the next live one, and replaces any
+    /// previously registered callback.
+    pub fn on_expire<F>(&mut self, f: F)
+    where
+        F: Fn(&[u8]) + Send + Sync + 'static,
+    {
+        self.on_expire = Some(Box::new(f));
+    }
+
+    /// Register a callback invoked with every message the buffer discards
+    ///
+    /// Covers messages evicted or rejected by a dropping
+    /// [`OverflowPolicy`], run inside the write that discarded them, and
+    /// messages skipped by [`UMSBBBuffer::read`] after their TTL passed.
+    /// Per-reason counts are in [`BufferStats::dropped`]. Replaces any
+    /// previously registered callback.
+    pub fn on_drop<F>(&mut self, f: F)
+    where
+        F: Fn(&[u8], DropReason) + Send + Sync + 'static,
+    {
+        self.on_drop = Some(Box::new(f));
+    }
+
+    fn fire_drop_hook(&self, messages: &[Vec<u8>], reason: DropReason) {
+        if let Some(hook) = &self.on_drop {
+            for message in messages {
+                hook(message, reason);
+            }
+        }
+    }
+
+    // Only pay for the extra stats lookup when a hook is registered
+    fn pending_before_op(&self) -> Option<u32> {
+        if self.hooks.is_set() {
+            Some(self.pending_count())
+        } else {
+            None
+        }
+    }
+
+    fn fire_transition_hooks(&self, pending_before: Option<u32>) {
+        let Some(before) = pending_before else {
+            return;
+        };
+
+        let after = self.pending_count();
+        let hook = match (before, after) {
+            (0, 1..) => &self.hooks.on_readable,
+            (1.., 0) => &self.hooks.on_empty,
+            _ => return,
+        };
+        if let Some(hook) = hook {
+            hook();
+        }
+    }
+
+    /// Get a consumer group on this buffer
+    ///
+    /// Members joined to the same group compete for messages: each message
+    /// is delivered to exactly one member, assigned round-robin. Separate
+    /// groups on the same buffer also compete for the underlying stream.
+    pub fn consumer_group(&self, group_id: &str) -> ConsumerGroup<'_> {
+        ConsumerGroup {
+            buffer: self,
+            group_id: group_id.to_string(),
+        }
+    }
+
+    /// Subscribe to a broadcast buffer
+    ///
+    /// The subscriber sees every message written from this point on.
+    /// Returns `InvalidParams` if the buffer was not created with
+    /// [`UMSBBBuffer::new_broadcast`].
+    pub fn subscribe(&self) -> UMSBBResult<Subscriber<'_>> {
+        if !self.use_mock {
+            return Err(UMSBBError::InvalidParams);
+        }
+
+        let subscriber_id = Self::mock_subscribe(&self.handle, false)?;
+        Ok(Subscriber {
+            buffer: self,
+            subscriber_id,
+        })
+    }
+
+    /// Open an independent, seekable read position on a broadcast buffer
+    ///
+    /// Unlike [`UMSBBBuffer::subscribe`], a cursor starts at the oldest
+    /// message still retained, so it can replay what other cursors have
+    /// already read. Returns `InvalidParams` if the buffer was not created
+    /// with [`UMSBBBuffer::new_broadcast`].
+    pub fn new_cursor(&self) -> UMSBBResult<Cursor<'_>> {
+        if !self.use_mock {
+            return Err(UMSBBError::InvalidParams);
+        }
+
+        let subscriber_id = Self::mock_subscribe(&self.handle, true)?;
+        Ok(Cursor {
+            subscriber: Subscriber {
+                buffer: self,
+                subscriber_id,
+            },
+        })
+    }
+
     // Mock implementation for development
-    fn mock_create_buffer(size_mb: u32) -> u32 {
-        let mut handles = MOCK_BUFFERS.lock().unwrap();
-        let mut next_handle = NEXT_HANDLE.lock().unwrap();
-        
-        let handle = *next_handle;
-        *next_handle += 1;
+    fn mock_create_buffer(size_mb: u32) -> BufferHandle {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+
+        // Recycle a destroyed buffer's id under a fresh generation
+        let recycled = lock_mock(&FREE_HANDLES).pop();
+        let handle = match recycled {
+            Some(stale) => BufferHandle {
+                id: stale.id,
+                generation: stale.generation.wrapping_add(1),
+            },
+            None => {
+                let mut next_handle = lock_mock(&NEXT_HANDLE);
+                let id = *next_handle;
+                *next_handle += 1;
+                BufferHandle { id, generation: 0 }
+            }
+        };
         
-        handles.insert(handle, MockBuffer {
+        handles.insert(handle.id, MockBuffer {
+            generation: handle.generation,
             size_mb,
+            overflow: OverflowPolicy::Error,
+            dropped_messages: 0,
+            expired_messages: 0,
             messages: Vec::new(),
             total_messages: 0,
             total_bytes: 0,
+            pending_bytes: 0,
+            groups: HashMap::new(),
+            broadcast: None,
+            faults: MockFaults::default(),
+            write_rates: RateRecorder::default(),
         });
         
         handle
     }
 
-    fn mock_write_message(handle: u32, data: &[u8]) -> c_int {
-        let mut handles = MOCK_BUFFERS.lock().unwrap();
+    // Messages discarded by the overflow policy are appended to `overflowed`;
+    // the write counts towards the rate window containing `now`
+    fn mock_write_message(
+        handle: &BufferHandle,
+        data: &[u8],
+        expires_at: Option<Instant>,
+        now: Instant,
+        overflowed: &mut Vec<Vec<u8>>,
+    ) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let total_held = mock_held_total(&handles);
         
-        if let Some(buffer) = handles.get_mut(&handle) {
-            if buffer.messages.len() > 1000 {
+        if let Some(buffer) = mock_buffer(&mut handles, handle) {
+            if buffer.broadcast.is_some() && expires_at.is_some() {
+                return -1; // Invalid params
+            }
+
+            // Plan any eviction, but only carry it out once nothing else
+            // can reject the write
+            let incoming = data.len() as u64;
+            let eviction = match buffer.overflow {
+                OverflowPolicy::DropOldest => buffer.eviction_for(incoming),
+                _ => buffer.has_room_for(incoming).then_some((0, 0)),
+            };
+            let Some((evict_count, freed)) = eviction else {
+                if buffer.overflow == OverflowPolicy::DropNewest {
+                    buffer.dropped_messages += 1;
+                    overflowed.push(data.to_vec());
+                    return 0; // Discarded as requested
+                }
                 return -2; // Buffer full
+            };
+
+            if !within_mock_memory_limit(total_held - freed + incoming) {
+                return -5; // Memory allocation
             }
-            
-            buffer.messages.push(data.to_vec());
-            buffer.total_messages += 1;
-            buffer.total_bytes += data.len() as u64;
+
+            buffer.evict_front(evict_count, overflowed);
+            buffer.push(data, expires_at, now);
             0 // Success
         } else {
             -4 // Invalid handle
         }
     }
 
-    fn mock_read_message(handle: u32, buffer: &mut [u8], actual_size: &mut u32) -> c_int {
-        let mut handles = MOCK_BUFFERS.lock().unwrap();
+    fn mock_write_batch(handle: &BufferHandle, messages: &[&[u8]], now: Instant) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let total_held = mock_held_total(&handles);
+        let Some(buffer) = mock_buffer(&mut handles, handle) else {
+            return -4; // Invalid handle
+        };
+
+        // Check room for the whole batch before committing any of it
+        let batch_bytes: u64 = messages.iter().map(|m| m.len() as u64).sum();
+        if buffer.held_bytes() + batch_bytes > buffer.capacity_bytes() {
+            return -2; // Buffer full
+        }
+        if !within_mock_memory_limit(total_held + batch_bytes) {
+            return -5; // Memory allocation
+        }
+
+        for data in messages {
+            buffer.push(data, None, now);
+        }
+        0 // Success
+    }
+
+    // Sleep for any injected latency, then report whether to fail `op`
+    fn mock_inject_faults(handle: &BufferHandle, op: MockOp) -> bool {
+        let (latency, fail) = {
+            let mut handles = lock_mock(&MOCK_BUFFERS);
+            match mock_buffer(&mut handles, handle) {
+                Some(buffer) => buffer.faults.next(op),
+                None => return false,
+            }
+        };
+
+        if !latency.is_zero() {
+            std::thread::sleep(latency);
+        }
+        fail
+    }
+
+    fn mock_read_message(
+        handle: &BufferHandle,
+        buffer: &mut [u8],
+        actual_size: &mut u32,
+        expired: &mut Vec<Vec<u8>>,
+        now: Instant,
+    ) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
         
-        if let Some(mock_buffer) = handles.get_mut(&handle) {
-            if mock_buffer.messages.is_empty() {
+        if let Some(mock_buffer) = mock_buffer(&mut handles, handle) {
+            if mock_buffer.broadcast.is_some() {
+                return -1; // Broadcast buffers are read through subscribers
+            }
+
+            // Skip past expired messages, handing them back to the caller
+            while mock_buffer.messages.first().is_some_and(|m| m.is_expired(now)) {
+                let message = mock_buffer.messages.remove(0);
+                mock_buffer.pending_bytes -= message.data.len() as u64;
+                mock_buffer.expired_messages += 1;
+                expired.push(message.data);
+            }
+
+            let Some(next) = mock_buffer.messages.first() else {
                 return -3; // Buffer empty
+            };
+            if next.data.len() > buffer.len() {
+                return -1; // Invalid params; leave it for a larger buffer
             }
             
-            let message = mock_buffer.messages.remove(0);
-            if message.len() > buffer.len() {
-                return -1; // Invalid params
-            }
+            let message = mock_buffer.messages.remove(0).data;
+            mock_buffer.pending_bytes -= message.len() as u64;
             
             buffer[..message.len()].copy_from_slice(&message);
             *actual_size = message.len() as u32;
@@ -252,40 +1489,450 @@ impl UMSBBBuffer {
         }
     }
 
-    fn mock_get_stats(handle: u32) -> BufferStats {
-        let handles = MOCK_BUFFERS.lock().unwrap();
-        
-        if let Some(buffer) = handles.get(&handle) {
-            BufferStats {
-                total_messages: buffer.total_messages,
-                total_bytes: buffer.total_bytes,
-                pending_messages: buffer.messages.len() as u32,
-                active_segments: 0,
+    fn mock_stats_into(handle: &BufferHandle, out: &mut BufferStats) {
+        // Copy the counters out under the lock and write `out` after it
+        // is released, keeping the critical section to a few loads
+        let (total_messages, total_bytes, pending_messages, dropped_messages, expired_messages, used_bytes, capacity_bytes) = {
+            let mut handles = lock_mock(&MOCK_BUFFERS);
+            match mock_buffer(&mut handles, handle) {
+                Some(buffer) => (
+                    buffer.total_messages,
+                    buffer.total_bytes,
+                    buffer.pending_messages() as u32,
+                    buffer.dropped_messages,
+                    buffer.expired_messages,
+                    buffer.held_bytes(),
+                    buffer.capacity_bytes(),
+                ),
+                None => (0, 0, 0, 0, 0, 0, 0),
             }
+        };
+
+        out.total_messages = total_messages;
+        out.total_bytes = total_bytes;
+        out.pending_messages = pending_messages;
+        out.active_segments = 0;
+        out.dropped_messages = dropped_messages;
+        out.expired_messages = expired_messages;
+        out.used_bytes = used_bytes;
+        out.capacity_bytes = capacity_bytes;
+    }
+
+    fn mock_reset_stats(handle: &BufferHandle) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let Some(buffer) = mock_buffer(&mut handles, handle) else {
+            return -4; // Invalid handle
+        };
+        buffer.total_messages = 0;
+        buffer.total_bytes = 0;
+        buffer.dropped_messages = 0;
+        buffer.expired_messages = 0;
+        0 // Success
+    }
+
+    fn mock_set_overflow_policy(handle: &BufferHandle, policy: OverflowPolicy) {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        if let Some(buffer) = mock_buffer(&mut handles, handle) {
+            buffer.overflow = policy;
+        }
+    }
+
+    fn mock_enable_broadcast(handle: &BufferHandle) {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        if let Some(buffer) = mock_buffer(&mut handles, handle) {
+            buffer.broadcast = Some(MockBroadcast::default());
+        }
+    }
+
+    fn mock_subscribe(handle: &BufferHandle, from_oldest: bool) -> UMSBBResult<u32> {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let buffer = mock_buffer(&mut handles, handle).ok_or(UMSBBError::InvalidHandle)?;
+        let broadcast = buffer.broadcast.as_mut().ok_or(UMSBBError::InvalidParams)?;
+
+        let subscriber_id = broadcast.next_subscriber_id;
+        broadcast.next_subscriber_id += 1;
+        let start = if from_oldest {
+            broadcast.base_seq
+        } else {
+            broadcast.base_seq + broadcast.log.len() as u64
+        };
+        broadcast.cursors.insert(subscriber_id, start);
+        Ok(subscriber_id)
+    }
+
+    // Current position of a subscriber, and the retained range it may seek in
+    fn mock_cursor_position(handle: &BufferHandle, subscriber_id: u32) -> Option<(u64, u64, u64)> {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let broadcast = mock_buffer(&mut handles, handle)?.broadcast.as_ref()?;
+        let &cursor = broadcast.cursors.get(&subscriber_id)?;
+        Some((cursor, broadcast.base_seq, broadcast.base_seq + broadcast.log.len() as u64))
+    }
+
+    fn mock_cursor_seek(handle: &BufferHandle, subscriber_id: u32, position: u64) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+
+        let Some(broadcast) = mock_buffer(&mut handles, handle).and_then(|b| b.broadcast.as_mut()) else {
+            return -4; // Invalid handle
+        };
+        if !broadcast.cursors.contains_key(&subscriber_id) {
+            return -4;
+        }
+        let tail = broadcast.base_seq + broadcast.log.len() as u64;
+        if position < broadcast.base_seq || position > tail {
+            return -1; // Already reclaimed or not yet written
+        }
+
+        broadcast.cursors.insert(subscriber_id, position);
+        broadcast.reclaim();
+        0 // Success
+    }
+
+    fn mock_unsubscribe(handle: &BufferHandle, subscriber_id: u32) {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        if let Some(broadcast) = mock_buffer(&mut handles, handle).and_then(|b| b.broadcast.as_mut()) {
+            broadcast.cursors.remove(&subscriber_id);
+            broadcast.reclaim();
+        }
+    }
+
+    fn mock_subscriber_read(
+        handle: &BufferHandle,
+        subscriber_id: u32,
+        buffer: &mut [u8],
+        actual_size: &mut u32,
+    ) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+
+        let Some(broadcast) = mock_buffer(&mut handles, handle).and_then(|b| b.broadcast.as_mut()) else {
+            return -4; // Invalid handle
+        };
+        let Some(&cursor) = broadcast.cursors.get(&subscriber_id) else {
+            return -4;
+        };
+
+        let Some(message) = broadcast.log.get((cursor - broadcast.base_seq) as usize) else {
+            return -3; // Buffer empty
+        };
+        if message.len() > buffer.len() {
+            return -1; // Invalid params
+        }
+
+        buffer[..message.len()].copy_from_slice(message);
+        *actual_size = message.len() as u32;
+        broadcast.cursors.insert(subscriber_id, cursor + 1);
+        broadcast.reclaim();
+        0 // Success
+    }
+
+    fn mock_group_join(handle: &BufferHandle, group_id: &str) -> Option<u32> {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let buffer = mock_buffer(&mut handles, handle)?;
+
+        let group = buffer.groups.entry(group_id.to_string()).or_default();
+        let member_id = group.next_member_id;
+        group.next_member_id += 1;
+        group.members.push(member_id);
+        group.assigned.insert(member_id, Vec::new());
+        Some(member_id)
+    }
+
+    fn mock_group_leave(handle: &BufferHandle, group_id: &str, member_id: u32) {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+        let Some(buffer) = mock_buffer(&mut handles, handle) else {
+            return;
+        };
+        let Some(group) = buffer.groups.get_mut(group_id) else {
+            return;
+        };
+
+        group.members.retain(|&id| id != member_id);
+        let orphaned = group.assigned.remove(&member_id).unwrap_or_default();
+        if group.members.is_empty() {
+            buffer.groups.remove(group_id);
+        }
+
+        // Return undelivered messages to the head of the stream
+        buffer
+            .messages
+            .splice(0..0, orphaned.into_iter().map(MockMessage::new));
+    }
+
+    fn mock_group_read(
+        handle: &BufferHandle,
+        group_id: &str,
+        member_id: u32,
+        buffer: &mut [u8],
+        actual_size: &mut u32,
+    ) -> c_int {
+        let mut handles = lock_mock(&MOCK_BUFFERS);
+
+        let Some(mock_buffer) = mock_buffer(&mut handles, handle) else {
+            return -4; // Invalid handle
+        };
+        let Some(group) = mock_buffer.groups.get_mut(group_id) else {
+            return -4;
+        };
+        if !group.members.contains(&member_id) {
+            return -4;
+        }
+
+        // Deal messages out round-robin until this member has one
+        while group.assigned[&member_id].is_empty() && !mock_buffer.messages.is_empty() {
+            let message = mock_buffer.messages.remove(0).data;
+            let target = group.members[group.next_turn % group.members.len()];
+            group.next_turn = group.next_turn.wrapping_add(1);
+            group.assigned.get_mut(&target).unwrap().push(message);
+        }
+
+        let queue = group.assigned.get_mut(&member_id).unwrap();
+        if queue.is_empty() {
+            return -3; // Buffer empty
+        }
+        if queue[0].len() > buffer.len() {
+            return -1; // Invalid params
+        }
+
+        let message = queue.remove(0);
+        mock_buffer.pending_bytes -= message.len() as u64;
+        buffer[..message.len()].copy_from_slice(&message);
+        *actual_size = message.len() as u32;
+        0 // Success
+    }
+}
+
+/// A named consumer group over a buffer
+///
+/// Obtained from [`UMSBBBuffer::consumer_group`]. Each call to
+/// [`ConsumerGroup::join`] registers a new competing member.
+pub struct ConsumerGroup<'a> {
+    buffer: &'a UMSBBBuffer,
+    group_id: String,
+}
+
+impl<'a> ConsumerGroup<'a> {
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    /// Register a new member with this group
+    pub fn join(&self) -> UMSBBResult<GroupMember<'a>> {
+        let member_id = if self.buffer.use_mock {
+            UMSBBBuffer::mock_group_join(&self.buffer.handle, &self.group_id)
+                .ok_or(UMSBBError::InvalidHandle)?
         } else {
-            BufferStats {
-                total_messages: 0,
-                total_bytes: 0,
-                pending_messages: 0,
-                active_segments: 0,
+            // The core has a single competing read path, so members simply
+            // share it
+            0
+        };
+
+        Ok(GroupMember {
+            buffer: self.buffer,
+            group_id: self.group_id.clone(),
+            member_id,
+        })
+    }
+}
+
+/// A single member of a [`ConsumerGroup`]
+///
+/// Leaving the group (dropping the member) returns any messages already
+/// assigned to it but not yet read to the head of the buffer.
+pub struct GroupMember<'a> {
+    buffer: &'a UMSBBBuffer,
+    group_id: String,
+    member_id: u32,
+}
+
+impl GroupMember<'_> {
+    pub fn member_id(&self) -> u32 {
+        self.member_id
+    }
+
+    /// Read the next message assigned to this member
+    pub fn read(&self) -> UMSBBResult<Option<Vec<u8>>> {
+        if !self.buffer.use_mock {
+            return self.buffer.read();
+        }
+
+        let mut buffer = vec![0u8; 65536];
+        let mut actual_size: u32 = 0;
+
+        let result = UMSBBBuffer::mock_group_read(
+            &self.buffer.handle,
+            &self.group_id,
+            self.member_id,
+            &mut buffer,
+            &mut actual_size,
+        );
+
+        match result {
+            0 => {
+                buffer.truncate(actual_size as usize);
+                Ok(Some(buffer))
             }
+            -3 => Ok(None),
+            -1 => Err(UMSBBError::InvalidParams),
+            -4 => Err(UMSBBError::InvalidHandle),
+            _ => Err(UMSBBError::CorruptedData),
+        }
+    }
+}
+
+impl Drop for GroupMember<'_> {
+    fn drop(&mut self) {
+        if self.buffer.use_mock {
+            UMSBBBuffer::mock_group_leave(&self.buffer.handle, &self.group_id, self.member_id);
+        }
+    }
+}
+
+/// A subscriber to a broadcast buffer
+///
+/// Holds an independent read position; dropping it unsubscribes and lets
+/// the buffer reclaim messages only this subscriber was holding back.
+pub struct Subscriber<'a> {
+    buffer: &'a UMSBBBuffer,
+    subscriber_id: u32,
+}
+
+impl Subscriber<'_> {
+    pub fn subscriber_id(&self) -> u32 {
+        self.subscriber_id
+    }
+
+    /// Read this subscriber's copy of the next message
+    pub fn read(&self) -> UMSBBResult<Option<Vec<u8>>> {
+        let mut buffer = vec![0u8; 65536];
+        let mut actual_size: u32 = 0;
+
+        let result = UMSBBBuffer::mock_subscriber_read(
+            &self.buffer.handle,
+            self.subscriber_id,
+            &mut buffer,
+            &mut actual_size,
+        );
+
+        match result {
+            0 => {
+                buffer.truncate(actual_size as usize);
+                Ok(Some(buffer))
+            }
+            -3 => Ok(None),
+            -1 => Err(UMSBBError::InvalidParams),
+            -4 => Err(UMSBBError::InvalidHandle),
+            _ => Err(UMSBBError::CorruptedData),
+        }
+    }
+}
+
+/// An independent read position on a broadcast buffer
+///
+/// Each cursor sees every retained message once, in order, regardless of
+/// other cursors. Positions are absolute message sequence numbers; messages
+/// stay retained until every cursor and subscriber has moved past them.
+pub struct Cursor<'a> {
+    subscriber: Subscriber<'a>,
+}
+
+impl Cursor<'_> {
+    /// Read the message at this cursor's position and advance past it
+    pub fn receive(&self) -> UMSBBResult<Option<Vec<u8>>> {
+        self.subscriber.read()
+    }
+
+    /// Sequence number of the next message this cursor will read
+    pub fn position(&self) -> UMSBBResult<u64> {
+        UMSBBBuffer::mock_cursor_position(&self.subscriber.buffer.handle, self.subscriber.subscriber_id)
+            .map(|(position, _, _)| position)
+            .ok_or(UMSBBError::InvalidHandle)
+    }
+
+    /// Range of sequence numbers this cursor can currently seek to
+    ///
+    /// The end is the position just past the newest message.
+    pub fn retained(&self) -> UMSBBResult<std::ops::Range<u64>> {
+        UMSBBBuffer::mock_cursor_position(&self.subscriber.buffer.handle, self.subscriber.subscriber_id)
+            .map(|(_, oldest, tail)| oldest..tail)
+            .ok_or(UMSBBError::InvalidHandle)
+    }
+
+    /// Move to `position`, e.g. to replay from an earlier message
+    ///
+    /// Returns `InvalidParams` if `position` has already been reclaimed
+    /// or lies beyond the newest message.
+    pub fn seek(&self, position: u64) -> UMSBBResult<()> {
+        match UMSBBBuffer::mock_cursor_seek(&self.subscriber.buffer.handle, self.subscriber.subscriber_id, position) {
+            0 => Ok(()),
+            -1 => Err(UMSBBError::InvalidParams),
+            -4 => Err(UMSBBError::InvalidHandle),
+            _ => Err(UMSBBError::CorruptedData),
         }
     }
 }
 
+impl Drop for Subscriber<'_> {
+    fn drop(&mut self) {
+        UMSBBBuffer::mock_unsubscribe(&self.buffer.handle, self.subscriber_id);
+    }
+}
+
 impl Drop for UMSBBBuffer {
     fn drop(&mut self) {
         if self.use_mock {
-            let mut handles = MOCK_BUFFERS.lock().unwrap();
-            handles.remove(&self.handle);
+            let mut handles = lock_mock(&MOCK_BUFFERS);
+            if mock_buffer(&mut handles, &self.handle).is_some() {
+                handles.remove(&self.handle.id);
+                lock_mock(&FREE_HANDLES).push(BufferHandle {
+                    id: self.handle.id,
+                    generation: self.handle.generation,
+                });
+            }
         } else {
             unsafe {
-                umsbb_destroy_buffer(self.handle);
+                umsbb_destroy_buffer(self.handle.id);
             }
         }
     }
 }
 
+// Drain files start with a magic and then hold `[u32 LE length][payload]`
+// frames back to back
+const DRAIN_FILE_MAGIC: &[u8; 4] = b"UMDF";
+
+fn save_messages(path: &Path, messages: &[Vec<u8>]) -> std::io::Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    writer.write_all(DRAIN_FILE_MAGIC)?;
+    for message in messages {
+        writer.write_all(&(message.len() as u32).to_le_bytes())?;
+        writer.write_all(message)?;
+    }
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path)
+}
+
+fn load_messages(path: &Path) -> UMSBBResult<Vec<Vec<u8>>> {
+    let mut contents = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut contents)?;
+
+    let Some(mut rest) = contents.strip_prefix(DRAIN_FILE_MAGIC.as_slice()) else {
+        return Err(UMSBBError::CorruptedData);
+    };
+    let mut messages = Vec::new();
+    while let Some((len, tail)) = rest.split_first_chunk::<4>() {
+        let len = u32::from_le_bytes(*len) as usize;
+        let Some(message) = tail.get(..len) else {
+            break; // Truncated by an interrupted write
+        };
+        messages.push(message.to_vec());
+        rest = &tail[len..];
+    }
+    Ok(messages)
+}
+
 // Convenience function
 pub fn create_buffer(size_mb: u32) -> UMSBBResult<UMSBBBuffer> {
     UMSBBBuffer::new(size_mb)
@@ -293,68 +1940,162 @@ pub fn create_buffer(size_mb: u32) -> UMSBBResult<UMSBBBuffer> {
 
 // Performance test
 pub fn performance_test(message_count: u32, buffer_size_mb: u32) -> UMSBBResult<()> {
-    use std::thread;
-    use std::time::Instant;
-    use std::sync::Arc;
-
     println!("UMSBB Rust Connector Test");
     println!("{}", "=".repeat(40));
 
+    run_benchmark(message_count, buffer_size_mb)?.print();
+    Ok(())
+}
+
+/// Run the producer/consumer benchmark without printing
+///
+/// Returns the measured throughput so callers can assert on it, e.g. to
+/// fail CI when messages/sec regresses below a threshold.
+pub fn run_benchmark(message_count: u32, buffer_size_mb: u32) -> UMSBBResult<BenchmarkResult> {
+    benchmark_with(message_count, buffer_size_mb, |i| format!("Message {}", i).into_bytes())
+}
+
+/// Throughput figures from a producer/consumer benchmark run
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub duration: std::time::Duration,
+    pub total_messages: u64,
+    pub total_bytes: u64,
+    pub messages_per_sec: f64,
+    pub mb_per_sec: f64,
+    /// Per-second write rates during the run
+    pub rate_histogram: RateHistogram,
+}
+
+impl BenchmarkResult {
+    fn print(&self) {
+        println!("\nTest Results:");
+        println!("Duration: {:.3} seconds", self.duration.as_secs_f64());
+        println!("Messages: {}", self.total_messages);
+        println!("Bytes: {}", self.total_bytes);
+        println!("Messages/sec: {:.0}", self.messages_per_sec);
+        println!("MB/sec: {:.2}", self.mb_per_sec);
+        println!(
+            "Per-second rate: p50 {} / p99 {} / peak {}",
+            self.rate_histogram.p50(),
+            self.rate_histogram.p99(),
+            self.rate_histogram.peak()
+        );
+    }
+}
+
+/// Performance test with constant-size payloads
+///
+/// Every message is `message_size` bytes, so MB/sec is comparable across
+/// message counts and buffer sizes. Prints the results and returns them
+/// for automated checks.
+pub fn performance_test_fixed(
+    message_count: u32,
+    buffer_size_mb: u32,
+    message_size: usize,
+) -> UMSBBResult<BenchmarkResult> {
+    if message_size > 65536 {
+        return Err(UMSBBError::InvalidParams);
+    }
+
+    println!("UMSBB Rust Connector Test ({} byte messages)", message_size);
+    println!("{}", "=".repeat(40));
+
+    let payload = vec![0xA5u8; message_size];
+    let result = benchmark_with(message_count, buffer_size_mb, move |_| payload.clone())?;
+    result.print();
+    Ok(result)
+}
+
+// Run one producer and one consumer over a fresh buffer
+fn benchmark_with<F>(message_count: u32, buffer_size_mb: u32, make_message: F) -> UMSBBResult<BenchmarkResult>
+where
+    F: Fn(u32) -> Vec<u8> + Send + 'static,
+{
+    use std::thread;
+
     let buffer = Arc::new(create_buffer(buffer_size_mb)?);
     let buffer_producer = Arc::clone(&buffer);
     let buffer_consumer = Arc::clone(&buffer);
+    // Set when the producer gives up, so the consumer stops waiting
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_consumer = Arc::clone(&stop);
 
     let start_time = Instant::now();
 
-    // Producer thread
-    let producer = thread::spawn(move || {
+    let producer = thread::spawn(move || -> UMSBBResult<()> {
         for i in 0..message_count {
-            let message = format!("Message {}", i);
-            buffer_producer.write_string(&message).unwrap();
-            
-            if i % 1000 == 0 {
-                println!("Produced {} messages", i);
+            let message = make_message(i);
+            // Wait for the consumer to make room rather than dropping
+            loop {
+                match buffer_producer.write(&message) {
+                    Err(UMSBBError::BufferFull) => thread::yield_now(),
+                    result => break result?,
+                }
             }
         }
+        Ok(())
     });
 
-    // Consumer thread
     let consumer = thread::spawn(move || {
         let mut received = 0;
-        while received < message_count {
+        while received < message_count && !stop_consumer.load(Ordering::Acquire) {
             if let Ok(Some(_message)) = buffer_consumer.read() {
                 received += 1;
-                if received % 1000 == 0 {
-                    println!("Consumed {} messages", received);
-                }
             } else {
                 thread::sleep(std::time::Duration::from_micros(100));
             }
         }
     });
 
-    producer.join().unwrap();
+    let produced = producer.join().unwrap();
+    if produced.is_err() {
+        stop.store(true, Ordering::Release);
+    }
     consumer.join().unwrap();
+    produced?;
 
     let duration = start_time.elapsed();
     let duration_sec = duration.as_secs_f64();
-
     let stats = buffer.get_stats();
 
-    println!("\nTest Results:");
-    println!("Duration: {:.3} seconds", duration_sec);
-    println!("Messages: {}", stats.total_messages);
-    println!("Bytes: {}", stats.total_bytes);
-    println!("Messages/sec: {:.0}", stats.total_messages as f64 / duration_sec);
-    println!("MB/sec: {:.2}", stats.total_bytes as f64 / (1024.0 * 1024.0) / duration_sec);
-
-    Ok(())
+    Ok(BenchmarkResult {
+        duration,
+        total_messages: stats.total_messages,
+        total_bytes: stats.total_bytes,
+        messages_per_sec: stats.total_messages as f64 / duration_sec,
+        mb_per_sec: stats.total_bytes as f64 / (1024.0 * 1024.0) / duration_sec,
+        rate_histogram: buffer.rate_histogram(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // The mock memory limit is process-wide, so a test that lowers it would
+    // fail writes in tests running alongside. Such tests re-run themselves
+    // alone in a child process; returns true in the child, where the test
+    // body should run, and false in the parent once the child has passed.
+    fn run_isolated(test: &str) -> bool {
+        const CHILD_VAR: &str = "UMSBB_ISOLATED_TEST";
+        if std::env::var_os(CHILD_VAR).is_some() {
+            return true;
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([&format!("tests::{}", test), "--exact", "--include-ignored"])
+            .env(CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        false
+    }
+
     #[test]
     fn test_buffer_creation() {
         let buffer = create_buffer(16).unwrap();
@@ -401,6 +2142,627 @@ mod tests {
         assert!(create_buffer(65).is_err());
     }
 
+    #[test]
+    fn test_consumer_group_round_robin() {
+        let buffer = create_buffer(16).unwrap();
+        let group = buffer.consumer_group("workers");
+        let a = group.join().unwrap();
+        let b = group.join().unwrap();
+
+        for i in 0..4 {
+            buffer.write_string(&format!("job {}", i)).unwrap();
+        }
+
+        assert_eq!(a.read().unwrap().unwrap(), b"job 0");
+        assert_eq!(b.read().unwrap().unwrap(), b"job 1");
+        assert_eq!(a.read().unwrap().unwrap(), b"job 2");
+        assert_eq!(b.read().unwrap().unwrap(), b"job 3");
+        assert!(a.read().unwrap().is_none());
+
+        // Messages assigned to a departing member go back to the stream
+        for i in 4..7 {
+            buffer.write_string(&format!("job {}", i)).unwrap();
+        }
+        assert_eq!(a.read().unwrap().unwrap(), b"job 4");
+        assert_eq!(a.read().unwrap().unwrap(), b"job 6");
+        assert_eq!(buffer.pending_count(), 1);
+        drop(b);
+        assert_eq!(a.read().unwrap().unwrap(), b"job 5");
+    }
+
+    #[test]
+    fn test_broadcast_subscribers() {
+        let buffer = UMSBBBuffer::new_broadcast(16).unwrap();
+        let first = buffer.subscribe().unwrap();
+        let second = buffer.subscribe().unwrap();
+
+        buffer.write_string("config v1").unwrap();
+        buffer.write_string("config v2").unwrap();
+        assert!(buffer.read().is_err());
+
+        assert_eq!(first.read().unwrap().unwrap(), b"config v1");
+        assert_eq!(first.read().unwrap().unwrap(), b"config v2");
+        assert!(first.read().unwrap().is_none());
+        assert_eq!(buffer.pending_count(), 2);
+
+        assert_eq!(second.read().unwrap().unwrap(), b"config v1");
+        assert_eq!(buffer.pending_count(), 1);
+        drop(second);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_byte_capacity() {
+        let buffer = create_buffer(1).unwrap();
+        let chunk = vec![0u8; 65536];
+
+        // A 1MB buffer holds exactly sixteen 64KB messages
+        for _ in 0..16 {
+            buffer.write(&chunk).unwrap();
+        }
+        assert_eq!(buffer.write(&chunk), Err(UMSBBError::BufferFull));
+        assert_eq!(buffer.write(b"x"), Err(UMSBBError::BufferFull));
+
+        buffer.read().unwrap().unwrap();
+        assert!(buffer.write(&chunk).is_ok());
+    }
+
+    #[test]
+    fn test_transition_hooks() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let readable = Arc::new(AtomicU32::new(0));
+        let empty = Arc::new(AtomicU32::new(0));
+
+        let mut buffer = create_buffer(16).unwrap();
+        let counter = Arc::clone(&readable);
+        buffer.on_readable(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let counter = Arc::clone(&empty);
+        buffer.on_empty(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        buffer.write_string("one").unwrap();
+        buffer.write_string("two").unwrap();
+        assert_eq!(readable.load(Ordering::SeqCst), 1);
+
+        buffer.read().unwrap();
+        assert_eq!(empty.load(Ordering::SeqCst), 0);
+        buffer.read().unwrap();
+        assert_eq!(empty.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_run_benchmark() {
+        let result = run_benchmark(1000, 1).unwrap();
+        assert_eq!(result.total_messages, 1000);
+        let expected: u64 = (0..1000).map(|i| format!("Message {}", i).len() as u64).sum();
+        assert_eq!(result.total_bytes, expected);
+        assert!(result.mb_per_sec > 0.0);
+    }
+
+    #[test]
+    fn test_benchmark_producer_error() {
+        // Oversized messages fail the first write
+        let result = benchmark_with(10, 1, |_| vec![0u8; 70000]);
+        assert_eq!(result.unwrap_err(), UMSBBError::InvalidParams);
+    }
+
+    #[test]
+    fn test_performance_test_fixed() {
+        let result = performance_test_fixed(500, 1, 128).unwrap();
+        assert_eq!(result.total_messages, 500);
+        assert_eq!(result.total_bytes, 500 * 128);
+        assert!(result.messages_per_sec > 0.0);
+    }
+
+    #[test]
+    fn test_stats_wire_encoding() {
+        let stats = BufferStats {
+            total_messages: 0x0102030405060708,
+            total_bytes: 42,
+            pending_messages: 7,
+            active_segments: 3,
+            dropped_messages: 5,
+            expired_messages: 4,
+            used_bytes: 9,
+            capacity_bytes: 1 << 20,
+        };
+        let bytes = stats.to_bytes();
+        assert_eq!(bytes[0], 0x08);
+        assert_eq!(bytes[16..20], [7, 0, 0, 0]);
+
+        let decoded = BufferStats::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.total_messages, stats.total_messages);
+        assert_eq!(decoded.total_bytes, 42);
+        assert_eq!(decoded.pending_messages, 7);
+        assert_eq!(decoded.active_segments, 3);
+        assert_eq!(decoded.dropped_messages, 5);
+        assert_eq!(decoded.used_bytes, 9);
+        assert_eq!(decoded.capacity_bytes, 1 << 20);
+        assert_eq!(decoded.dropped(DropReason::Expired), 4);
+
+        assert_eq!(
+            BufferStats::from_bytes(&bytes[..20]).unwrap_err(),
+            ParseError::InvalidLength { expected: 56, found: 20 }
+        );
+    }
+
+    #[test]
+    fn test_cursors() {
+        let buffer = UMSBBBuffer::new_broadcast(1).unwrap();
+        let first = buffer.new_cursor().unwrap();
+        buffer.write(b"a").unwrap();
+        buffer.write(b"b").unwrap();
+
+        // A later cursor still replays from the oldest retained message
+        let second = buffer.new_cursor().unwrap();
+        assert_eq!(first.receive().unwrap(), Some(b"a".to_vec()));
+        assert_eq!(first.receive().unwrap(), Some(b"b".to_vec()));
+        assert_eq!(first.receive().unwrap(), None);
+        assert_eq!(second.receive().unwrap(), Some(b"a".to_vec()));
+        assert_eq!(second.position().unwrap(), 1);
+
+        // `second` holds "b", so `first` can rewind to it but no further
+        assert_eq!(first.retained().unwrap(), 1..2);
+        first.seek(1).unwrap();
+        assert_eq!(first.receive().unwrap(), Some(b"b".to_vec()));
+        assert_eq!(first.seek(0), Err(UMSBBError::InvalidParams));
+        assert_eq!(first.seek(3), Err(UMSBBError::InvalidParams));
+
+        assert!(UMSBBBuffer::new(1).unwrap().new_cursor().is_err());
+    }
+
+    #[test]
+    fn test_corrupt_text_handling() {
+        let buffer = UMSBBBuffer::new(1).unwrap();
+        buffer.write(&[0x66, 0x6f, 0xff]).unwrap();
+        assert_eq!(buffer.read_string_lossy().unwrap(), Some("fo\u{FFFD}".to_string()));
+
+        buffer.write(&[0xff, 0xfe]).unwrap();
+        buffer.write(&[0xc3]).unwrap();
+        buffer.write_string("valid").unwrap();
+        assert_eq!(buffer.read_skip_corrupt().unwrap(), Some("valid".to_string()));
+
+        buffer.write(&[0xff]).unwrap();
+        assert_eq!(buffer.read_skip_corrupt().unwrap(), None);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_reset_stats() {
+        let buffer = UMSBBBuffer::new(1).unwrap();
+        buffer.write(b"first").unwrap();
+        buffer.write(b"second").unwrap();
+        buffer.read().unwrap();
+
+        buffer.reset_stats().unwrap();
+        let stats = buffer.get_stats();
+        assert_eq!(stats.total_messages, 0);
+        assert_eq!(stats.total_bytes, 0);
+        assert_eq!(stats.pending_messages, 1);
+
+        buffer.write(b"third").unwrap();
+        assert_eq!(buffer.get_stats().total_messages, 1);
+        assert_eq!(buffer.get_stats().total_bytes, 5);
+    }
+
+    #[test]
+    fn test_stats_into() {
+        let buffer = UMSBBBuffer::new(1).unwrap();
+        buffer.write(b"abcd").unwrap();
+
+        let mut stats = BufferStats {
+            active_segments: 99,
+            ..BufferStats::default()
+        };
+        buffer.stats_into(&mut stats);
+        assert_eq!(stats.total_messages, 1);
+        assert_eq!(stats.total_bytes, 4);
+        assert_eq!(stats.pending_messages, 1);
+        assert_eq!(stats.active_segments, 0);
+    }
+
+    #[test]
+    fn test_expire_callback() {
+        let expired = Arc::new(Mutex::new(Vec::new()));
+
+        let clock = Arc::new(MockClock::new());
+        let mut buffer = create_buffer(16).unwrap();
+        buffer.set_clock(clock.clone());
+        let sink = Arc::clone(&expired);
+        buffer.on_expire(move |message| {
+            sink.lock().unwrap().push(message.to_vec());
+        });
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&dropped);
+        buffer.on_drop(move |message, reason| {
+            sink.lock().unwrap().push((message.to_vec(), reason));
+        });
+
+        buffer.write_with_ttl(b"stale", Duration::from_secs(5)).unwrap();
+        buffer.write_with_ttl(b"fresh", Duration::from_secs(60)).unwrap();
+        buffer.write(b"forever").unwrap();
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(buffer.read().unwrap(), Some(b"fresh".to_vec()));
+        assert_eq!(*expired.lock().unwrap(), vec![b"stale".to_vec()]);
+        assert_eq!(*dropped.lock().unwrap(), vec![(b"stale".to_vec(), DropReason::Expired)]);
+        assert_eq!(buffer.read().unwrap(), Some(b"forever".to_vec()));
+        assert_eq!(buffer.read().unwrap(), None);
+        let stats = buffer.get_stats();
+        assert_eq!(stats.pending_messages, 0);
+        assert_eq!(stats.expired_messages, 1);
+    }
+
+    #[test]
+    fn test_stale_handle_rejected() {
+        let buffer = create_buffer(1).unwrap();
+        let mismatched = BufferHandle {
+            id: buffer.handle().id(),
+            generation: buffer.handle().generation().wrapping_add(1),
+        };
+        assert_eq!(UMSBBBuffer::mock_write_message(&mismatched, b"ghost", None, Instant::now(), &mut Vec::new()), -4);
+
+        let stale = BufferHandle {
+            id: buffer.handle().id(),
+            generation: buffer.handle().generation(),
+        };
+        drop(buffer);
+
+        // Whether or not the id has been recycled, the old handle is dead
+        let recycled = create_buffer(1).unwrap();
+        assert_eq!(UMSBBBuffer::mock_write_message(&stale, b"ghost", None, Instant::now(), &mut Vec::new()), -4);
+        assert!(recycled.is_empty());
+    }
+
+    #[test]
+    fn test_mock_preload() {
+        let buffer = create_buffer(1).unwrap();
+        buffer.mock_preload(vec![b"a".to_vec(), b"bc".to_vec()]);
+
+        let stats = buffer.get_stats();
+        assert_eq!(stats.total_messages, 2);
+        assert_eq!(stats.total_bytes, 3);
+        assert_eq!(stats.pending_messages, 2);
+        assert_eq!(buffer.read().unwrap(), Some(b"a".to_vec()));
+        assert_eq!(buffer.read().unwrap(), Some(b"bc".to_vec()));
+        assert_eq!(buffer.read().unwrap(), None);
+    }
+
+    #[test]
+    fn test_survives_poisoned_mock_lock() {
+        let buffer = create_buffer(1).unwrap();
+
+        let poisoner = std::thread::spawn(|| {
+            let _guard = MOCK_BUFFERS.lock();
+            panic!("poison the mock lock");
+        });
+        assert!(poisoner.join().is_err());
+
+        buffer.write_string("still works").unwrap();
+        assert_eq!(buffer.read_string().unwrap(), Some("still works".to_string()));
+    }
+
+    #[test]
+    fn test_write_batch_atomic() {
+        let buffer = create_buffer(1).unwrap();
+        let chunk = vec![0u8; 64 * 1024];
+
+        // Sixteen 64KB messages fill the buffer, so seventeen can't fit
+        let too_many = vec![chunk.as_slice(); 17];
+        assert_eq!(buffer.write_batch_atomic(&too_many), Err(UMSBBError::BufferFull));
+        assert!(buffer.is_empty());
+
+        buffer.write_batch_atomic(&too_many[..16]).unwrap();
+        assert_eq!(buffer.get_stats().pending_messages, 16);
+        assert_eq!(buffer.write_batch_atomic(&[b"x"]), Err(UMSBBError::BufferFull));
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let chunk = vec![0u8; 64 * 1024];
+
+        let mut buffer = UMSBBBuffer::with_overflow_policy(1, OverflowPolicy::DropOldest).unwrap();
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&dropped);
+        buffer.on_drop(move |message, reason| sink.lock().unwrap().push((message[0], reason)));
+        for i in 0..18u8 {
+            let mut message = chunk.clone();
+            message[0] = i;
+            buffer.write(&message).unwrap();
+        }
+        let stats = buffer.get_stats();
+        assert_eq!(stats.pending_messages, 16);
+        assert_eq!(stats.dropped_messages, 2);
+        assert_eq!(buffer.read().unwrap().unwrap()[0], 2);
+        assert_eq!(*dropped.lock().unwrap(), vec![(0, DropReason::Overflow), (1, DropReason::Overflow)]);
+
+        let mut buffer = UMSBBBuffer::with_overflow_policy(1, OverflowPolicy::DropNewest).unwrap();
+        let sink = Arc::clone(&dropped);
+        buffer.on_drop(move |message, reason| sink.lock().unwrap().push((message[0], reason)));
+        for i in 0..18u8 {
+            let mut message = chunk.clone();
+            message[0] = i;
+            buffer.write(&message).unwrap();
+        }
+        assert_eq!(buffer.get_stats().dropped(DropReason::Overflow), 2);
+        assert_eq!(buffer.read().unwrap().unwrap()[0], 0);
+        assert_eq!(dropped.lock().unwrap()[2..], [(16, DropReason::Overflow), (17, DropReason::Overflow)]);
+
+        let buffer = UMSBBBuffer::with_overflow_policy(1, OverflowPolicy::Error).unwrap();
+        for _ in 0..16 {
+            buffer.write(&chunk).unwrap();
+        }
+        assert_eq!(buffer.write(&chunk), Err(UMSBBError::BufferFull));
+        assert_eq!(buffer.get_stats().dropped_messages, 0);
+    }
+
+    #[test]
+    fn test_overflow_block() {
+        let chunk = vec![0u8; 64 * 1024];
+        let buffer = Arc::new(UMSBBBuffer::with_overflow_policy(1, OverflowPolicy::Block).unwrap());
+        for _ in 0..16 {
+            buffer.write(&chunk).unwrap();
+        }
+
+        let reader = Arc::clone(&buffer);
+        let drain = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            reader.read().unwrap()
+        });
+        buffer.write(&chunk).unwrap();
+        assert!(drain.join().unwrap().is_some());
+        assert_eq!(buffer.get_stats().pending_messages, 16);
+    }
+
+    #[test]
+    fn test_drain_to_file() {
+        let path = std::env::temp_dir().join(format!("umsbb-drain-{}.bin", std::process::id()));
+
+        let buffer = create_buffer(1).unwrap();
+        buffer.write(b"first").unwrap();
+        buffer.write(b"").unwrap();
+        buffer.write(b"third").unwrap();
+        assert_eq!(buffer.drain_to_file(&path).unwrap(), 3);
+        assert!(buffer.is_empty());
+
+        // Chop the last message in half, as an interrupted write would
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 2]).unwrap();
+
+        let restored = create_buffer(1).unwrap();
+        assert_eq!(restored.load_from_file(&path).unwrap(), 2);
+        assert_eq!(restored.read().unwrap(), Some(b"first".to_vec()));
+        assert_eq!(restored.read().unwrap(), Some(Vec::new()));
+        assert_eq!(restored.read().unwrap(), None);
+
+        std::fs::write(&path, b"not a drain file").unwrap();
+        assert_eq!(restored.load_from_file(&path), Err(UMSBBError::CorruptedData));
+        std::fs::remove_file(&path).unwrap();
+
+        // A failed save puts everything back, in order, and reports the
+        // I/O error
+        let buffer = create_buffer(1).unwrap();
+        for i in 0..16u8 {
+            buffer.write(&[i; 64 * 1024]).unwrap();
+        }
+        let unwritable = path.join("no-such-dir").join("drain.bin");
+        assert_eq!(buffer.drain_to_file(&unwritable), Err(UMSBBError::Io));
+        let stats = buffer.get_stats();
+        assert_eq!(stats.pending_messages, 16);
+        assert_eq!(stats.total_messages, 16);
+        for i in 0..16u8 {
+            assert_eq!(buffer.read().unwrap(), Some(vec![i; 64 * 1024]));
+        }
+    }
+
+    #[test]
+    fn test_wait_empty() {
+        let buffer = Arc::new(create_buffer(1).unwrap());
+        assert!(buffer.wait_empty(Duration::ZERO).unwrap());
+
+        buffer.write(b"pending").unwrap();
+        assert!(!buffer.wait_empty(Duration::from_millis(5)).unwrap());
+
+        let consumer = Arc::clone(&buffer);
+        let drain = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            consumer.read().unwrap()
+        });
+        assert!(buffer.wait_empty(Duration::from_secs(5)).unwrap());
+        assert!(drain.join().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_backend_error_context() {
+        let buffer = create_buffer(1).unwrap();
+        assert_eq!(buffer.backend(), Backend::Mock);
+
+        let err = buffer.write(&vec![0u8; 65537]).unwrap_err().with_backend(buffer.backend());
+        assert_eq!(err.to_string(), "Invalid parameters (backend=Mock)");
+        assert_eq!(UMSBBError::from(err), UMSBBError::InvalidParams);
+    }
+
+    #[test]
+    fn test_read_into() {
+        let buffer = create_buffer(1).unwrap();
+        buffer.write(b"short").unwrap();
+        buffer.write(&[7u8; 32]).unwrap();
+
+        let mut buf = [0u8; 16];
+        assert_eq!(buffer.read_into(&mut buf).unwrap(), Some(5));
+        assert_eq!(&buf[..5], b"short");
+
+        // Too big for the slice: rejected without being consumed
+        assert_eq!(buffer.read_into(&mut buf), Err(UMSBBError::InvalidParams));
+        assert_eq!(buffer.read().unwrap(), Some(vec![7u8; 32]));
+        assert_eq!(buffer.read_into(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn test_mock_total_memory() {
+        let buffer = create_buffer(1).unwrap();
+        buffer.write_batch_atomic(&[&[0u8; 60_000], &[0u8; 60_000]]).unwrap();
+
+        // Other tests' buffers share the total, so only a lower bound holds
+        assert!(mock_total_memory() >= 120_000);
+    }
+
+    #[test]
+    fn test_mock_memory_limit() {
+        if !run_isolated("test_mock_memory_limit") {
+            return;
+        }
+
+        let buffer = create_buffer(1).unwrap();
+        buffer.write(&[0u8; 60_000]).unwrap();
+        set_mock_memory_limit(Some(mock_total_memory() + 1000));
+
+        assert_eq!(buffer.write(&[0u8; 2000]), Err(UMSBBError::MemoryAllocation));
+        assert_eq!(
+            buffer.write_batch_atomic(&[&[0u8; 600], &[0u8; 600]]),
+            Err(UMSBBError::MemoryAllocation)
+        );
+        buffer.write(&[0u8; 1000]).unwrap();
+        assert_eq!(buffer.get_stats().pending_messages, 2);
+
+        // Reading frees room under the cap
+        buffer.read().unwrap();
+        buffer.write(&[0u8; 2000]).unwrap();
+
+        // A rejected write doesn't evict anything on the way
+        let evicting = UMSBBBuffer::with_overflow_policy(1, OverflowPolicy::DropOldest).unwrap();
+        set_mock_memory_limit(None);
+        for _ in 0..16 {
+            evicting.write(&[0u8; 64 * 1024]).unwrap();
+        }
+        set_mock_memory_limit(Some(mock_total_memory() - 1000));
+        assert_eq!(evicting.write(&[0u8; 64 * 1024]), Err(UMSBBError::MemoryAllocation));
+        assert_eq!(evicting.get_stats().pending_messages, 16);
+        assert_eq!(evicting.get_stats().dropped(DropReason::Overflow), 0);
+
+        set_mock_memory_limit(None);
+        buffer.write(&[0u8; 60_000]).unwrap();
+    }
+
+    #[test]
+    fn test_byte_occupancy_stats() {
+        let buffer = UMSBBBuffer::new(1).unwrap();
+        buffer.write(&[0u8; 1000]).unwrap();
+        buffer.write(&[0u8; 24]).unwrap();
+
+        let stats = buffer.get_stats();
+        assert_eq!(stats.used_bytes, 1024);
+        assert_eq!(stats.capacity_bytes, 1024 * 1024);
+        assert!((stats.fill_ratio() - 1.0 / 1024.0).abs() < f64::EPSILON);
+
+        buffer.read().unwrap();
+        assert_eq!(buffer.get_stats().used_bytes, 24);
+    }
+
+    #[test]
+    fn test_mock_fault_injection() {
+        let buffer = create_buffer(1).unwrap();
+        buffer.mock_set_latency(Duration::from_millis(5), Duration::ZERO);
+        buffer.write(b"slow").unwrap();
+        let started = Instant::now();
+        assert_eq!(buffer.read().unwrap(), Some(b"slow".to_vec()));
+        assert!(started.elapsed() >= Duration::from_millis(5));
+        buffer.mock_set_latency(Duration::ZERO, Duration::ZERO);
+
+        let outcomes = |buffer: &UMSBBBuffer| -> Vec<bool> {
+            buffer.mock_set_failure_rate(0.5);
+            (0..64).map(|_| buffer.write(b"x").is_ok()).collect()
+        };
+        let first = outcomes(&buffer);
+        assert!(first.contains(&true) && first.contains(&false));
+        // Reseeding replays the same failures
+        assert_eq!(outcomes(&buffer), first);
+
+        buffer.mock_set_failure_rate(1.0);
+        assert_eq!(buffer.write(b"x"), Err(UMSBBError::MemoryAllocation));
+        assert_eq!(buffer.read(), Err(UMSBBError::CorruptedData));
+        buffer.mock_set_failure_rate(0.0);
+        assert!(buffer.read().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_can_send() {
+        let buffer = create_buffer(1).unwrap();
+        assert!(buffer.can_send(65536));
+        assert!(!buffer.can_send(65537));
+
+        for _ in 0..15 {
+            buffer.write(&[0u8; 65536]).unwrap();
+        }
+        assert!(buffer.can_send(65536));
+        buffer.write(&[0u8; 65000]).unwrap();
+        assert!(buffer.can_send(536));
+        assert!(!buffer.can_send(537));
+        assert_eq!(buffer.write(&[0u8; 537]), Err(UMSBBError::BufferFull));
+    }
+
+    #[test]
+    fn test_rate_recorder() {
+        let start = Instant::now();
+        let mut recorder = RateRecorder::default();
+        for _ in 0..10 {
+            recorder.record(start);
+        }
+        recorder.record(start + Duration::from_millis(2500));
+
+        let histogram = recorder.histogram();
+        assert_eq!(histogram.windows(), &[10, 0, 1]);
+        assert_eq!(histogram.p50(), 1);
+        assert_eq!(histogram.p99(), 1);
+        assert_eq!(histogram.percentile(100), 10);
+        assert_eq!(histogram.peak(), 10);
+        assert_eq!(RateHistogram::default().p99(), 0);
+
+        let buffer = create_buffer(1).unwrap();
+        assert!(buffer.rate_histogram().windows().is_empty());
+        buffer.write(b"a").unwrap();
+        buffer.write(b"b").unwrap();
+        assert_eq!(buffer.rate_histogram().windows(), &[2]);
+    }
+
+    #[test]
+    fn test_mock_clock_rates_and_wait_empty() {
+        let clock = Arc::new(MockClock::new());
+        let mut buffer = create_buffer(1).unwrap();
+        buffer.set_clock(clock.clone());
+
+        // Writes land in the window of the buffer's clock, not real time
+        buffer.write(b"a").unwrap();
+        buffer.write_batch_atomic(&[b"b", b"c"]).unwrap();
+        clock.advance(Duration::from_secs(2));
+        buffer.write(b"d").unwrap();
+        assert_eq!(buffer.rate_histogram().windows(), &[3, 0, 1]);
+
+        // A frozen clock never reaches the deadline, however long a reader takes
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                while buffer.read().unwrap().is_some() {}
+            });
+            assert_eq!(buffer.wait_empty(Duration::from_millis(1)), Ok(true));
+        });
+
+        // And the deadline passes only as the clock is advanced
+        buffer.write(b"e").unwrap();
+        std::thread::scope(|s| {
+            let waiter = s.spawn(|| buffer.wait_empty(Duration::from_secs(10)));
+            while !waiter.is_finished() {
+                clock.advance(Duration::from_secs(1));
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(waiter.join().unwrap(), Ok(false));
+        });
+    }
+
     #[test]
     fn test_large_message() {
         let buffer = create_buffer(16).unwrap();
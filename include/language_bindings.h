@@ -7,6 +7,10 @@
 extern "C" {
 #endif
 
+// Version of the direct binding ABI, reported by umsbb_abi_version();
+// bump whenever a struct below or a direct function signature changes
+#define UMSBB_ABI_VERSION 1
+
 // Language binding types
 typedef enum {
     LANG_C = 0,
@@ -34,6 +38,9 @@ typedef void (*lang_callback_t)(universal_data_t* data);
 typedef bool (*lang_validator_t)(const universal_data_t* data);
 typedef void* (*lang_allocator_t)(size_t size);
 typedef void (*lang_deallocator_t)(void* ptr);
+// Gives a buffer adopted by umsbb_submit_owned_direct back to its
+// allocator, with the size and capacity it was submitted with
+typedef void (*umsbb_release_fn)(void* data, size_t size, size_t capacity);
 
 // Language runtime structure
 typedef struct {
@@ -58,6 +65,14 @@ typedef struct {
     bool auto_balance_load;
 } scaling_config_t;
 
+// Message counters for a direct bus
+typedef struct {
+    uint64_t total_messages;
+    uint64_t total_bytes;
+    uint32_t pending_messages;
+    uint32_t active_segments;
+} umsbb_direct_stats_t;
+
 // Core FFI functions
 bool register_language_runtime(language_type_t lang, const language_runtime_t* runtime);
 bool unregister_language_runtime(language_type_t lang);
@@ -77,9 +92,32 @@ uint32_t get_optimal_consumer_count();
 
 // Direct language bindings (no API wrapper)
 void* umsbb_create_direct(size_t buffer_size, uint32_t segment_count, language_type_t lang);
+// Like umsbb_create_direct with the segments on `numa_node`; returns NULL
+// when NUMA is unavailable (no libnuma, or no such node)
+void* umsbb_create_direct_numa(size_t buffer_size, uint32_t segment_count, language_type_t lang, uint32_t numa_node);
 bool umsbb_submit_direct(void* bus_handle, const universal_data_t* data);
+// Submit to a specific segment (taken modulo the segment count) instead of
+// the one picked from the type id
+bool umsbb_submit_to_segment_direct(void* bus_handle, uint32_t segment_id, const universal_data_t* data);
+// Queue `data` without copying it. On success the bus owns the buffer and
+// passes it to `release` once the message is drained or the bus destroyed;
+// on failure the caller keeps it. The segment is taken modulo the count.
+bool umsbb_submit_owned_direct(void* bus_handle, uint32_t segment_id, void* data, size_t size,
+                               size_t capacity, uint32_t type_id, language_type_t lang,
+                               umsbb_release_fn release);
+// Payload bytes waiting in a segment
+size_t umsbb_segment_used_direct(void* bus_handle, uint32_t segment_id);
+// Segments the bus was created with; the core picks the count when 0 was
+// requested and caps larger requests
+uint32_t umsbb_segment_count_direct(void* bus_handle);
 universal_data_t* umsbb_drain_direct(void* bus_handle, language_type_t target_lang);
+// Like umsbb_drain_direct, also storing the sender's language in
+// `source_lang` when it is not NULL. The returned data's source_lang is the
+// target language, whose allocator owns it.
+universal_data_t* umsbb_drain_direct_ex(void* bus_handle, language_type_t target_lang, language_type_t* source_lang);
 void umsbb_destroy_direct(void* bus_handle);
+umsbb_direct_stats_t umsbb_get_stats_direct(void* bus_handle);
+uint32_t umsbb_abi_version(void);
 
 #ifdef __cplusplus
 }
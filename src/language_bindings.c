@@ -6,6 +6,9 @@
 #include <string.h>
 #include <pthread.h>
 #include <time.h>
+#ifdef UMSBB_HAVE_LIBNUMA
+#include <numa.h>
+#endif
 
 // Global state
 static language_runtime_t registered_runtimes[16];
@@ -13,7 +16,7 @@ static bool runtime_initialized[16] = {false};
 static scaling_config_t current_scaling_config = {0};
 static pthread_mutex_t scaling_mutex = PTHREAD_MUTEX_INITIALIZER;
 
-// Performance monitoring
+// Performance monitoring, shared by every bus; guarded by scaling_mutex
 static struct {
     uint32_t active_producers;
     uint32_t active_consumers;
@@ -210,10 +213,102 @@ uint32_t get_optimal_consumer_count() {
     return count > 0 ? count : 1;
 }
 
+// Count a finished operation towards the auto-scaling metrics
+static void record_operation(bool on_gpu) {
+    pthread_mutex_lock(&scaling_mutex);
+    performance_stats.total_operations++;
+    if (on_gpu) {
+        performance_stats.gpu_operations++;
+    }
+    pthread_mutex_unlock(&scaling_mutex);
+}
+
+// State behind a direct handle. The core's rings and arena are not
+// thread-safe, so every direct call takes the handle's lock.
+typedef struct {
+    UniversalMultiSegmentedBiBufferBus* bus;
+    pthread_mutex_t lock;
+    uint64_t total_messages;
+    uint64_t total_bytes;
+    uint32_t pending[MAX_AGENTS];
+    size_t pending_bytes[MAX_AGENTS];
+} direct_bus_t;
+
+// Segments that actually exist; the ring caps the requested count
+static uint32_t direct_segment_count(const direct_bus_t* direct) {
+    return (uint32_t)direct->bus->ring.activeCount;
+}
+
+// What the direct bindings queue in place of the bare payload, which
+// follows the header. The core keeps a pointer to the submitted bytes
+// rather than a copy, so the frame also outlives the caller's buffer, and
+// it carries the type id and sender that the segment index can't. A frame
+// for an adopted buffer is just the header, pointing at `owned`.
+typedef struct {
+    uint32_t type_id;
+    language_type_t source_lang;
+    size_t size;
+    void* owned;
+    size_t capacity;
+    umsbb_release_fn release;
+} direct_frame_t;
+
+static void* direct_frame_payload(direct_frame_t* frame) {
+    return frame->owned ? frame->owned : (void*)(frame + 1);
+}
+
+// Free a frame, handing any adopted buffer back to its owner
+static void direct_frame_free(direct_frame_t* frame) {
+    if (frame->owned) {
+        frame->release(frame->owned, frame->size, frame->capacity);
+    }
+    free(frame);
+}
+
+// Queue `frame` on `segment` and account for it; the caller holds the lock.
+// On failure the frame is left to the caller.
+static bool direct_enqueue_frame(direct_bus_t* direct, uint32_t segment, direct_frame_t* frame, size_t frame_size) {
+    BiBuffer* target = &direct->bus->ring.buffers[segment];
+    size_t committed = atomic_load_size(&target->commitIndex);
+    if (!umsbb_submit_to(direct->bus, segment, (const char*)frame, frame_size)) {
+        return false;
+    }
+    
+    direct->total_messages++;
+    direct->total_bytes += frame->size;
+    if (atomic_load_size(&target->commitIndex) != committed) {
+        direct->pending[segment]++;
+        direct->pending_bytes[segment] += frame->size;
+    } else {
+        // Large messages the core ran on the GPU are accepted without being queued
+        direct_frame_free(frame);
+    }
+    return true;
+}
+
+// Copy a message into a frame and queue it; the caller holds the lock
+static bool direct_enqueue(direct_bus_t* direct, uint32_t segment, const universal_data_t* data) {
+    direct_frame_t* frame = malloc(sizeof(direct_frame_t) + data->size);
+    if (!frame) return false;
+    frame->type_id = data->type_id;
+    frame->source_lang = data->source_lang;
+    frame->size = data->size;
+    frame->owned = NULL;
+    if (data->size > 0) {
+        memcpy(frame + 1, data->data, data->size);
+    }
+    
+    if (!direct_enqueue_frame(direct, segment, frame, sizeof(direct_frame_t) + data->size)) {
+        free(frame);
+        return false;
+    }
+    return true;
+}
+
 // Direct language bindings (no API wrapper)
 void* umsbb_create_direct(size_t buffer_size, uint32_t segment_count, language_type_t lang) {
     // Initialize GPU if configured for GPU preference
-    if (current_scaling_config.gpu_preferred) {
+    if (get_scaling_config().gpu_preferred) {
         initialize_gpu();
     }
     
@@ -223,36 +318,69 @@ void* umsbb_create_direct(size_t buffer_size, uint32_t segment_count, language_t
         optimal_segments = get_optimal_producer_count() + get_optimal_consumer_count();
     }
     
-    UniversalMultiSegmentedBiBufferBus* bus = umsbb_init(buffer_size, optimal_segments);
-    if (!bus) return NULL;
+    direct_bus_t* direct = calloc(1, sizeof(direct_bus_t));
+    if (!direct) return NULL;
+    
+    direct->bus = umsbb_init(buffer_size, optimal_segments);
+    if (!direct->bus) {
+        free(direct);
+        return NULL;
+    }
+    pthread_mutex_init(&direct->lock, NULL);
     
     printf("[Direct] Created bus for %s with %u segments (%zu bytes each)\n",
            lang < 16 && runtime_initialized[lang] ? registered_runtimes[lang].lang_name : "Unknown",
            optimal_segments, buffer_size);
     
-    return bus;
+    return direct;
+}
+
+void* umsbb_create_direct_numa(size_t buffer_size, uint32_t segment_count, language_type_t lang, uint32_t numa_node) {
+#ifdef UMSBB_HAVE_LIBNUMA
+    if (numa_available() < 0 || numa_node > (uint32_t)numa_max_node()) {
+        return NULL;
+    }
+    
+    // Segments are allocated by this thread during the create, so prefer
+    // the node only for its duration
+    numa_set_preferred((int)numa_node);
+    void* handle = umsbb_create_direct(buffer_size, segment_count, lang);
+    numa_set_localalloc();
+    return handle;
+#else
+    (void)buffer_size;
+    (void)segment_count;
+    (void)lang;
+    (void)numa_node;
+    return NULL; // Built without libnuma
+#endif
 }
 
 bool umsbb_submit_direct(void* bus_handle, const universal_data_t* data) {
     if (!bus_handle || !data) return false;
     
-    UniversalMultiSegmentedBiBufferBus* bus = (UniversalMultiSegmentedBiBufferBus*)bus_handle;
+    return umsbb_submit_to_segment_direct(bus_handle, data->type_id, data);
+}
+
+bool umsbb_submit_to_segment_direct(void* bus_handle, uint32_t segment_id, const universal_data_t* data) {
+    if (!bus_handle || !data) return false;
+    
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
     
     // Try GPU execution for large data
     bool gpu_used = false;
-    if (current_scaling_config.gpu_preferred && data->size > 1024 * 1024) {
+    if (get_scaling_config().gpu_preferred && data->size > 1024 * 1024) {
         gpu_used = try_gpu_execute(data->data, data->size);
-        if (gpu_used) {
-            performance_stats.gpu_operations++;
-        }
     }
     
     // Submit to appropriate segment
-    uint32_t segment_id = data->type_id % bus->segment_count;
-    bool result = umsbb_submit_to(bus, segment_id, data->data, data->size);
+    pthread_mutex_lock(&direct->lock);
+    uint32_t segment = segment_id % direct_segment_count(direct);
+    bool result = direct_enqueue(direct, segment, data);
+    pthread_mutex_unlock(&direct->lock);
     
     if (result) {
-        performance_stats.total_operations++;
+        record_operation(gpu_used);
         // Update performance stats for auto-scaling
         trigger_scale_evaluation();
     }
@@ -260,24 +388,85 @@ bool umsbb_submit_direct(void* bus_handle, const universal_data_t* data) {
     return result;
 }
 
+bool umsbb_submit_owned_direct(void* bus_handle, uint32_t segment_id, void* data, size_t size,
+                               size_t capacity, uint32_t type_id, language_type_t lang,
+                               umsbb_release_fn release) {
+    if (!bus_handle || !data || !release) return false;
+    
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
+    direct_frame_t* frame = malloc(sizeof(direct_frame_t));
+    if (!frame) return false;
+    frame->type_id = type_id;
+    frame->source_lang = lang;
+    frame->size = size;
+    frame->owned = data;
+    frame->capacity = capacity;
+    frame->release = release;
+    
+    pthread_mutex_lock(&direct->lock);
+    uint32_t segment = segment_id % direct_segment_count(direct);
+    bool result = direct_enqueue_frame(direct, segment, frame, sizeof(direct_frame_t));
+    pthread_mutex_unlock(&direct->lock);
+    
+    if (!result) {
+        free(frame); // The buffer stays with the caller
+        return false;
+    }
+    record_operation(false);
+    trigger_scale_evaluation();
+    return true;
+}
+
 universal_data_t* umsbb_drain_direct(void* bus_handle, language_type_t target_lang) {
+    return umsbb_drain_direct_ex(bus_handle, target_lang, NULL);
+}
+
+universal_data_t* umsbb_drain_direct_ex(void* bus_handle, language_type_t target_lang, language_type_t* source_lang) {
     if (!bus_handle) return NULL;
     
-    UniversalMultiSegmentedBiBufferBus* bus = (UniversalMultiSegmentedBiBufferBus*)bus_handle;
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
     
     // Try draining from multiple segments
-    for (uint32_t i = 0; i < bus->segment_count; i++) {
-        size_t size;
-        void* data = umsbb_drain_from(bus, i, &size);
-        if (data && size > 0) {
-            // Create universal data structure
-            universal_data_t* udata = create_universal_data(data, size, i, target_lang);
-            free(data); // Free original data
+    pthread_mutex_lock(&direct->lock);
+    for (uint32_t i = 0; i < direct_segment_count(direct); i++) {
+        size_t peeked;
+        MessageCapsule* cap;
+        while ((cap = bi_buffer_read(&direct->bus->ring.buffers[i], &peeked)) != NULL) {
+            // The capsule points at the frame we queued; drain_from copies it
+            direct_frame_t* frame = (direct_frame_t*)cap->payload;
+            size_t size;
+            direct_frame_t* copy = umsbb_drain_from(direct->bus, i, &size);
+            direct->pending[i]--;
+            direct->pending_bytes[i] -= frame->size;
+            
+            if (!copy || size < sizeof(direct_frame_t)) {
+                // The core has already released a capsule it couldn't copy.
+                // An adopted buffer is leaked rather than released through
+                // a header that may be corrupt.
+                printf("[Direct] Discarding corrupted message on segment %u\n", i);
+                free(frame);
+                free(copy);
+                continue;
+            }
+            pthread_mutex_unlock(&direct->lock);
             
-            performance_stats.total_operations++;
+            // Create universal data structure; it belongs to the target's
+            // allocator, so the sender is reported separately
+            universal_data_t* udata = create_universal_data(direct_frame_payload(copy), copy->size, copy->type_id, target_lang);
+            if (udata && source_lang) {
+                *source_lang = copy->source_lang;
+            }
+            free(frame);
+            if (copy->owned) {
+                copy->release(copy->owned, copy->size, copy->capacity);
+            }
+            free(copy); // Free original data
+            
+            record_operation(false);
             return udata;
         }
     }
+    pthread_mutex_unlock(&direct->lock);
     
     return NULL;
 }
@@ -285,8 +474,64 @@ universal_data_t* umsbb_drain_direct(void* bus_handle, language_type_t target_la
 void umsbb_destroy_direct(void* bus_handle) {
     if (!bus_handle) return;
     
-    UniversalMultiSegmentedBiBufferBus* bus = (UniversalMultiSegmentedBiBufferBus*)bus_handle;
-    umsbb_free(bus);
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
+    
+    // Free frames still queued, handing adopted buffers back to their owners
+    for (uint32_t i = 0; i < direct_segment_count(direct); i++) {
+        BiBuffer* buf = &direct->bus->ring.buffers[i];
+        size_t peeked;
+        MessageCapsule* cap;
+        while ((cap = bi_buffer_read(buf, &peeked)) != NULL) {
+            direct_frame_free((direct_frame_t*)cap->payload);
+            bi_buffer_release(buf);
+        }
+    }
+    umsbb_free(direct->bus);
+    pthread_mutex_destroy(&direct->lock);
+    free(direct);
     
     printf("[Direct] Bus destroyed\n");
+}
+
+umsbb_direct_stats_t umsbb_get_stats_direct(void* bus_handle) {
+    umsbb_direct_stats_t stats = {0};
+    if (!bus_handle) return stats;
+    
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
+    
+    pthread_mutex_lock(&direct->lock);
+    stats.total_messages = direct->total_messages;
+    stats.total_bytes = direct->total_bytes;
+    for (uint32_t i = 0; i < direct_segment_count(direct); i++) {
+        stats.pending_messages += direct->pending[i];
+        if (direct->pending[i] > 0) {
+            stats.active_segments++;
+        }
+    }
+    pthread_mutex_unlock(&direct->lock);
+    
+    return stats;
+}
+
+uint32_t umsbb_abi_version(void) {
+    return UMSBB_ABI_VERSION;
+}
+
+size_t umsbb_segment_used_direct(void* bus_handle, uint32_t segment_id) {
+    if (!bus_handle) return 0;
+    
+    direct_bus_t* direct = (direct_bus_t*)bus_handle;
+    
+    pthread_mutex_lock(&direct->lock);
+    size_t used = direct->pending_bytes[segment_id % direct_segment_count(direct)];
+    pthread_mutex_unlock(&direct->lock);
+    
+    return used;
+}
+
+uint32_t umsbb_segment_count_direct(void* bus_handle) {
+    if (!bus_handle) return 0;
+    
+    // The ring is sized once at creation, so no lock is needed
+    return direct_segment_count((direct_bus_t*)bus_handle);
 }
\ No newline at end of file
@@ -391,6 +391,30 @@ WASM_EXPORT uint32_t umsbb_get_pending_messages(int buffer_id) {
     return buffer->stats.pending_messages;
 }
 
+// Zero the cumulative counters; pending and segment counts are left intact
+WASM_EXPORT int umsbb_reset_stats(int buffer_id) {
+    if (!g_system_initialized) {
+        return UMSBB_ERROR_SYSTEM_NOT_INITIALIZED;
+    }
+    if (buffer_id < 0 || buffer_id >= UMSBB_MAX_BUFFERS) {
+        return UMSBB_ERROR_INVALID_BUFFER;
+    }
+    
+    umsbb_buffer_t* buffer = g_buffers[buffer_id];
+    if (!buffer || !buffer->is_initialized) {
+        return UMSBB_ERROR_INVALID_BUFFER;
+    }
+    
+    buffer->stats.total_messages_written = 0;
+    buffer->stats.total_messages_read = 0;
+    buffer->stats.total_bytes_written = 0;
+    buffer->stats.total_bytes_read = 0;
+    buffer->stats.peak_pending_messages = buffer->stats.pending_messages;
+    buffer->stats.average_message_size = 0.0;
+    
+    return UMSBB_SUCCESS;
+}
+
 WASM_EXPORT const char* umsbb_get_version() {
     return "UMSBB WebAssembly Core v4.0";
 }